@@ -0,0 +1,90 @@
+//! Collecting hashes into SIMD-aligned storage.
+
+use crate::{KmerHasher, S, hash_kmers_into_ptr};
+use packed_seq::Seq;
+
+/// Two SIMD vectors, so that blocks are 64-byte (cache line) aligned.
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(64))]
+struct Block([S; 2]);
+
+/// K-mer hashes in order, stored in 64-byte aligned SIMD vectors.
+///
+/// The last vector is padded with zeros; [`AlignedHashes::len`] is the number of valid hashes.
+#[derive(Clone, Default)]
+pub struct AlignedHashes {
+    blocks: Vec<Block>,
+    len: usize,
+}
+
+impl AlignedHashes {
+    /// The number of valid hashes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The hashes as SIMD vectors. Lanes past [`AlignedHashes::len`] in the last vector are zero.
+    pub fn as_simd(&self) -> &[S] {
+        // SIMD vectors are stored contiguously, and `Block` adds no padding.
+        let simd = unsafe {
+            std::slice::from_raw_parts(self.blocks.as_ptr() as *const S, 2 * self.blocks.len())
+        };
+        &simd[..self.len.div_ceil(8)]
+    }
+
+    /// The valid hashes as a flat slice.
+    pub fn as_slice(&self) -> &[u32] {
+        unsafe { std::slice::from_raw_parts(self.blocks.as_ptr() as *const u32, self.len) }
+    }
+}
+
+/// Collect all k-mer hashes of `seq` into 64-byte aligned SIMD vectors, in order.
+pub fn hash_kmers_aligned<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>) -> AlignedHashes {
+    let n = (seq.len() + 1).saturating_sub(hasher.k());
+    let mut blocks = vec![Block::default(); n.div_ceil(16)];
+    // SAFETY: the blocks hold `16 * blocks.len() >= n` values.
+    let len = unsafe {
+        hash_kmers_into_ptr(
+            hasher,
+            seq,
+            blocks.as_mut_ptr() as *mut u32,
+            16 * blocks.len(),
+        )
+    };
+    debug_assert_eq!(len, n);
+    AlignedHashes { blocks, len }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn aligned_matches_scalar() {
+        let seq = PackedSeqVec::random(1000);
+        for len in [0, 1, 8, 20, 24, 1000] {
+            let seq = seq.slice(0..len);
+            for k in [1, 5, 21] {
+                let hasher = NtHasher::<true>::new(k);
+                let expected: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+                let aligned = hash_kmers_aligned(&hasher, seq);
+                assert_eq!(aligned.as_slice(), expected, "len={len} k={k}");
+                assert_eq!(aligned.as_simd().as_ptr() as usize % 64, 0);
+                let flat: Vec<u32> = aligned
+                    .as_simd()
+                    .iter()
+                    .flat_map(|s| s.to_array())
+                    .collect();
+                assert_eq!(flat.len(), expected.len().div_ceil(8) * 8);
+                assert_eq!(flat[..expected.len()], expected);
+                assert!(flat[expected.len()..].iter().all(|&x| x == 0));
+            }
+        }
+    }
+}
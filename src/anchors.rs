@@ -0,0 +1,193 @@
+//! Threshold-based k-mer sampling and shared k-mer anchors, as sparse seeds for alignment.
+
+use crate::intrinsics::append_filtered;
+use crate::{KmerHasher, NtHasher, PaddedLayout, S};
+use packed_seq::Seq;
+use std::collections::HashMap;
+use std::iter::zip;
+
+/// The `(pos, hash)` pairs of all k-mers in `seq` with hash at most `max`, ordered by position.
+///
+/// Each SIMD block of hashes is filtered as it is produced, using a vectorized comparison and compress-store,
+/// so the full hash stream is never materialized.
+fn hashes_at_most<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>, max: u32) -> Vec<(u32, u32)> {
+    let hashes = hasher.hash_kmers_simd(seq, 1);
+    let positions = hashes.lane_positions(1);
+    let m = S::splat(max);
+    let mut pos = vec![];
+    let mut vals = vec![];
+    for (h, (p, mask)) in hashes.it.zip(positions) {
+        let keep = mask & !m.simd_lt(h);
+        append_filtered(h, keep, &mut vals);
+        append_filtered(p, keep, &mut pos);
+    }
+    // Lanes cover distant parts of `seq`, so restore the order by position.
+    let mut sampled: Vec<_> = zip(pos, vals).collect();
+    sampled.sort_unstable_by_key(|&(p, _)| p);
+    sampled
+}
+
+/// All `(pos, hash)` pairs of k-mers in `seq` with hash below `threshold`, ordered by position.
+///
+/// With a canonical hasher, the same k-mers are sampled on both strands.
+/// A threshold of `t` samples a fraction of roughly `t / 2^32` of all k-mers.
+///
+/// Hashes are computed in parallel, and selected k-mers are extracted 8 at a time
+/// using a vectorized comparison and compress-store, without materializing all hashes.
+pub fn anchors<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    threshold: u32,
+) -> Vec<(usize, u32)> {
+    if threshold == 0 {
+        return vec![];
+    }
+    hashes_at_most(hasher, seq, threshold - 1)
+        .into_iter()
+        .map(|(p, h)| (p as usize, h))
+        .collect()
+}
+
+/// The positions of a uniform random fraction `p` of the k-mers of `seq`, in increasing order.
+///
+/// Selects the k-mers whose canonical [`NtHasher`] hash with the given `seed` is below `p * 2^32`,
+/// so the same k-mers are selected on both strands and across sequences.
+/// Useful for downsampling coverage. Filtering is vectorized and streaming, as in [`anchors`].
+pub fn sample_fraction<'s>(seq: impl Seq<'s>, k: usize, p: f64, seed: u32) -> Vec<u32> {
+    assert!((0.0..=1.0).contains(&p), "p must be in [0, 1].");
+    // Hashes `h < bound` are selected.
+    let bound = (p * (1u64 << 32) as f64).ceil() as u64;
+    if bound == 0 {
+        return vec![];
+    }
+    let hasher = NtHasher::<true>::new_with_seed(k, seed);
+    hashes_at_most(&hasher, seq, (bound - 1) as u32)
+        .into_iter()
+        .map(|(p, _)| p)
+        .collect()
+}
+
+/// All `(pos_a, pos_b)` pairs of positions where `a` and `b` share a k-mer hash, ordered by position.
+///
+/// With a canonical hasher, this also pairs k-mers with their reverse complement.
+/// K-mers occurring more than `max_occ` times in either sequence are skipped, to avoid a quadratic blowup on repeats.
+/// Builds a position index of the k-mers of `b`, and then streams the k-mers of `a`.
+pub fn shared_anchors<'s, 't, H: KmerHasher>(
+    hasher: &H,
+    a: impl Seq<'s>,
+    b: impl Seq<'t>,
+    max_occ: usize,
+) -> Vec<(usize, usize)> {
+    let mut index = HashMap::<u32, Vec<usize>>::new();
+    let hashes_b = hasher.hash_kmers_simd(b, 1).collect();
+    for (pos_b, h) in hashes_b.into_iter().enumerate() {
+        index.entry(h).or_default().push(pos_b);
+    }
+    let hashes_a = hasher.hash_kmers_simd(a, 1).collect();
+    let mut count_a = HashMap::<u32, usize>::new();
+    for &h in &hashes_a {
+        *count_a.entry(h).or_default() += 1;
+    }
+
+    let mut pairs = vec![];
+    for (pos_a, h) in hashes_a.into_iter().enumerate() {
+        let Some(positions) = index.get(&h) else {
+            continue;
+        };
+        if positions.len() > max_occ || count_a[&h] > max_occ {
+            continue;
+        }
+        pairs.extend(positions.iter().map(|&pos_b| (pos_a, pos_b)));
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn anchors_naive() {
+        let seq = PackedSeqVec::random(3000);
+        for len in [0, 5, 37, 3000] {
+            let seq = seq.slice(0..len);
+            for k in [1, 15, 31] {
+                let hasher = NtHasher::<true>::new(k);
+                for threshold in [0, u32::MAX / 10, u32::MAX] {
+                    let naive: Vec<(usize, u32)> = hasher
+                        .hash_kmers_scalar(seq)
+                        .enumerate()
+                        .filter(|&(_, h)| h < threshold)
+                        .collect();
+                    assert_eq!(anchors(&hasher, seq, threshold), naive, "len={len} k={k}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_fraction_naive() {
+        let seq = PackedSeqVec::random(10000);
+        let k = 21;
+        for p in [0.0, 1e-12, 0.01, 0.5, 1.0] {
+            let hasher = NtHasher::<true>::new_with_seed(k, 42);
+            let naive: Vec<u32> = hasher
+                .hash_kmers_scalar(seq.as_slice())
+                .enumerate()
+                .filter(|&(_, h)| (h as f64) < p * (1u64 << 32) as f64)
+                .map(|(i, _)| i as u32)
+                .collect();
+            assert_eq!(sample_fraction(seq.as_slice(), k, p, 42), naive, "p={p}");
+        }
+        assert_eq!(
+            sample_fraction(seq.as_slice(), k, 1.0, 42).len(),
+            10000 - (k - 1)
+        );
+        let n = sample_fraction(seq.as_slice(), k, 0.1, 42).len();
+        assert!((800..1200).contains(&n), "n={n}");
+        // Strand-symmetric selection.
+        let rc = seq.as_slice().to_revcomp();
+        let mut rc_pos: Vec<u32> = sample_fraction(rc.as_slice(), k, 0.1, 42)
+            .into_iter()
+            .map(|i| (10000 - k) as u32 - i)
+            .collect();
+        rc_pos.reverse();
+        assert_eq!(rc_pos, sample_fraction(seq.as_slice(), k, 0.1, 42));
+    }
+
+    #[test]
+    fn shared_anchors_naive() {
+        let k = 15;
+        let hasher = NtHasher::<true>::new(k);
+        let a = PackedSeqVec::random(1000);
+        // `b` shares a forward and a reverse-complement part with `a`, and contains a repeat.
+        let mut ascii = PackedSeqVec::random(200).as_slice().unpack();
+        ascii.extend(a.slice(100..300).unpack());
+        ascii.extend(a.slice(500..700).to_revcomp().as_slice().unpack());
+        for _ in 0..3 {
+            ascii.extend(a.slice(800..850).unpack());
+        }
+        let b = PackedSeqVec::from_ascii(&ascii);
+
+        let ha: Vec<u32> = hasher.hash_kmers_scalar(a.as_slice()).collect();
+        let hb: Vec<u32> = hasher.hash_kmers_scalar(b.as_slice()).collect();
+        for max_occ in [1, 2, 3, usize::MAX] {
+            let occ = |hs: &[u32], h: u32| hs.iter().filter(|&&x| x == h).count();
+            let mut naive = vec![];
+            for (i, &h) in ha.iter().enumerate() {
+                for (j, &h2) in hb.iter().enumerate() {
+                    if h == h2 && occ(&ha, h) <= max_occ && occ(&hb, h) <= max_occ {
+                        naive.push((i, j));
+                    }
+                }
+            }
+            let pairs = shared_anchors(&hasher, a.as_slice(), b.as_slice(), max_occ);
+            assert_eq!(pairs, naive, "max_occ={max_occ}");
+        }
+        let pairs = shared_anchors(&hasher, a.as_slice(), b.as_slice(), 1);
+        assert!(pairs.contains(&(100, 200)));
+        assert!(pairs.contains(&(500, 400 + 200 - k)));
+        assert!(!pairs.iter().any(|&(i, _)| (800..850 - k).contains(&i)));
+    }
+}
@@ -0,0 +1,147 @@
+//! Hashing many short reads of different lengths together in SIMD.
+
+use crate::{KmerHasher, S};
+use packed_seq::{PaddedIt, Seq};
+use std::array::from_fn;
+
+/// The k-mer hashes of each read in `reads`, computed 8 reads at a time in SIMD.
+///
+/// Each lane hashes the concatenation of the reads assigned to it, and hashes of k-mers spanning two reads are dropped.
+/// Reads are assigned greedily to the lane with the least work so far, so that lanes stay busy
+/// without sorting reads by length, and only the tails of the lanes are padded.
+/// This gives high lane occupancy for short reads, where [`KmerHasher::hash_kmers_simd`] per read would mostly pad.
+///
+/// Like [`Seq::par_iter_bp`], the lanes are bit-packed into interleaved `u32` words,
+/// so that each step shifts one character out of a SIMD register, and a new vector is loaded once per word.
+/// The output is transposed back to lanes with [`PaddedIt::collect`].
+///
+/// Returns the hashes of each read, in input order, equal to [`KmerHasher::hash_kmers_scalar`] per read.
+pub fn hash_reads_batched<'s, H: KmerHasher>(hasher: &H, reads: &[impl Seq<'s>]) -> Vec<Vec<u32>> {
+    let Some(&first) = reads.first() else {
+        return vec![];
+    };
+    let k = hasher.k();
+    let delay = hasher.delay().0;
+    let b = first.bits_per_char();
+    // Characters per `u32` word.
+    let cpw = 32 / b;
+
+    // For each lane, the `(read, start)` of each read in it.
+    let mut lane_lens = [0; 8];
+    let mut segments: [Vec<(usize, usize)>; 8] = from_fn(|_| vec![]);
+    for (i, read) in reads.iter().enumerate() {
+        assert_eq!(
+            read.bits_per_char(),
+            b,
+            "All reads must have the same alphabet."
+        );
+        let j = (0..8).min_by_key(|&j| lane_lens[j]).unwrap();
+        segments[j].push((i, lane_lens[j]));
+        lane_lens[j] += read.len();
+    }
+    let len = lane_lens.into_iter().max().unwrap();
+
+    // Word `w` holds characters `w*cpw..(w+1)*cpw` of each lane, with character `t` at bits `b*(t%cpw)..`.
+    let mut words = vec![[0u32; 8]; len.div_ceil(cpw)];
+    for j in 0..8 {
+        let mut write = |pos: usize, mut x: u64, mut bits: usize| {
+            let mut bit = pos * b;
+            while bits > 0 {
+                words[bit / 32][j] |= (x << (bit % 32)) as u32;
+                let taken = (32 - bit % 32).min(bits);
+                x >>= taken;
+                bit += taken;
+                bits -= taken;
+            }
+        };
+        for &(i, start) in &segments[j] {
+            let read = reads[i];
+            // Copy whole `u64`s of characters, and the tail one character at a time.
+            let full = read.len() / (64 / b) * (64 / b);
+            for p in (0..full).step_by(64 / b) {
+                write(start + p, read.slice(p..p + 64 / b).as_u64(), 64);
+            }
+            for (p, c) in read.slice(full..read.len()).iter_bp().enumerate() {
+                write(start + full + p, c as u64, b);
+            }
+        }
+    }
+
+    let mask = S::splat(u32::MAX >> (32 - b));
+    let mut mapper = hasher.in_out_mapper_simd(first);
+    let (mut add, mut remove) = (S::ZERO, S::ZERO);
+    let it = (0..len).map(|t| {
+        if t.is_multiple_of(cpw) {
+            add = S::new(words[t / cpw]);
+        }
+        let a = add & mask;
+        add = add >> b as u32;
+        let r = if t < delay {
+            S::ZERO
+        } else {
+            if (t - delay).is_multiple_of(cpw) {
+                remove = S::new(words[(t - delay) / cpw]);
+            }
+            let r = remove & mask;
+            remove = remove >> b as u32;
+            r
+        };
+        mapper((a, r))
+    });
+    // Lane `j` is at `out[j*len..(j+1)*len]`.
+    let out = PaddedIt { it, padding: 0 }.collect();
+
+    let mut hashes = vec![vec![]; reads.len()];
+    for (j, segments) in segments.iter().enumerate() {
+        for &(i, start) in segments {
+            let end = start + reads[i].len();
+            // The hash at position `t` is of the k-mer ending at `t`.
+            if start + k - 1 < end {
+                hashes[i] = out[j * len + start + k - 1..j * len + end].to_vec();
+            }
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AntiLexHasher, MulHasher, NtHasher, SipKmerHasher};
+    use packed_seq::{AsciiSeq, PackedSeqVec, SeqVec};
+
+    fn check<H: KmerHasher>(hasher: &H, reads: &[PackedSeqVec]) {
+        let slices: Vec<_> = reads.iter().map(|r| r.as_slice()).collect();
+        check_slices(hasher, &slices);
+    }
+
+    fn check_slices<'s, H: KmerHasher>(hasher: &H, slices: &[impl Seq<'s>]) {
+        let batched = hash_reads_batched(hasher, slices);
+        assert_eq!(batched.len(), slices.len());
+        for (read, hashes) in std::iter::zip(slices, batched) {
+            assert_eq!(hashes, hasher.hash_kmers_scalar(*read).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn ragged_reads() {
+        let reads: Vec<PackedSeqVec> = (0..100)
+            .map(|_| PackedSeqVec::random(rand::random_range(0..300)))
+            .collect();
+        for k in [1, 5, 21, 31] {
+            check(&NtHasher::<true>::new(k), &reads);
+            check(&AntiLexHasher::<false>::new(k), &reads);
+            check(&<SipKmerHasher>::new(k), &reads);
+        }
+        // Other alphabets are packed with a different number of characters per word.
+        let ascii: Vec<Vec<u8>> = reads.iter().map(|r| r.as_slice().unpack()).collect();
+        let ascii_seqs: Vec<_> = ascii.iter().map(|r| AsciiSeq(r)).collect();
+        let bytes: Vec<_> = ascii.iter().map(|r| r.as_slice()).collect();
+        for k in [1, 21] {
+            check_slices(&NtHasher::<true>::new(k), &ascii_seqs);
+            check_slices(&MulHasher::<false>::new(k), &bytes);
+        }
+        check(&NtHasher::<true>::new(21), &reads[..3]);
+        check(&NtHasher::<true>::new(21), &reads[..0]);
+    }
+}
@@ -0,0 +1,162 @@
+//! Hashing texts from the [`bio`] crate, choosing a sequence representation from their [`Alphabet`].
+
+use crate::{DEFAULT_CHUNK_LEN, HashSeqExt, KmerHasher};
+use bio::alphabets::{Alphabet, dna};
+use bio::utils::TextSlice;
+use packed_seq::{AsciiSeq, PackedNSeqVec};
+
+/// A rust-bio text together with the sequence representation used to hash it.
+///
+/// The representation is chosen from the alphabet of the text by [`BioText::new`]:
+/// - texts over [`dna::alphabet`] are hashed directly as an [`AsciiSeq`], without any conversion;
+/// - texts over [`dna::iupac_alphabet`] (including `N`) are packed into a [`PackedNSeqVec`]
+///   one chunk of [`DEFAULT_CHUNK_LEN`] k-mers at a time while hashing,
+///   so that k-mers containing other characters than `ACGT` are marked ambiguous
+///   without holding a packed copy of the whole text;
+/// - other texts, e.g. protein, are hashed as raw bytes, which requires a hasher supporting 8-bit characters,
+///   such as [`crate::MulHasher`].
+///
+/// Hash via [`HashSeqExt`], as in `(&text).kmer_hashes(&hasher)`.
+///
+/// Requires the `bio` feature.
+#[derive(Clone, Debug)]
+pub enum BioText<'a> {
+    /// Text over `ACGTacgt`.
+    Dna(AsciiSeq<'a>),
+    /// DNA text containing ambiguous characters.
+    DnaAmbiguous(&'a [u8]),
+    /// Any other text.
+    Bytes(&'a [u8]),
+}
+
+impl<'a> BioText<'a> {
+    /// The representation of `text`, whose characters are all in `alphabet`.
+    pub fn new(text: TextSlice<'a>, alphabet: &Alphabet) -> Self {
+        debug_assert!(
+            alphabet.is_word(text),
+            "Text contains characters outside the alphabet."
+        );
+        if alphabet.difference(&dna::alphabet()).is_empty() {
+            BioText::Dna(AsciiSeq(text))
+        } else if alphabet.difference(&dna::iupac_alphabet()).is_empty() {
+            BioText::DnaAmbiguous(text)
+        } else {
+            BioText::Bytes(text)
+        }
+    }
+
+    /// The representation of a DNA `text`, inferring its alphabet from its characters.
+    pub fn dna(text: TextSlice<'a>) -> Self {
+        Self::new(text, &Alphabet::new(text))
+    }
+}
+
+impl HashSeqExt for &BioText<'_> {
+    /// All k-mer hashes of the text, ignoring ambiguity.
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        match self {
+            BioText::Dna(seq) => seq.kmer_hashes(hasher),
+            BioText::DnaAmbiguous(text) => hash_ambiguous(text, hasher, false),
+            BioText::Bytes(text) => text.kmer_hashes(hasher),
+        }
+    }
+
+    fn valid_kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        match self {
+            BioText::DnaAmbiguous(text) => hash_ambiguous(text, hasher, true),
+            _ => self.kmer_hashes(hasher),
+        }
+    }
+}
+
+/// Hash the k-mers of an ambiguous DNA `text`, packing it one chunk of [`DEFAULT_CHUNK_LEN`] k-mers at a time.
+///
+/// Chunks overlap by `k-1` characters. With `valid`, k-mers containing ambiguous characters get hash `u32::MAX`.
+fn hash_ambiguous(text: &[u8], hasher: &impl KmerHasher, valid: bool) -> Vec<u32> {
+    let k = hasher.k();
+    let num_kmers = (text.len() + 1).saturating_sub(k);
+    let mut out = Vec::with_capacity(num_kmers);
+    let mut nseq = PackedNSeqVec::default();
+    let mut buf = vec![];
+    for start in (0..num_kmers).step_by(DEFAULT_CHUNK_LEN) {
+        let end = (start + DEFAULT_CHUNK_LEN).min(num_kmers);
+        nseq.clear();
+        nseq.push_ascii(&text[start..end + k - 1]);
+        if valid {
+            hasher
+                .hash_valid_kmers_simd(nseq.as_slice(), 1)
+                .collect_into(&mut buf);
+        } else {
+            hasher
+                .hash_kmers_simd(nseq.as_slice().seq, 1)
+                .collect_into(&mut buf);
+        }
+        out.extend_from_slice(&buf);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MulHasher, NtHasher};
+    use bio::alphabets::protein;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn representations() {
+        let hasher = <NtHasher>::new(5);
+        let text = b"ACGTTGCAacgtagctAGCTGATCGATCGTAGC";
+        let expected = PackedSeqVec::from_ascii(text)
+            .as_slice()
+            .kmer_hashes(&hasher);
+
+        let dna = BioText::new(text, &dna::alphabet());
+        assert!(matches!(dna, BioText::Dna(_)));
+        assert_eq!((&dna).kmer_hashes(&hasher), expected);
+        assert_eq!((&dna).valid_kmer_hashes(&hasher), expected);
+
+        let n = BioText::new(text, &dna::n_alphabet());
+        assert!(matches!(n, BioText::DnaAmbiguous(_)));
+        assert_eq!((&n).valid_kmer_hashes(&hasher), expected);
+
+        let mut ambiguous = text.to_vec();
+        ambiguous[10] = b'N';
+        let n = BioText::dna(&ambiguous);
+        let hashes = (&n).valid_kmer_hashes(&hasher);
+        assert_eq!(hashes[5], expected[5]);
+        assert!(hashes[6..=10].iter().all(|&h| h == u32::MAX));
+        assert_eq!(hashes[11..], expected[11..]);
+
+        // Ambiguous texts are packed in chunks; hashes must not change across chunk boundaries.
+        let long: Vec<u8> = (0..2 * DEFAULT_CHUNK_LEN + 100)
+            .map(|i| {
+                if i % 1000 == 999 {
+                    b'N'
+                } else {
+                    b"ACGT"[i * 7 % 11 % 4]
+                }
+            })
+            .collect();
+        let packed = PackedNSeqVec::from_ascii(&long);
+        let n = BioText::dna(&long);
+        assert!(matches!(n, BioText::DnaAmbiguous(_)));
+        assert_eq!(
+            (&n).kmer_hashes(&hasher),
+            packed.as_slice().kmer_hashes(&hasher)
+        );
+        assert_eq!(
+            (&n).valid_kmer_hashes(&hasher),
+            packed.as_slice().valid_kmer_hashes(&hasher)
+        );
+
+        let text = b"MKVLAAGIVGLLLAW";
+        let protein = BioText::new(text, &protein::alphabet());
+        assert!(matches!(protein, BioText::Bytes(_)));
+        let hasher = <MulHasher>::new(5);
+        assert_eq!(
+            (&protein).kmer_hashes(&hasher),
+            text.as_slice().kmer_hashes(&hasher)
+        );
+    }
+}
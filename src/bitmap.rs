@@ -0,0 +1,77 @@
+//! Roaring bitmaps of k-mer hashes, for set operations on k-mer sets.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+use roaring::RoaringBitmap;
+
+/// The set of k-mer hashes of `seq`, as a [`RoaringBitmap`].
+///
+/// Use a canonical hasher (e.g. the default [`crate::NtHasher`]) to identify k-mers on either strand,
+/// and the same hasher for all sets that are compared.
+pub fn collect_roaring<'s>(hasher: &impl KmerHasher, seq: impl Seq<'s>) -> RoaringBitmap {
+    hasher
+        .hash_kmers_simd(seq, 1)
+        .collect()
+        .into_iter()
+        .collect()
+}
+
+/// The hashes present in all `sets`, or the empty set when there are none.
+pub fn intersect_roaring<'a>(sets: impl IntoIterator<Item = &'a RoaringBitmap>) -> RoaringBitmap {
+    let mut sets = sets.into_iter();
+    let Some(first) = sets.next() else {
+        return RoaringBitmap::new();
+    };
+    sets.fold(first.clone(), |acc, set| acc & set)
+}
+
+/// The hashes present in any of `sets`.
+pub fn union_roaring<'a>(sets: impl IntoIterator<Item = &'a RoaringBitmap>) -> RoaringBitmap {
+    sets.into_iter()
+        .fold(RoaringBitmap::new(), |acc, set| acc | set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::HashSet;
+
+    #[test]
+    fn set_operations() {
+        let hasher = <NtHasher>::new(21);
+        let shared = PackedSeqVec::random(1000);
+        let mut seqs = vec![];
+        for _ in 0..3 {
+            let mut seq = PackedSeqVec::random(500);
+            seq.push_seq(shared.as_slice());
+            seqs.push(seq);
+        }
+        let sets: Vec<RoaringBitmap> = seqs
+            .iter()
+            .map(|s| collect_roaring(&hasher, s.as_slice()))
+            .collect();
+        for (seq, set) in seqs.iter().zip(&sets) {
+            let hashes: HashSet<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+            assert_eq!(set.iter().collect::<HashSet<u32>>(), hashes);
+        }
+
+        let both = intersect_roaring(&sets);
+        let all = union_roaring(&sets);
+        let mut expected_both = sets[0].iter().collect::<HashSet<u32>>();
+        let mut expected_all = HashSet::new();
+        for set in &sets {
+            expected_both.retain(|h| set.contains(*h));
+            expected_all.extend(set);
+        }
+        assert_eq!(both.iter().collect::<HashSet<u32>>(), expected_both);
+        assert_eq!(all.iter().collect::<HashSet<u32>>(), expected_all);
+
+        // The shared k-mers are found, also on the reverse strand.
+        let rc = collect_roaring(&hasher, shared.as_slice().to_revcomp().as_slice());
+        assert!(rc.is_subset(&both));
+        assert!(intersect_roaring([]).is_empty());
+        assert!(union_roaring([]).is_empty());
+    }
+}
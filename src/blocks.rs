@@ -0,0 +1,94 @@
+//! Yielding k-mer hashes in fixed-size blocks.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+
+/// Yields the k-mer hashes of a sequence in consecutive blocks of a fixed size, using a reused buffer.
+///
+/// Each block is hashed separately in SIMD, so only a single block is in memory at a time.
+/// All blocks have exactly `block_size` hashes, except possibly the last one.
+///
+/// ```
+/// use seq_hash::{HashBlocks, KmerHasher, NtHasher};
+/// use seq_hash::packed_seq::{PackedSeqVec, SeqVec};
+/// let seq = PackedSeqVec::random(10000);
+/// let hasher = <NtHasher>::new(21);
+/// let mut blocks = HashBlocks::new(&hasher, seq.as_slice(), 4096);
+/// let mut num_hashes = 0;
+/// while let Some(block) = blocks.next_block() {
+///     assert!(block.len() <= 4096);
+///     num_hashes += block.len();
+/// }
+/// assert_eq!(num_hashes, 10000 - 20);
+/// ```
+pub struct HashBlocks<'h, H: KmerHasher, Q> {
+    hasher: &'h H,
+    seq: Q,
+    block_size: usize,
+    /// Position of the first k-mer of the next block.
+    pos: usize,
+    buf: Vec<u32>,
+}
+
+impl<'h, 's, H: KmerHasher, Q: Seq<'s>> HashBlocks<'h, H, Q> {
+    pub fn new(hasher: &'h H, seq: Q, block_size: usize) -> Self {
+        assert!(block_size > 0, "Block size must be positive.");
+        Self {
+            hasher,
+            seq,
+            block_size,
+            pos: 0,
+            buf: Vec::with_capacity(block_size),
+        }
+    }
+
+    /// The position of the first k-mer of the next block.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The hashes of the next block of k-mers, or `None` when all k-mers have been hashed.
+    pub fn next_block(&mut self) -> Option<&[u32]> {
+        let k = self.hasher.k();
+        let num_kmers = (self.seq.len() + 1).saturating_sub(k);
+        if self.pos >= num_kmers {
+            return None;
+        }
+        let end = (self.pos + self.block_size).min(num_kmers);
+        self.buf.clear();
+        self.hasher
+            .hash_kmers_simd(self.seq.slice(self.pos..end + k - 1), 1)
+            .collect_into(&mut self.buf);
+        self.pos = end;
+        Some(&self.buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn blocks_match_full() {
+        let seq = PackedSeqVec::random(5000);
+        for len in [0, 10, 100, 5000] {
+            let seq = seq.slice(0..len);
+            for k in [1, 21, 64] {
+                let hasher = NtHasher::<true>::new(k);
+                let expected: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+                for block_size in [1, 7, 64, 4096] {
+                    let mut blocks = HashBlocks::new(&hasher, seq, block_size);
+                    let mut hashes = vec![];
+                    while let Some(block) = blocks.next_block() {
+                        let done = hashes.len() + block.len() == expected.len();
+                        assert!(block.len() == block_size || done);
+                        hashes.extend_from_slice(block);
+                    }
+                    assert_eq!(hashes, expected, "len={len} k={k} block_size={block_size}");
+                }
+            }
+        }
+    }
+}
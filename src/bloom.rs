@@ -0,0 +1,149 @@
+//! A Bloom filter over 32-bit k-mer hashes, with SIMD lookups.
+
+use crate::S;
+use crate::intrinsics::gather_slice;
+
+/// Odd multiplier deriving the second probe hash from the first.
+const C: u32 = 0x9E37_79B1;
+
+/// A Bloom filter over `u32` hashes, e.g. k-mer hashes from a [`crate::KmerHasher`].
+///
+/// Probe `i` of hash `h` is bit `h + i*h2` for a second hash `h2` derived from `h` (double hashing),
+/// so no rehashing of the k-mer itself is needed.
+/// The number of bits is a power of two.
+#[derive(Clone, Debug)]
+pub struct BloomFilter {
+    words: Vec<u32>,
+    /// `num_bits - 1`.
+    mask: u32,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// A filter with at least `num_bits` bits (rounded up to a power of two, at least 32) and `num_hashes` probes per item.
+    pub fn new(num_bits: usize, num_hashes: u32) -> Self {
+        assert!(num_hashes > 0, "Number of hashes must be positive.");
+        let num_bits = num_bits.max(32).next_power_of_two();
+        assert!(num_bits <= 1 << 32, "At most 2^32 bits are supported.");
+        Self {
+            words: vec![0; num_bits / 32],
+            mask: (num_bits - 1) as u32,
+            num_hashes,
+        }
+    }
+
+    /// A filter for about `n` items using about `bits_per_item` bits each, with the optimal number of probes.
+    ///
+    /// E.g. 10 bits per item give a false-positive rate of around 1%.
+    pub fn with_capacity(n: usize, bits_per_item: usize) -> Self {
+        let num_hashes = (bits_per_item as f64 * std::f64::consts::LN_2).round() as u32;
+        Self::new(n * bits_per_item, num_hashes.clamp(1, 16))
+    }
+
+    /// The number of bits.
+    pub fn num_bits(&self) -> usize {
+        self.mask as usize + 1
+    }
+
+    /// The number of probes per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// The fraction of bits that are set.
+    pub fn load(&self) -> f64 {
+        let ones: u32 = self.words.iter().map(|w| w.count_ones()).sum();
+        ones as f64 / self.num_bits() as f64
+    }
+
+    #[inline(always)]
+    fn probe_step(h: u32) -> u32 {
+        h.rotate_left(16).wrapping_mul(C) | 1
+    }
+
+    /// Insert hash `h`.
+    #[inline(always)]
+    pub fn insert(&mut self, h: u32) {
+        let step = Self::probe_step(h);
+        let mut b = h;
+        for _ in 0..self.num_hashes {
+            let i = b & self.mask;
+            self.words[(i >> 5) as usize] |= 1 << (i & 31);
+            b = b.wrapping_add(step);
+        }
+    }
+
+    /// Whether hash `h` may have been inserted. There are no false negatives.
+    #[inline(always)]
+    pub fn contains(&self, h: u32) -> bool {
+        let step = Self::probe_step(h);
+        let mut b = h;
+        for _ in 0..self.num_hashes {
+            let i = b & self.mask;
+            if self.words[(i >> 5) as usize] & (1 << (i & 31)) == 0 {
+                return false;
+            }
+            b = b.wrapping_add(step);
+        }
+        true
+    }
+
+    /// SIMD version of [`Self::contains`], returning all ones in the lanes that may have been inserted.
+    #[inline(always)]
+    pub fn contains_simd(&self, h: S) -> S {
+        let step = (((h << 16) | (h >> 16)) * S::splat(C)) | S::ONE;
+        let mask = S::splat(self.mask);
+        let mut b = h;
+        let mut found = S::MAX;
+        for _ in 0..self.num_hashes {
+            let i = b & mask;
+            // SAFETY: `i >> 5` is less than `num_bits / 32 = words.len()`.
+            let w = unsafe { gather_slice(&self.words, i >> 5) };
+            found &= ((w >> (i & S::splat(31))) & S::ONE).simd_eq(S::ONE);
+            b += step;
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_false_negatives() {
+        let mut bloom = BloomFilter::with_capacity(10000, 10);
+        assert_eq!(bloom.num_hashes(), 7);
+        let items: Vec<u32> = (0..10000).map(|_| rand::random()).collect();
+        for &h in &items {
+            bloom.insert(h);
+        }
+        assert!(items.iter().all(|&h| bloom.contains(h)));
+        for chunk in items.chunks_exact(8) {
+            let found = bloom.contains_simd(S::new(chunk.try_into().unwrap()));
+            assert_eq!(found, S::MAX);
+        }
+    }
+
+    #[test]
+    fn false_positive_rate() {
+        let mut bloom = BloomFilter::with_capacity(10000, 10);
+        for _ in 0..10000 {
+            bloom.insert(rand::random());
+        }
+        let queries: Vec<u32> = (0..80000).map(|_| rand::random()).collect();
+        let fp = queries.iter().filter(|&&h| bloom.contains(h)).count();
+        assert!(fp < 80000 / 50, "fp={fp}");
+        for chunk in queries.chunks_exact(8) {
+            let found = bloom.contains_simd(S::new(chunk.try_into().unwrap()));
+            let expected = S::new(std::array::from_fn(|j| {
+                if bloom.contains(chunk[j]) {
+                    u32::MAX
+                } else {
+                    0
+                }
+            }));
+            assert_eq!(found, expected);
+        }
+    }
+}
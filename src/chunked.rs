@@ -0,0 +1,102 @@
+//! Hashing a sequence that arrives in arbitrary pieces.
+
+use crate::KmerHasher;
+use packed_seq::AsciiSeq;
+
+/// Hashes an ASCII DNA sequence that is fed in arbitrary pieces, e.g. as it is read or decompressed.
+///
+/// The last `k-1` characters of each piece are retained, so that k-mers spanning piece boundaries are hashed as well,
+/// and the concatenated output equals the hashes of the full sequence.
+///
+/// ```
+/// use seq_hash::{ChunkedHasher, HashSeqExt, NtHasher, KmerHasher, packed_seq::AsciiSeq};
+/// let seq = b"ACGTTGCAGGTACGGAT";
+/// let mut chunked = ChunkedHasher::new(<NtHasher>::new(5));
+/// let mut hashes = vec![];
+/// for piece in seq.chunks(3) {
+///     hashes.extend_from_slice(chunked.feed(piece));
+/// }
+/// assert_eq!(hashes, AsciiSeq(seq).kmer_hashes(chunked.hasher()));
+/// ```
+pub struct ChunkedHasher<H: KmerHasher> {
+    hasher: H,
+    /// The last `k-1` characters seen so far, followed by the current piece.
+    buf: Vec<u8>,
+    hashes: Vec<u32>,
+    /// The number of k-mers hashed so far.
+    pos: usize,
+}
+
+impl<H: KmerHasher> ChunkedHasher<H> {
+    pub fn new(hasher: H) -> Self {
+        Self {
+            hasher,
+            buf: vec![],
+            hashes: vec![],
+            pos: 0,
+        }
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// The number of k-mers hashed so far, i.e., the position of the next k-mer in the full sequence.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Append a piece of the sequence, and return the hashes of all k-mers that end in it.
+    ///
+    /// The returned buffer is reused by the next call.
+    pub fn feed(&mut self, piece: &[u8]) -> &[u32] {
+        self.buf.extend_from_slice(piece);
+        self.hashes.clear();
+        if self.buf.len() >= self.hasher.k() {
+            self.hasher
+                .hash_kmers_simd(AsciiSeq(&self.buf), 1)
+                .collect_into(&mut self.hashes);
+        }
+        let keep = self.buf.len().min(self.hasher.k() - 1);
+        self.buf.drain(..self.buf.len() - keep);
+        self.pos += self.hashes.len();
+        &self.hashes
+    }
+
+    /// Start a new sequence.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.hashes.clear();
+        self.pos = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+
+    #[test]
+    fn pieces_match_full() {
+        let seq: Vec<u8> = (0..3000)
+            .map(|_| b"ACGT"[rand::random_range(0..4)])
+            .collect();
+        for k in [1, 5, 31, 64] {
+            let hasher = NtHasher::<true>::new(k);
+            let expected: Vec<u32> = hasher.hash_kmers_scalar(AsciiSeq(&seq)).collect();
+            let mut chunked = ChunkedHasher::new(hasher);
+            for _ in 0..2 {
+                let mut hashes = vec![];
+                let mut start = 0;
+                while start < seq.len() {
+                    let end = (start + rand::random_range(0..100)).min(seq.len());
+                    hashes.extend_from_slice(chunked.feed(&seq[start..end]));
+                    assert_eq!(chunked.position(), hashes.len());
+                    start = end;
+                }
+                assert_eq!(hashes, expected, "k={k}");
+                chunked.reset();
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Colexicographic k-mer codes.
+
+use crate::{KmerHasher, S};
+use packed_seq::Seq;
+
+/// A 'hasher' returning the colexicographic rank of each k-mer, for `k <= 16`.
+///
+/// Characters are ranked in `ACGT` order, and the last character of the k-mer is the most significant.
+/// That is, k-mer `c_0 .. c_{k-1}` maps to `sum_i rank(c_i) * 4^i`,
+/// so that sorting by hash sorts k-mers by their reversed string.
+/// This is useful for succinct data structures that index k-mers in colex order.
+///
+/// Only supports 2-bit DNA sequences ([`packed_seq::AsciiSeq`] and [`packed_seq::PackedSeq`]).
+pub struct ColexHasher {
+    k: usize,
+    /// Number of bits to shift each new character up to make it the most significant one.
+    shift: u32,
+}
+
+impl ColexHasher {
+    /// Create a new [`ColexHasher`] for kmers of length `k <= 16`.
+    #[inline(always)]
+    pub const fn new(k: usize) -> Self {
+        assert!(0 < k && k <= 16, "ColexHasher requires k <= 16.");
+        Self {
+            k,
+            shift: 2 * (k as u32 - 1),
+        }
+    }
+}
+
+/// Map the packed `ACTG` encoding to `ACGT` rank.
+#[inline(always)]
+fn rank(a: u32) -> u32 {
+    a ^ (a >> 1)
+}
+
+impl KmerHasher for ColexHasher {
+    const CANONICAL: bool = false;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        2
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+
+        let mut fw: u32 = 0;
+        move |(a, _r)| {
+            fw = (fw >> 2) | (rank(a as u32) << self.shift);
+            fw
+        }
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+
+        let mut fw: S = S::splat(0);
+        move |(a, _r)| {
+            fw = (fw >> 2) | ((a ^ (a >> 1)) << self.shift);
+            fw
+        }
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        assert!(seq.len() <= 16, "ColexHasher requires k <= 16.");
+        let shift = 2 * (seq.len().max(1) as u32 - 1);
+
+        let mut fw: u32 = 0;
+        move |a| {
+            fw = (fw >> 2) | (rank(a as u32) << shift);
+            fw
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{AsciiSeq, PackedSeqVec, SeqVec};
+
+    #[test]
+    fn colex_order() {
+        let ascii: Vec<u8> = (0..2000)
+            .map(|_| b"ACGT"[rand::random_range(0..4)])
+            .collect();
+        let packed = PackedSeqVec::from_ascii(&ascii);
+        for k in [1, 2, 5, 15, 16] {
+            let hasher = ColexHasher::new(k);
+            let hashes: Vec<u32> = hasher.hash_kmers_scalar(packed.as_slice()).collect();
+            assert_eq!(
+                hasher.hash_kmers_simd(packed.as_slice(), 1).collect(),
+                hashes
+            );
+            assert_eq!(
+                hasher
+                    .hash_kmers_scalar(AsciiSeq(&ascii))
+                    .collect::<Vec<_>>(),
+                hashes
+            );
+            for (i, w) in ascii.windows(k).enumerate() {
+                let naive = w.iter().rev().fold(0, |x, &c| {
+                    4 * x + b"ACGT".iter().position(|&b| b == c).unwrap() as u32
+                });
+                assert_eq!(hashes[i], naive, "k={k}");
+                assert_eq!(hasher.hash_seq(packed.as_slice().slice(i..i + k)), naive);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Auditing hash collisions between distinct k-mers.
+
+use crate::KmerHasher;
+use packed_seq::{Seq, complement_base};
+use std::collections::HashMap;
+
+/// The result of [`audit_collisions`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CollisionReport {
+    /// The number of k-mers in the sequence.
+    pub num_kmers: usize,
+    /// The number of distinct k-mers (up to reverse complement for canonical hashers).
+    pub num_distinct: usize,
+    /// The number of pairs of distinct k-mers with the same hash.
+    pub num_colliding_pairs: usize,
+    /// Up to `limit` colliding pairs, as `(pos1, pos2, hash)` with `pos1 < pos2`
+    /// the first occurrences of the two k-mers.
+    pub examples: Vec<(usize, usize, u32)>,
+}
+
+/// Hash all k-mers of `seq` and count collisions between distinct k-mers, reporting up to `limit` example pairs.
+///
+/// For canonical hashers (see [`KmerHasher::is_canonical`]), a k-mer and its reverse complement are considered equal.
+/// This is meant for debugging e.g. unexpected duplicate minimizers, and takes memory linear in the number of distinct k-mers.
+pub fn audit_collisions<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    limit: usize,
+) -> CollisionReport {
+    let k = hasher.k();
+    let canonical = hasher.is_canonical();
+    let same = |i: usize, j: usize| {
+        (0..k).all(|x| seq.get(i + x) == seq.get(j + x))
+            || canonical
+                && (0..k).all(|x| seq.get(i + x) == complement_base(seq.get(j + k - 1 - x)))
+    };
+
+    let hashes: Vec<u32> = hasher.hash_kmers_simd(seq, 1).collect();
+    let mut report = CollisionReport {
+        num_kmers: hashes.len(),
+        ..Default::default()
+    };
+    // For each hash, the first occurrences of the distinct k-mers with that hash.
+    let mut reps = HashMap::<u32, Vec<usize>>::new();
+    for (i, &h) in hashes.iter().enumerate() {
+        let reps = reps.entry(h).or_default();
+        if reps.iter().any(|&j| same(i, j)) {
+            continue;
+        }
+        report.num_distinct += 1;
+        report.num_colliding_pairs += reps.len();
+        for &j in reps.iter() {
+            if report.examples.len() < limit {
+                report.examples.push((j, i, h));
+            }
+        }
+        reps.push(i);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AntiLexHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn random_has_no_collisions() {
+        let mut seq = PackedSeqVec::random(2000).as_slice().unpack();
+        // Repeat a part, including as reverse complement.
+        let part = seq[100..200].to_vec();
+        seq.extend_from_slice(&part);
+        seq.extend(part.iter().rev().map(|&c| packed_seq::complement_char(c)));
+        let seq = PackedSeqVec::from_ascii(&seq);
+        let hasher = NtHasher::<true>::new(21);
+        let report = audit_collisions(&hasher, seq.as_slice(), 10);
+        assert_eq!(report.num_kmers, 2200 - 20);
+        let distinct: std::collections::HashSet<u64> =
+            crate::canonical_kmer_codes(seq.as_slice(), 21).collect();
+        assert_eq!(report.num_distinct, distinct.len());
+        assert_eq!(report.num_colliding_pairs, 0);
+        assert!(report.examples.is_empty());
+    }
+
+    #[test]
+    fn truncating_hasher_collides() {
+        // Anti-lex only uses the last 16 characters, so these three 20-mers collide.
+        let suffix = b"ACGTTGCAACGTGGCA";
+        let mut ascii = vec![];
+        for prefix in [b"AAAA", b"CCCC", b"AAAA", b"GGGG"] {
+            ascii.extend_from_slice(prefix);
+            ascii.extend_from_slice(suffix);
+        }
+        let seq = PackedSeqVec::from_ascii(&ascii);
+        let hasher = AntiLexHasher::<false>::new(20);
+        let report = audit_collisions(&hasher, seq.as_slice(), 2);
+        assert_eq!(report.num_colliding_pairs, 3);
+        assert_eq!(report.examples.len(), 2);
+        assert_eq!(report.examples[0], (0, 20, report.examples[0].2));
+    }
+}
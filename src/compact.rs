@@ -0,0 +1,161 @@
+//! Compact per-k-mer words combining a truncated hash with validity and strand flags.
+
+use crate::kmers::{code_mapper, strand_mapper_simd};
+use crate::{KmerHasher, S};
+use packed_seq::{ChunkIt, PackedNSeq, PaddedIt, Seq};
+use std::iter::zip;
+
+/// A k-mer hash truncated to 30 bits, with a validity bit and a strand bit, packed in a single `u32`.
+///
+/// Bits `0..30` hold the low bits of the hash, bit 30 is set for k-mers without ambiguous bases,
+/// and bit 31 is set when the forward code of the k-mer is at most its reverse-complement code,
+/// i.e. when it is in canonical orientation (see [`crate::strand_flags`]).
+/// The strand bit of a k-mer and its reverse complement differ, unless the k-mer is its own reverse complement.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct CompactHash(pub u32);
+
+impl CompactHash {
+    /// Mask of the hash bits.
+    pub const HASH_MASK: u32 = (1 << 30) - 1;
+    /// The validity bit.
+    pub const VALID: u32 = 1 << 30;
+    /// The strand bit.
+    pub const FORWARD: u32 = 1 << 31;
+
+    /// Pack the low 30 bits of `hash` with the validity and strand bits.
+    #[inline(always)]
+    pub fn new(hash: u32, valid: bool, forward: bool) -> Self {
+        Self(
+            (hash & Self::HASH_MASK)
+                | if valid { Self::VALID } else { 0 }
+                | if forward { Self::FORWARD } else { 0 },
+        )
+    }
+
+    /// View the words collected from [`hash_kmers_compact_simd`] as [`CompactHash`]es, without copying.
+    #[inline(always)]
+    pub fn from_words(words: &[u32]) -> &[CompactHash] {
+        // SAFETY: `CompactHash` is a `repr(transparent)` wrapper around `u32`.
+        unsafe { std::slice::from_raw_parts(words.as_ptr().cast(), words.len()) }
+    }
+
+    /// The hash, truncated to 30 bits.
+    #[inline(always)]
+    pub fn hash(self) -> u32 {
+        self.0 & Self::HASH_MASK
+    }
+
+    /// Whether the k-mer has no ambiguous bases.
+    #[inline(always)]
+    pub fn is_valid(self) -> bool {
+        self.0 & Self::VALID != 0
+    }
+
+    /// Whether the forward code of the k-mer is at most its reverse-complement code.
+    #[inline(always)]
+    pub fn is_forward(self) -> bool {
+        self.0 & Self::FORWARD != 0
+    }
+}
+
+/// A scalar iterator over the [`CompactHash`] of each k-mer in `nseq`.
+///
+/// The hash bits are the low 30 bits of [`KmerHasher::hash_kmers_scalar`] on `nseq.seq`. Requires `k <= 32`.
+#[inline(always)]
+pub fn hash_kmers_compact_scalar<'s, H: KmerHasher>(
+    hasher: &H,
+    nseq: PackedNSeq<'s>,
+) -> impl ExactSizeIterator<Item = CompactHash> {
+    let k = hasher.k();
+    assert!(k <= 32, "Compact hashes require k <= 32.");
+    let hashes = hasher.hash_kmers_scalar(nseq.seq);
+    // `iter_kmer_ambiguity` only supports small `k`, but short sequences have no k-mers anyway.
+    let ambiguity = if nseq.seq.len() < k {
+        nseq.ambiguous.slice(0..0).iter_kmer_ambiguity(1)
+    } else {
+        nseq.ambiguous.iter_kmer_ambiguity(k)
+    };
+    let mut fw = code_mapper::<false>(k);
+    let mut canonical = code_mapper::<true>(k);
+    let mut a = nseq.seq.iter_bp();
+    a.by_ref().take(k - 1).for_each(|a| {
+        fw(a);
+        canonical(a);
+    });
+    zip(zip(hashes, ambiguity), a).map(move |((hash, ambiguous), a)| {
+        CompactHash::new(hash, !ambiguous, fw(a) == canonical(a))
+    })
+}
+
+/// SIMD version of [`hash_kmers_compact_scalar`], yielding the [`CompactHash`] words of 8 k-mers at a time.
+///
+/// Use [`CompactHash::from_words`] on the collected output. Requires `k <= 32`.
+#[inline(always)]
+pub fn hash_kmers_compact_simd<'s, 't, H: KmerHasher>(
+    hasher: &'t H,
+    nseq: PackedNSeq<'s>,
+    context: usize,
+) -> PaddedIt<impl ChunkIt<S> + use<'s, 't, H>> {
+    let k = hasher.k();
+    assert!(k <= 32, "Compact hashes require k <= 32.");
+    let mut hash_mapper = hasher.in_out_mapper_simd(nseq.seq);
+    let mut ambiguity_it = nseq
+        .ambiguous
+        .par_iter_kmer_ambiguity(k, context + k - 1, 0);
+    let mut strand = strand_mapper_simd(k);
+    let valid = S::splat(CompactHash::VALID);
+    let forward = S::splat(CompactHash::FORWARD);
+    let hash_mask = S::splat(CompactHash::HASH_MASK);
+    nseq.seq
+        .par_iter_bp_delayed_with_factor(context + k - 1, hasher.delay(), 2)
+        .map(move |(a, r)| {
+            // SAFETY: these iterators have the same length.
+            let is_ambiguous = unsafe { ambiguity_it.it.next().unwrap_unchecked() };
+            let hash = hash_mapper((a, r));
+            (hash & hash_mask) | (valid & !is_ambiguous) | (forward & strand(a))
+        })
+        .advance(k - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NtHasher, SipKmerHasher, canonical_kmer_codes};
+    use packed_seq::{PackedNSeqVec, PackedSeqVec, SeqVec};
+
+    fn check(hasher: &impl KmerHasher, nseq: PackedNSeq) {
+        let k = hasher.k();
+        let hashes = hasher.hash_kmers_scalar(nseq.seq).collect::<Vec<_>>();
+        let valid = hasher.hash_valid_kmers_scalar(nseq).collect::<Vec<_>>();
+        let canonical = canonical_kmer_codes(nseq.seq, k).collect::<Vec<_>>();
+
+        let compact = hash_kmers_compact_scalar(hasher, nseq).collect::<Vec<_>>();
+        assert_eq!(compact.len(), hashes.len());
+        for i in 0..compact.len() {
+            let c = compact[i];
+            assert_eq!(c.hash(), hashes[i] & CompactHash::HASH_MASK);
+            assert_eq!(c.is_valid(), valid[i] != u32::MAX, "k={k} i={i}");
+            let fw = nseq.seq.slice(i..i + k).as_u64();
+            assert_eq!(c.is_forward(), fw == canonical[i], "k={k} i={i}");
+        }
+        let words = hash_kmers_compact_simd(hasher, nseq, 1).collect();
+        assert_eq!(CompactHash::from_words(&words), compact);
+    }
+
+    #[test]
+    fn compact_naive() {
+        let mut ascii = PackedSeqVec::random(2000).as_slice().unpack();
+        for i in (0..2000).step_by(97) {
+            ascii[i] = b'N';
+        }
+        let nseq = PackedNSeqVec::from_ascii(&ascii);
+        for k in [1, 5, 21, 31, 32] {
+            for len in [0, 10, 100, 2000] {
+                let nseq = nseq.slice(0..len);
+                check(&NtHasher::<true>::new(k), nseq);
+                check(&SipKmerHasher::<false>::new(k), nseq);
+            }
+        }
+    }
+}
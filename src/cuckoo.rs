@@ -0,0 +1,326 @@
+//! A cuckoo filter over 32-bit k-mer hashes, with SIMD lookups and deletions.
+
+use crate::intrinsics::gather_slice;
+use crate::{KmerHasher, PaddedLayout, S};
+use packed_seq::Seq;
+
+/// The number of fingerprints per bucket.
+const SLOTS: usize = 4;
+/// The maximum number of evictions when inserting into a full bucket.
+const MAX_KICKS: usize = 500;
+/// Odd multiplier deriving the fingerprint from the hash.
+const F: u32 = 0x85EB_CA6B;
+/// Odd multiplier deriving the alternate bucket from the fingerprint.
+const C: u32 = 0x9E37_79B1;
+
+/// A cuckoo filter over `u32` hashes, e.g. k-mer hashes from a [`crate::KmerHasher`].
+///
+/// Each hash is stored as a 16-bit fingerprint in one of two buckets of 4 slots (partial-key cuckoo hashing).
+/// Unlike a [`crate::BloomFilter`], hashes can be removed again, and at high load
+/// the false-positive rate of about `8/2^16` is lower than that of a Bloom filter using the same memory.
+/// The number of buckets is a power of two.
+#[derive(Clone, Debug)]
+pub struct CuckooFilter {
+    /// Two words of two 16-bit fingerprints per bucket. Fingerprint `0` marks an empty slot.
+    words: Vec<u32>,
+    /// `num_buckets - 1`.
+    mask: u32,
+    len: usize,
+    /// A fingerprint and its bucket that could not be placed after [`MAX_KICKS`] evictions.
+    victim: Option<(u32, u16)>,
+    /// Xorshift state for choosing slots to evict.
+    rng: u32,
+}
+
+impl CuckooFilter {
+    /// A filter with at least `num_buckets` buckets of 4 slots (rounded up to a power of two), using 8 bytes per bucket.
+    pub fn new(num_buckets: usize) -> Self {
+        let num_buckets = num_buckets.max(1).next_power_of_two();
+        assert!(
+            num_buckets <= 1 << 30,
+            "At most 2^30 buckets are supported."
+        );
+        Self {
+            words: vec![0; 2 * num_buckets],
+            mask: (num_buckets - 1) as u32,
+            len: 0,
+            victim: None,
+            rng: 0x2545_F491,
+        }
+    }
+
+    /// A filter for up to about `n` hashes, at a load of at most 90%.
+    pub fn with_capacity(n: usize) -> Self {
+        Self::new((n as f64 / (0.9 * SLOTS as f64)).ceil() as usize)
+    }
+
+    /// The number of buckets.
+    pub fn num_buckets(&self) -> usize {
+        self.mask as usize + 1
+    }
+
+    /// The number of stored hashes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no hashes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The fraction of slots that are in use.
+    pub fn load(&self) -> f64 {
+        self.len as f64 / (SLOTS * self.num_buckets()) as f64
+    }
+
+    #[inline(always)]
+    fn fingerprint(h: u32) -> u16 {
+        ((h.wrapping_mul(F) >> 16) as u16).max(1)
+    }
+
+    #[inline(always)]
+    fn alt_bucket(&self, i: u32, fp: u16) -> u32 {
+        i ^ ((fp as u32).wrapping_mul(C) & self.mask)
+    }
+
+    #[inline(always)]
+    fn slot(&self, i: u32, s: usize) -> u16 {
+        (self.words[2 * i as usize + s / 2] >> (16 * (s % 2))) as u16
+    }
+
+    #[inline(always)]
+    fn set_slot(&mut self, i: u32, s: usize, fp: u16) {
+        let w = &mut self.words[2 * i as usize + s / 2];
+        let shift = 16 * (s % 2);
+        *w = (*w & !(0xffff << shift)) | ((fp as u32) << shift);
+    }
+
+    /// Put `fp` in an empty slot of bucket `i`, if there is one.
+    fn try_put(&mut self, i: u32, fp: u16) -> bool {
+        match (0..SLOTS).find(|&s| self.slot(i, s) == 0) {
+            Some(s) => {
+                self.set_slot(i, s, fp);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove one copy of `fp` from bucket `i`, if present.
+    fn try_take(&mut self, i: u32, fp: u16) -> bool {
+        match (0..SLOTS).find(|&s| self.slot(i, s) == fp) {
+            Some(s) => {
+                self.set_slot(i, s, 0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn next_rng(&mut self) -> u32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+
+    /// Insert hash `h`, returning `false` when the filter is full.
+    ///
+    /// Inserting the same hash again stores another copy, so that it can be removed as often as it was inserted.
+    pub fn insert(&mut self, h: u32) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+        let mut fp = Self::fingerprint(h);
+        let i1 = h & self.mask;
+        let i2 = self.alt_bucket(i1, fp);
+        self.len += 1;
+        if self.try_put(i1, fp) || self.try_put(i2, fp) {
+            return true;
+        }
+        let mut i = if self.next_rng() & 1 == 0 { i1 } else { i2 };
+        for _ in 0..MAX_KICKS {
+            let s = self.next_rng() as usize % SLOTS;
+            let evicted = self.slot(i, s);
+            self.set_slot(i, s, fp);
+            fp = evicted;
+            i = self.alt_bucket(i, fp);
+            if self.try_put(i, fp) {
+                return true;
+            }
+        }
+        // Keep the last evicted fingerprint aside, so there are no false negatives.
+        self.victim = Some((i, fp));
+        true
+    }
+
+    /// Remove one copy of hash `h`, returning whether it was (possibly) present.
+    ///
+    /// Only remove hashes that were inserted, since a colliding fingerprint of another hash may be removed otherwise.
+    pub fn remove(&mut self, h: u32) -> bool {
+        let fp = Self::fingerprint(h);
+        let i1 = h & self.mask;
+        let i2 = self.alt_bucket(i1, fp);
+        if self
+            .victim
+            .is_some_and(|(i, v)| v == fp && (i == i1 || i == i2))
+        {
+            self.victim = None;
+        } else if !self.try_take(i1, fp) && !self.try_take(i2, fp) {
+            return false;
+        }
+        self.len -= 1;
+        // Try to move the victim into the freed slot.
+        if let Some((i, fp)) = self.victim
+            && (self.try_put(i, fp) || self.try_put(self.alt_bucket(i, fp), fp))
+        {
+            self.victim = None;
+        }
+        true
+    }
+
+    /// Whether hash `h` may have been inserted. There are no false negatives.
+    #[inline(always)]
+    pub fn contains(&self, h: u32) -> bool {
+        let fp = Self::fingerprint(h);
+        let i1 = h & self.mask;
+        let i2 = self.alt_bucket(i1, fp);
+        (0..SLOTS).any(|s| self.slot(i1, s) == fp || self.slot(i2, s) == fp)
+            || self
+                .victim
+                .is_some_and(|(i, v)| v == fp && (i == i1 || i == i2))
+    }
+
+    /// SIMD version of [`Self::contains`], returning all ones in the lanes that may have been inserted.
+    #[inline(always)]
+    pub fn contains_simd(&self, h: S) -> S {
+        let mask = S::splat(self.mask);
+        let fp: S = (h * S::splat(F)) >> 16;
+        let fp = fp | (fp.simd_eq(S::ZERO) & S::ONE);
+        let i1 = h & mask;
+        let i2 = i1 ^ ((fp * S::splat(C)) & mask);
+        let low = S::splat(0xffff);
+        let mut found = S::ZERO;
+        for i in [i1, i2] {
+            for w in [i + i, i + i + S::ONE] {
+                // SAFETY: `w < 2 * num_buckets = words.len()`.
+                let w = unsafe { gather_slice(&self.words, w) };
+                let high: S = w >> 16;
+                found |= (w & low).simd_eq(fp) | high.simd_eq(fp);
+            }
+        }
+        if let Some((i, v)) = self.victim {
+            let i = S::splat(i);
+            found |= fp.simd_eq(S::splat(v as u32)) & (i1.simd_eq(i) | i2.simd_eq(i));
+        }
+        found
+    }
+
+    /// Insert the distinct k-mer hashes of `seq`, streamed from [`KmerHasher::hash_kmers_simd`].
+    ///
+    /// Hashes that are already (possibly) present are skipped, so that the filter stores a set,
+    /// and repeated k-mers (e.g. homopolymers) do not overflow their two buckets.
+    /// Since false positives are skipped as well, the k-mers inserted this way are insert-only:
+    /// after [`Self::remove`]-ing a colliding hash, a skipped k-mer is no longer found.
+    /// Returns `false` when the filter became full, in which case some hashes were not inserted.
+    pub fn insert_kmers<'s>(&mut self, hasher: &impl KmerHasher, seq: impl Seq<'s>) -> bool {
+        let hashes = hasher.hash_kmers_simd(seq, 1);
+        let positions = hashes.lane_positions(1);
+        for (h, (_, mask)) in hashes.it.zip(positions) {
+            let insert = (mask & !self.contains_simd(h)).to_bitmask();
+            for (j, h) in h.to_array().into_iter().enumerate() {
+                if insert >> j & 1 != 0 && !self.contains(h) && !self.insert(h) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::array::from_fn;
+
+    #[test]
+    fn insert_remove() {
+        let mut filter = CuckooFilter::with_capacity(10000);
+        let items: Vec<u32> = (0..10000).map(|_| rand::random()).collect();
+        for &h in &items {
+            assert!(filter.insert(h));
+        }
+        assert_eq!(filter.len(), 10000);
+        assert!(filter.load() > 0.5 && filter.load() <= 0.9);
+        assert!(items.iter().all(|&h| filter.contains(h)));
+        for chunk in items.chunks_exact(8) {
+            let found = filter.contains_simd(S::new(chunk.try_into().unwrap()));
+            assert_eq!(found, S::MAX);
+        }
+
+        let queries: Vec<u32> = (0..80000).map(|_| rand::random()).collect();
+        let fp = queries.iter().filter(|&&h| filter.contains(h)).count();
+        assert!(fp < 80000 / 500, "fp={fp}");
+        for chunk in queries.chunks_exact(8) {
+            let found = filter.contains_simd(S::new(chunk.try_into().unwrap()));
+            let expected = S::new(from_fn(|j| {
+                if filter.contains(chunk[j]) {
+                    u32::MAX
+                } else {
+                    0
+                }
+            }));
+            assert_eq!(found, expected);
+        }
+
+        for &h in &items[..5000] {
+            assert!(filter.remove(h));
+        }
+        assert_eq!(filter.len(), 5000);
+        assert!(items[5000..].iter().all(|&h| filter.contains(h)));
+        let remaining = items[..5000]
+            .iter()
+            .filter(|&&h| filter.contains(h))
+            .count();
+        assert!(remaining < 10, "remaining={remaining}");
+    }
+
+    #[test]
+    fn full() {
+        let mut filter = CuckooFilter::new(4);
+        let items: Vec<u32> = (0..100).map(|_| rand::random()).collect();
+        let inserted = items.iter().take_while(|&&h| filter.insert(h)).count();
+        assert!((12..=17).contains(&inserted), "inserted={inserted}");
+        assert!(items[..inserted].iter().all(|&h| filter.contains(h)));
+        // Removing frees space again.
+        for &h in &items[..inserted] {
+            assert!(filter.remove(h));
+        }
+        assert!(filter.is_empty());
+        assert!(filter.insert(items[0]));
+    }
+
+    #[test]
+    fn kmers() {
+        let hasher = <NtHasher>::new(21);
+        let seq = PackedSeqVec::random(10000);
+        let mut filter = CuckooFilter::with_capacity(10000);
+        assert!(filter.insert_kmers(&hasher, seq.as_slice()));
+        assert!(filter.insert_kmers(&hasher, seq.slice(100..5000)));
+        assert!((9970..=9980).contains(&filter.len()));
+        assert!(
+            hasher
+                .hash_kmers_scalar(seq.as_slice())
+                .all(|h| filter.contains(h))
+        );
+
+        // A repeated k-mer takes a single slot.
+        let mut filter = CuckooFilter::new(1);
+        let poly_a = PackedSeqVec::from_ascii(&[b'A'; 1000]);
+        assert!(filter.insert_kmers(&hasher, poly_a.as_slice()));
+        assert_eq!(filter.len(), 1);
+    }
+}
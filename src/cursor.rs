@@ -0,0 +1,156 @@
+//! A scalar k-mer hash iterator that can be repositioned.
+
+use packed_seq::Seq;
+
+/// Creates fresh `(in, out)` mappers for a [`KmerCursor`].
+///
+/// Implemented for all closures `Fn() -> M`, so that the factory is stored by value and called without dynamic dispatch.
+pub trait MapperFactory {
+    /// The mapper type, e.g. that of [`crate::KmerHasher::in_out_mapper_scalar`].
+    type Mapper: FnMut((u8, u8)) -> u32;
+
+    /// A fresh mapper.
+    fn new_mapper(&self) -> Self::Mapper;
+}
+
+impl<F: Fn() -> M, M: FnMut((u8, u8)) -> u32> MapperFactory for F {
+    type Mapper = M;
+
+    #[inline(always)]
+    fn new_mapper(&self) -> M {
+        self()
+    }
+}
+
+/// A scalar iterator over the k-mer hashes of a sequence, like [`crate::KmerHasher::hash_kmers_scalar`],
+/// that can [`seek`](KmerCursor::seek) to any k-mer in `O(k)` time by re-rolling only its window.
+///
+/// Construct it via [`crate::KmerHasher::kmer_cursor`].
+/// Useful for random-access consumers that would otherwise hash from the start of the sequence.
+pub struct KmerCursor<Q, F: MapperFactory> {
+    seq: Q,
+    k: usize,
+    delay: usize,
+    /// Creates a fresh mapper, for each seek.
+    new_mapper: F,
+    mapper: F::Mapper,
+    /// The start of the next k-mer.
+    pos: usize,
+}
+
+impl<'s, Q: Seq<'s>, F: MapperFactory> KmerCursor<Q, F> {
+    pub(crate) fn new(seq: Q, k: usize, delay: usize, new_mapper: F) -> Self {
+        assert!(delay < k, "Requires delay <= k-1.");
+        let mut cursor = Self {
+            seq,
+            k,
+            delay,
+            mapper: new_mapper.new_mapper(),
+            new_mapper,
+            pos: 0,
+        };
+        cursor.seek(0);
+        cursor
+    }
+
+    /// The number of k-mers in the sequence.
+    pub fn num_kmers(&self) -> usize {
+        (self.seq.len() + 1).saturating_sub(self.k)
+    }
+
+    /// The start of the k-mer whose hash is returned next.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Continue from the k-mer starting at `pos`, which may be `num_kmers` to end the iteration.
+    pub fn seek(&mut self, pos: usize) {
+        assert!(
+            pos <= self.num_kmers(),
+            "Seek position {pos} is past the last k-mer."
+        );
+        self.mapper = self.new_mapper.new_mapper();
+        self.pos = pos;
+        if pos == self.num_kmers() {
+            return;
+        }
+        // Roll in the first `k-1` characters, with `out=0` for the first `delay` of them.
+        for i in pos..pos + self.k - 1 {
+            let r = if i >= pos + self.delay {
+                self.seq.get(i - self.delay)
+            } else {
+                0
+            };
+            (self.mapper)((self.seq.get(i), r));
+        }
+    }
+}
+
+impl<'s, Q: Seq<'s>, F: MapperFactory> Iterator for KmerCursor<Q, F> {
+    type Item = u32;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<u32> {
+        if self.pos >= self.num_kmers() {
+            return None;
+        }
+        let i = self.pos + self.k - 1;
+        self.pos += 1;
+        Some((self.mapper)((
+            self.seq.get(i),
+            self.seq.get(i - self.delay),
+        )))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.num_kmers() - self.pos;
+        (len, Some(len))
+    }
+}
+
+impl<'s, Q: Seq<'s>, F: MapperFactory> ExactSizeIterator for KmerCursor<Q, F> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AntiLexHasher, KmerHasher, MulHasher, NtHasher, SipKmerHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    fn check(hasher: &impl KmerHasher) {
+        let seq = PackedSeqVec::random(500);
+        let seq = seq.as_slice();
+        let expected: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+        let mut cursor = hasher.kmer_cursor(seq);
+        assert_eq!(cursor.num_kmers(), expected.len());
+        assert_eq!(cursor.by_ref().take(10).collect::<Vec<_>>(), expected[..10]);
+        assert_eq!(cursor.position(), 10);
+        for pos in [300, 0, 17, expected.len() - 3, 5] {
+            cursor.seek(pos);
+            assert_eq!(cursor.len(), expected.len() - pos);
+            assert_eq!(
+                cursor.by_ref().take(20).collect::<Vec<_>>(),
+                expected[pos..(pos + 20).min(expected.len())]
+            );
+        }
+        cursor.seek(expected.len());
+        assert_eq!(cursor.next(), None);
+    }
+
+    #[test]
+    fn seek_matches_scalar() {
+        for k in [1, 5, 31, 32] {
+            check(&<NtHasher>::new(k));
+            check(&MulHasher::<false>::new(k));
+            check(&AntiLexHasher::<true>::new(k));
+            check(&SipKmerHasher::<true>::new(k));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn seek_past_end() {
+        let seq = PackedSeqVec::random(100);
+        let hasher = <NtHasher>::new(21);
+        hasher.kmer_cursor(seq.as_slice()).seek(81);
+    }
+}
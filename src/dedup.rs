@@ -0,0 +1,151 @@
+//! Streaming detection of exact and reverse-complement duplicate reads.
+
+use crate::ReadHasher;
+use packed_seq::Seq;
+use std::collections::HashSet;
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Odd multiplier used to spread bits, as in FxHash.
+const SPREAD: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A [`Hasher`] that passes through already well-mixed `u64` keys.
+///
+/// Other keys are folded into the state FxHash-style, 8 bytes at a time.
+#[derive(Default)]
+pub(crate) struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    #[inline(always)]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.0 = (self.0.rotate_left(5) ^ u64::from_le_bytes(word)).wrapping_mul(SPREAD);
+        }
+    }
+    #[inline(always)]
+    fn write_u64(&mut self, x: u64) {
+        // A single `u64` key is passed through unchanged.
+        self.0 = self.0.rotate_left(5) ^ x;
+    }
+    #[inline(always)]
+    fn write_u32(&mut self, x: u32) {
+        // Spread the bits, since hashbrown uses the top bits for tags.
+        self.0 = (self.0.rotate_left(5) ^ x as u64).wrapping_mul(SPREAD);
+    }
+}
+
+pub(crate) type IdentityBuildHasher = BuildHasherDefault<IdentityHasher>;
+
+/// Flags reads that are exact or reverse-complement duplicates of a previously seen read.
+///
+/// Only the 64-bit [`ReadHasher`] hash of each distinct read is stored,
+/// so false duplicates are possible with the probability documented there.
+#[derive(Default, Clone)]
+pub struct DuplicateDetector {
+    hasher: ReadHasher,
+    seen: HashSet<u64, IdentityBuildHasher>,
+}
+
+impl DuplicateDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_with_seed(seed: u32) -> Self {
+        Self {
+            hasher: ReadHasher::new_with_seed(seed),
+            seen: HashSet::default(),
+        }
+    }
+
+    /// Process one read. Returns `true` if it should be kept, i.e., it was not seen before.
+    #[inline(always)]
+    pub fn insert<'s>(&mut self, read: impl Seq<'s>) -> bool {
+        self.seen.insert(self.hasher.hash64(read))
+    }
+
+    /// Returns `true` when `read` (or its reverse complement) was seen before, without inserting it.
+    #[inline(always)]
+    pub fn contains<'s>(&self, read: impl Seq<'s>) -> bool {
+        self.seen.contains(&self.hasher.hash64(read))
+    }
+
+    /// Process a stream of reads, returning a keep (`true`) / discard (`false`) mask.
+    pub fn mask<'s, S: Seq<'s>>(&mut self, reads: impl IntoIterator<Item = S>) -> Vec<bool> {
+        reads.into_iter().map(|read| self.insert(read)).collect()
+    }
+
+    /// The number of distinct reads seen so far.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::hash::Hash;
+
+    #[test]
+    fn duplicates() {
+        let reads: Vec<PackedSeqVec> = (0..100).map(|_| PackedSeqVec::random(150)).collect();
+        let mut stream = vec![];
+        let mut expected = vec![];
+        for (i, read) in reads.iter().enumerate() {
+            stream.push(read.clone());
+            expected.push(true);
+            if i % 3 == 0 {
+                stream.push(read.as_slice().to_revcomp());
+                expected.push(false);
+            }
+            if i % 5 == 0 {
+                stream.push(reads[i / 2].clone());
+                expected.push(false);
+            }
+        }
+
+        let mut detector = DuplicateDetector::new();
+        assert_eq!(detector.mask(stream.iter().map(|r| r.as_slice())), expected);
+        assert_eq!(detector.len(), reads.len());
+        assert!(detector.contains(reads[7].as_slice()));
+    }
+
+    #[test]
+    fn identity_hasher_other_keys() {
+        let hash = |key: &dyn Fn(&mut IdentityHasher)| {
+            let mut h = IdentityHasher::default();
+            key(&mut h);
+            h.finish()
+        };
+        assert_eq!(hash(&|h| h.write_u64(12345)), 12345);
+
+        let keys: Vec<String> = (0..1000).map(|i| format!("read-{i}")).collect();
+        let mut set = HashSet::<&str, IdentityBuildHasher>::default();
+        set.extend(keys.iter().map(|k| k.as_str()));
+        assert_eq!(set.len(), keys.len());
+        assert!(set.contains("read-999"));
+
+        let pairs: HashSet<(u64, u64), IdentityBuildHasher> = (0..100)
+            .flat_map(|i| (0..100).map(move |j| (i, j)))
+            .collect();
+        assert_eq!(pairs.len(), 100 * 100);
+        assert_ne!(
+            hash(&|h| (1u64, 2u64).hash(h)),
+            hash(&|h| (2u64, 1u64).hash(h))
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Cryptographic BLAKE3 digests of k-mers.
+
+/// The 256-bit BLAKE3 digest of each k-mer of the ASCII sequence `seq`.
+///
+/// Unlike the rolling hashers, this hashes each k-mer from scratch,
+/// giving cryptographic collision resistance, e.g. for content-addressed k-mer storage.
+/// Each k-mer is a separate `blake3::hash` call: BLAKE3 only parallelizes over the 1 KiB chunks
+/// of a single input, and has no stable API to hash many short inputs at once,
+/// so this runs at the per-call rate of BLAKE3 on tiny inputs rather than its SIMD throughput.
+/// The digest of a k-mer equals `blake3::hash` of its ASCII bytes,
+/// so it can be recomputed without this crate.
+///
+/// When `canonical` is set, the lexicographically smaller of the k-mer and its reverse complement is hashed.
+/// The reverse complement maps `ACGT` to `TGCA` (preserving case), and keeps other characters as-is.
+pub fn kmer_digests(seq: &[u8], k: usize, canonical: bool) -> Vec<[u8; 32]> {
+    assert!(k > 0, "k must be positive.");
+    let mut rc = vec![0; k];
+    seq.windows(k)
+        .map(|kmer| {
+            let mut input = kmer;
+            if canonical {
+                for (r, &c) in rc.iter_mut().zip(kmer.iter().rev()) {
+                    *r = complement(c);
+                }
+                if rc.as_slice() < kmer {
+                    input = &rc;
+                }
+            }
+            *blake3::hash(input).as_bytes()
+        })
+        .collect()
+}
+
+#[inline(always)]
+fn complement(c: u8) -> u8 {
+    match c {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        b'a' => b't',
+        b'c' => b'g',
+        b'g' => b'c',
+        b't' => b'a',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests() {
+        let seq = b"ACGTTGCAAGGCT";
+        let digests = kmer_digests(seq, 5, false);
+        assert_eq!(digests.len(), seq.len() - 4);
+        assert_eq!(digests[2], *blake3::hash(b"GTTGC").as_bytes());
+
+        let canonical = kmer_digests(seq, 5, true);
+        // GTTGC has reverse complement GCAAC, which is smaller.
+        assert_eq!(canonical[2], *blake3::hash(b"GCAAC").as_bytes());
+        let rc: Vec<u8> = seq.iter().rev().map(|&c| complement(c)).collect();
+        let mut canonical_rc = kmer_digests(&rc, 5, true);
+        canonical_rc.reverse();
+        assert_eq!(canonical, canonical_rc);
+    }
+}
@@ -0,0 +1,203 @@
+//! A simple binary format for dumping and loading hash streams.
+//!
+//! The format is the magic `SQH1`, a one-byte [`DumpEncoding`] tag, the number of hashes as a little-endian `u64`,
+//! followed by the encoded hashes.
+
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"SQH1";
+
+/// How hashes are encoded in a dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpEncoding {
+    /// Little-endian `u32`s, in the original order.
+    Raw = 0,
+    /// Sorted and deduplicated, with the differences between consecutive hashes encoded as LEB128 varints.
+    ///
+    /// Dense hash sets (e.g. canonical k-mer sets) compress several-fold,
+    /// and this is the common exchange format for set comparisons.
+    SortedDeltaVarint = 1,
+}
+
+/// Append `x` as a LEB128 varint.
+#[inline(always)]
+fn write_varint(out: &mut Vec<u8>, mut x: u32) {
+    while x >= 0x80 {
+        out.push(x as u8 | 0x80);
+        x >>= 7;
+    }
+    out.push(x as u8);
+}
+
+fn read_varint(r: &mut impl Read) -> io::Result<u32> {
+    let mut x = 0u32;
+    for shift in (0..35).step_by(7) {
+        let mut b = [0u8];
+        r.read_exact(&mut b)?;
+        let b = b[0];
+        // The 5th byte only has room for the top 4 bits.
+        if shift == 28 && b & 0x70 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "varint overflows u32",
+            ));
+        }
+        x |= ((b & 0x7f) as u32) << shift;
+        if b < 0x80 {
+            return Ok(x);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+/// Read exactly `n` bytes, where `n` comes from an untrusted header.
+///
+/// The buffer grows as data arrives, so a corrupt length fails with [`io::ErrorKind::UnexpectedEof`]
+/// instead of allocating `n` bytes up front.
+pub(crate) fn read_bytes(r: &mut impl Read, n: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(n.min(1 << 16) as usize);
+    r.take(n).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < n {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "fewer bytes than announced in the header",
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Write `hashes` to `w` using the given encoding.
+///
+/// With [`DumpEncoding::SortedDeltaVarint`], the hashes are sorted and deduplicated first.
+pub fn write_hashes(w: &mut impl Write, hashes: &[u32], encoding: DumpEncoding) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(13 + 4 * hashes.len());
+    buf.extend_from_slice(MAGIC);
+    buf.push(encoding as u8);
+    match encoding {
+        DumpEncoding::Raw => {
+            buf.extend_from_slice(&(hashes.len() as u64).to_le_bytes());
+            for &h in hashes {
+                buf.extend_from_slice(&h.to_le_bytes());
+            }
+        }
+        DumpEncoding::SortedDeltaVarint => {
+            let mut sorted = hashes.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            buf.extend_from_slice(&(sorted.len() as u64).to_le_bytes());
+            let mut prev = 0;
+            for h in sorted {
+                write_varint(&mut buf, h - prev);
+                prev = h;
+            }
+        }
+    }
+    w.write_all(&buf)
+}
+
+/// Read hashes written by [`write_hashes`].
+///
+/// Varints are read byte-by-byte, so prefer passing a buffered reader.
+pub fn read_hashes(r: &mut impl Read) -> io::Result<Vec<u32>> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut header = [0u8; 13];
+    r.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(invalid("not a seq-hash dump"));
+    }
+    let len = u64::from_le_bytes(header[5..].try_into().unwrap());
+    match header[4] {
+        0 => {
+            let num_bytes = len
+                .checked_mul(4)
+                .ok_or_else(|| invalid("too many hashes"))?;
+            let bytes = read_bytes(r, num_bytes)?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+                .collect())
+        }
+        1 => {
+            // Each hash takes at least one byte, so do not trust `len` beyond what is read.
+            let mut hashes = Vec::with_capacity(len.min(1 << 16) as usize);
+            let mut prev = 0u32;
+            for _ in 0..len {
+                prev = prev
+                    .checked_add(read_varint(r)?)
+                    .ok_or_else(|| invalid("delta overflows"))?;
+                hashes.push(prev);
+            }
+            Ok(hashes)
+        }
+        _ => Err(invalid("unknown encoding")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KmerHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn round_trip() {
+        let seq = PackedSeqVec::random(100_000);
+        let hashes = <NtHasher>::new(21)
+            .hash_kmers_simd(seq.as_slice(), 1)
+            .collect();
+
+        let mut raw = vec![];
+        write_hashes(&mut raw, &hashes, DumpEncoding::Raw).unwrap();
+        assert_eq!(read_hashes(&mut raw.as_slice()).unwrap(), hashes);
+
+        let mut compressed = vec![];
+        write_hashes(&mut compressed, &hashes, DumpEncoding::SortedDeltaVarint).unwrap();
+        let mut sorted = hashes.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(read_hashes(&mut compressed.as_slice()).unwrap(), sorted);
+        assert!(compressed.len() < raw.len());
+
+        for h in [0, 1, 127, 128, 16383, 16384, u32::MAX] {
+            let mut buf = vec![];
+            write_varint(&mut buf, h);
+            assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), h);
+        }
+        assert!(read_hashes(&mut &b"nope"[..]).is_err());
+
+        // `u32::MAX` with an extra bit set in the 5th byte.
+        let overflow = [0xff, 0xff, 0xff, 0xff, 0x1f];
+        let err = read_varint(&mut overflow.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(DumpEncoding::SortedDeltaVarint as u8);
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&overflow);
+        let err = read_hashes(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupt_header() {
+        // Lengths that overflow `4 * len`, or would allocate far more than the input.
+        for len in [u64::MAX, u64::MAX / 4 + 1, 1 << 40] {
+            for encoding in [0, 1] {
+                let mut bytes = MAGIC.to_vec();
+                bytes.push(encoding);
+                bytes.extend_from_slice(&len.to_le_bytes());
+                bytes.extend_from_slice(&[1, 2, 3, 4]);
+                let err = read_hashes(&mut bytes.as_slice()).unwrap_err();
+                assert!(
+                    matches!(
+                        err.kind(),
+                        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                    ),
+                    "{err}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! A compressed set of sorted 32-bit hashes using Elias–Fano coding.
+//!
+//! The serialized format is the magic `SQEF`, the number of hashes as a little-endian `u64`,
+//! followed by the low-bit and high-bit words as little-endian `u64`s.
+
+use crate::KmerHasher;
+use crate::dump::read_bytes;
+use packed_seq::Seq;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"SQEF";
+
+/// Every `SAMPLE`th zero in the high bits is sampled to speed up lookups.
+const SAMPLE: usize = 256;
+
+/// A compressed set of distinct `u32` hashes, e.g. canonical k-mer hashes, supporting membership queries.
+///
+/// Each hash is split into `l ≈ log2(2^32/n)` low bits, stored verbatim,
+/// and high bits, stored as a unary-coded bitvector of bucket sizes.
+/// This takes about `2 + log2(2^32/n)` bits per hash, e.g. 14 bits for a million hashes,
+/// compared to 32 for a plain vector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EliasFanoSet {
+    len: usize,
+    low_bits: u32,
+    low: Vec<u64>,
+    high: Vec<u64>,
+    /// Position in `high` after every `SAMPLE`th zero.
+    samples: Vec<usize>,
+}
+
+/// The number of low bits per hash, and the number of low and high words, for `len` hashes.
+fn layout(len: usize) -> (u32, usize, usize) {
+    let low_bits = ((1u64 << 32) / len.max(1) as u64).ilog2();
+    let high_len = len + (1usize << (32 - low_bits));
+    (
+        low_bits,
+        (len * low_bits as usize).div_ceil(64),
+        high_len.div_ceil(64),
+    )
+}
+
+impl EliasFanoSet {
+    /// Build the set from `len` strictly increasing `hashes`, in a single streaming pass.
+    ///
+    /// Panics when the hashes are not strictly increasing, or when there are not exactly `len` of them.
+    pub fn from_sorted(len: usize, hashes: impl IntoIterator<Item = u32>) -> Self {
+        let (low_bits, low_words, high_words) = layout(len);
+        let mut low = vec![0u64; low_words];
+        let mut high = vec![0u64; high_words];
+        let mut i = 0;
+        let mut prev = None;
+        for h in hashes {
+            assert!(i < len, "More than {len} hashes given.");
+            assert!(prev < Some(h), "Hashes must be strictly increasing.");
+            prev = Some(h);
+            let h = h as u64;
+            if low_bits > 0 {
+                let bit = i * low_bits as usize;
+                let lo = h & ((1 << low_bits) - 1);
+                low[bit / 64] |= lo << (bit % 64);
+                if bit % 64 + low_bits as usize > 64 {
+                    low[bit / 64 + 1] |= lo >> (64 - bit % 64);
+                }
+            }
+            let pos = (h >> low_bits) as usize + i;
+            high[pos / 64] |= 1 << (pos % 64);
+            i += 1;
+        }
+        assert_eq!(i, len, "Fewer than {len} hashes given.");
+        Self::from_parts(len, low_bits, low, high)
+    }
+
+    /// Build the set from arbitrary `hashes`, which are sorted and deduplicated first.
+    pub fn from_hashes(hashes: impl IntoIterator<Item = u32>) -> Self {
+        let mut hashes: Vec<u32> = hashes.into_iter().collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        Self::from_sorted(hashes.len(), hashes)
+    }
+
+    /// The set of k-mer hashes of `refs`.
+    ///
+    /// Build it with a canonical hasher to match k-mers on either strand,
+    /// and use the same hasher to query it.
+    pub fn from_kmers<'s, H: KmerHasher>(
+        hasher: &H,
+        refs: impl IntoIterator<Item = impl Seq<'s>>,
+    ) -> Self {
+        let mut hashes = vec![];
+        for seq in refs {
+            hashes.extend(hasher.hash_kmers_simd(seq, 1).collect());
+        }
+        Self::from_hashes(hashes)
+    }
+
+    fn from_parts(len: usize, low_bits: u32, low: Vec<u64>, high: Vec<u64>) -> Self {
+        let mut samples = vec![0];
+        let mut zeros = 0;
+        for (w, &word) in high.iter().enumerate() {
+            let mut inv = !word;
+            while inv != 0 {
+                zeros += 1;
+                if zeros % SAMPLE == 0 {
+                    samples.push(64 * w + inv.trailing_zeros() as usize + 1);
+                }
+                inv &= inv - 1;
+            }
+        }
+        Self {
+            len,
+            low_bits,
+            low,
+            high,
+            samples,
+        }
+    }
+
+    /// The number of hashes in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The size of the encoded low and high bits in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        8 * (self.low.len() + self.high.len())
+    }
+
+    #[inline(always)]
+    fn low(&self, i: usize) -> u64 {
+        if self.low_bits == 0 {
+            return 0;
+        }
+        let bit = i * self.low_bits as usize;
+        let mut v = self.low[bit / 64] >> (bit % 64);
+        if bit % 64 + self.low_bits as usize > 64 {
+            v |= self.low[bit / 64 + 1] << (64 - bit % 64);
+        }
+        v & ((1 << self.low_bits) - 1)
+    }
+
+    #[inline(always)]
+    fn high_bit(&self, pos: usize) -> bool {
+        self.high
+            .get(pos / 64)
+            .is_some_and(|w| w >> (pos % 64) & 1 != 0)
+    }
+
+    /// The position in `high` just after the `n`th zero.
+    fn select_zero(&self, n: usize) -> usize {
+        let mut pos = self.samples[n / SAMPLE];
+        let mut r = n % SAMPLE;
+        if r == 0 {
+            return pos;
+        }
+        loop {
+            // Zeros at or after `pos` in the current word.
+            let mut inv = !self.high[pos / 64] & (u64::MAX << (pos % 64));
+            let zeros = inv.count_ones() as usize;
+            if r <= zeros {
+                for _ in 1..r {
+                    inv &= inv - 1;
+                }
+                return pos / 64 * 64 + inv.trailing_zeros() as usize + 1;
+            }
+            r -= zeros;
+            pos = (pos / 64 + 1) * 64;
+        }
+    }
+
+    /// Whether hash `h` is in the set.
+    pub fn contains(&self, h: u32) -> bool {
+        if self.len == 0 {
+            return false;
+        }
+        let h = h as u64;
+        let hi = (h >> self.low_bits) as usize;
+        let lo = h & ((1 << self.low_bits) - 1);
+        // Hashes with high part `hi` are the ones between the `hi`th and `hi+1`th zero.
+        let mut pos = self.select_zero(hi);
+        let mut i = pos - hi;
+        while self.high_bit(pos) {
+            let l = self.low(i);
+            if l >= lo {
+                return l == lo;
+            }
+            pos += 1;
+            i += 1;
+        }
+        false
+    }
+
+    /// Iterate over the hashes in increasing order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        let mut pos = 0;
+        (0..self.len).map(move |i| {
+            while !self.high_bit(pos) {
+                pos += 1;
+            }
+            let hi = (pos - i) as u64;
+            pos += 1;
+            ((hi << self.low_bits) | self.low(i)) as u32
+        })
+    }
+
+    /// Write the set to `w`.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(12 + self.size_in_bytes());
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&(self.len as u64).to_le_bytes());
+        for word in self.low.iter().chain(&self.high) {
+            buf.extend_from_slice(&word.to_le_bytes());
+        }
+        w.write_all(&buf)
+    }
+
+    /// Read a set written by [`Self::write`].
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut header = [0u8; 12];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a seq-hash Elias-Fano set"));
+        }
+        let len = u64::from_le_bytes(header[4..].try_into().unwrap());
+        if len > 1 << 32 {
+            return Err(invalid("too many hashes"));
+        }
+        let len = len as usize;
+        let (low_bits, low_words, high_words) = layout(len);
+        let bytes = read_bytes(r, 8 * (low_words + high_words) as u64)?;
+        let mut words = bytes
+            .chunks_exact(8)
+            .map(|c| u64::from_le_bytes(c.try_into().unwrap()));
+        let low = words.by_ref().take(low_words).collect();
+        let high: Vec<u64> = words.collect();
+        let ones: usize = high.iter().map(|w| w.count_ones() as usize).sum();
+        if ones != len {
+            return Err(invalid("wrong number of hashes"));
+        }
+        Ok(Self::from_parts(len, low_bits, low, high))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::HashSet;
+
+    #[test]
+    fn membership() {
+        for n in [0, 1, 2, 100, 10_000, 300_000] {
+            let mut hashes: Vec<u32> = (0..n).map(|_| rand::random()).collect();
+            hashes.extend([0, u32::MAX]);
+            let set = EliasFanoSet::from_hashes(hashes.iter().copied());
+            let exact: HashSet<u32> = hashes.iter().copied().collect();
+            assert_eq!(set.len(), exact.len());
+
+            let mut sorted: Vec<u32> = exact.iter().copied().collect();
+            sorted.sort_unstable();
+            assert_eq!(set.iter().collect::<Vec<_>>(), sorted, "n={n}");
+
+            for &h in &hashes {
+                assert!(set.contains(h));
+                assert!(set.contains(h.wrapping_add(1)) == exact.contains(&h.wrapping_add(1)));
+            }
+            for _ in 0..10_000 {
+                let h = rand::random();
+                assert_eq!(set.contains(h), exact.contains(&h));
+            }
+        }
+        let empty = EliasFanoSet::from_sorted(0, []);
+        assert!(empty.is_empty() && !empty.contains(0));
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    fn kmers_round_trip() {
+        let seqs: Vec<PackedSeqVec> = (0..10).map(|_| PackedSeqVec::random(100_000)).collect();
+        let hasher = <NtHasher>::new(21);
+        let set = EliasFanoSet::from_kmers(&hasher, seqs.iter().map(|s| s.as_slice()));
+        assert!(set.len() > 990_000);
+        // Under 16 bits per hash.
+        assert!(set.size_in_bytes() < 2 * set.len());
+        for h in hasher.hash_kmers_scalar(seqs[3].as_slice()) {
+            assert!(set.contains(h));
+        }
+
+        let mut buf = vec![];
+        set.write(&mut buf).unwrap();
+        assert_eq!(EliasFanoSet::read(&mut buf.as_slice()).unwrap(), set);
+        assert!(EliasFanoSet::read(&mut &buf[..20]).is_err());
+        assert!(EliasFanoSet::read(&mut &b"nope"[..]).is_err());
+    }
+
+    #[test]
+    fn corrupt_header() {
+        // A 12-byte input announcing 2^32 hashes must not allocate their words up front.
+        for len in [1u64 << 32, 1 << 20, 1 << 32 | 1] {
+            let mut bytes = MAGIC.to_vec();
+            bytes.extend_from_slice(&len.to_le_bytes());
+            let err = EliasFanoSet::read(&mut bytes.as_slice()).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind(),
+                    io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                ),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic = "strictly increasing"]
+    fn unsorted() {
+        EliasFanoSet::from_sorted(2, [3, 3]);
+    }
+}
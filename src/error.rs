@@ -0,0 +1,43 @@
+//! Errors returned by checked hasher constructors.
+
+use std::fmt;
+
+/// An error indicating that a hasher can not handle the given input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HashError {
+    /// The sequence uses more bits per character than the hasher supports,
+    /// e.g. when passing a `&[u8]` to an [`crate::NtHasher`].
+    UnsupportedAlphabet {
+        bits_per_char: usize,
+        max_bits_per_char: usize,
+    },
+    /// The polynomial passed to [`crate::RabinHasher`] is not irreducible or has an unsupported degree.
+    InvalidPolynomial { polynomial: u64 },
+    /// Minimal perfect hash construction still had colliding keys after the maximum number of levels.
+    MphfNotConverged { levels: usize },
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashError::UnsupportedAlphabet {
+                bits_per_char,
+                max_bits_per_char,
+            } => write!(
+                f,
+                "sequence has {bits_per_char} bits per character, but the hasher supports at most {max_bits_per_char}"
+            ),
+            HashError::InvalidPolynomial { polynomial } => write!(
+                f,
+                "polynomial {polynomial:#x} is not irreducible of degree 9..=56"
+            ),
+            HashError::MphfNotConverged { levels } => write!(
+                f,
+                "minimal perfect hash construction did not converge within {levels} levels"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
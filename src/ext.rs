@@ -0,0 +1,120 @@
+//! Extension methods on sequence types for hashing all their k-mers.
+
+use crate::{DynNtHasher, KmerHasher, PaddedLayout};
+use packed_seq::{AsciiSeq, PackedNSeq, PackedNSeqVec, PackedSeq, Seq};
+use std::iter::zip;
+
+/// Convenience methods to hash all k-mers of a sequence, as in `seq.kmer_hashes(&hasher)`.
+///
+/// These collect [`KmerHasher::hash_kmers_simd`] (or [`KmerHasher::hash_valid_kmers_simd`])
+/// with a context of a single k-mer.
+/// To avoid allocations or to stream over the hashes, use those methods directly.
+///
+/// ```
+/// use seq_hash::packed_seq::{PackedNSeqVec, PackedSeqVec, Seq, SeqVec};
+/// use seq_hash::{HashSeqExt, KmerHasher, NtHasher};
+/// let hasher = <NtHasher>::new(3);
+/// let seq = PackedSeqVec::from_ascii(b"ACGTNACGT");
+/// assert_eq!(seq.as_slice().kmer_hashes(&hasher).len(), 7);
+///
+/// let nseq = PackedNSeqVec::from_ascii(b"ACGTNACGT");
+/// let hashes = nseq.as_slice().valid_kmer_hashes(&hasher);
+/// assert_eq!(hashes[0], hasher.hash_seq(seq.as_slice().slice(0..3)));
+/// assert_eq!(hashes[2], u32::MAX);
+/// ```
+pub trait HashSeqExt: Sized {
+    /// All k-mer hashes of the sequence.
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32>;
+
+    /// All k-mer hashes of the sequence, with `u32::MAX` for k-mers containing ambiguous characters.
+    ///
+    /// Sequences without ambiguity information have no ambiguous k-mers,
+    /// so this equals [`HashSeqExt::kmer_hashes`] for them.
+    fn valid_kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        self.kmer_hashes(hasher)
+    }
+}
+
+#[inline(always)]
+fn kmer_hashes<'s>(seq: impl Seq<'s>, hasher: &impl KmerHasher) -> Vec<u32> {
+    hasher.hash_kmers_simd(seq, 1).collect()
+}
+
+impl HashSeqExt for PackedSeq<'_> {
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        kmer_hashes(self, hasher)
+    }
+}
+
+impl HashSeqExt for AsciiSeq<'_> {
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        kmer_hashes(self, hasher)
+    }
+}
+
+impl HashSeqExt for &[u8] {
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        kmer_hashes(self, hasher)
+    }
+}
+
+impl HashSeqExt for PackedNSeq<'_> {
+    /// All k-mer hashes of the underlying sequence, ignoring ambiguity.
+    fn kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        kmer_hashes(self.seq, hasher)
+    }
+
+    fn valid_kmer_hashes(self, hasher: &impl KmerHasher) -> Vec<u32> {
+        hasher.hash_valid_kmers_simd(self, 1).collect()
+    }
+}
+
+/// Hash all k-mers of an ASCII DNA sequence with [`DynNtHasher`](crate::DynNtHasher).
+///
+/// The sequence is packed internally, and k-mers containing characters other than `ACGT` get hash `u32::MAX`.
+/// Mostly intended for scripts and tests; prefer reusing a hasher and packed sequence when performance matters.
+///
+/// ```
+/// let hashes = seq_hash::hash_ascii_kmers(b"ACGTNACGT", 3, true);
+/// assert_eq!(hashes.len(), 7);
+/// assert_eq!(hashes[0], hashes[5]);
+/// assert_eq!(hashes[2], u32::MAX);
+/// ```
+pub fn hash_ascii_kmers(seq: &[u8], k: usize, canonical: bool) -> Vec<u32> {
+    let nseq = PackedNSeqVec::from_ascii(seq);
+    nseq.as_slice()
+        .valid_kmer_hashes(&<DynNtHasher>::new(k, canonical))
+}
+
+/// Write all k-mer hashes of `seq` to `out`, in order and without padding, and return their number.
+///
+/// Hashes are written directly into the caller's buffer, e.g. memory allocated by C or NumPy.
+/// When there are more than `cap` k-mers, nothing is written, and the caller can retry with a larger buffer.
+/// Requires fewer than `2^32` k-mers.
+///
+/// # Safety
+///
+/// `out` must be valid for writes of `cap` consecutive `u32` values.
+pub unsafe fn hash_kmers_into_ptr<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    out: *mut u32,
+    cap: usize,
+) -> usize {
+    let hashes = hasher.hash_kmers_simd(seq, 1);
+    let n = hashes.num_positions(1);
+    if n > cap {
+        return n;
+    }
+    let positions = hashes.lane_positions(1);
+    for (hashes, (pos, mask)) in hashes.it.zip(positions) {
+        let mask = mask.to_bitmask();
+        for (j, (h, p)) in zip(hashes.to_array(), pos.to_array()).enumerate() {
+            if mask >> j & 1 != 0 {
+                // SAFETY: `p < n <= cap`.
+                unsafe { out.add(p as usize).write(h) };
+            }
+        }
+    }
+    n
+}
@@ -0,0 +1,230 @@
+//! Keyed permutation of hash outputs, for sharing hash sets without revealing k-mers.
+
+use crate::signature::mix64;
+use crate::{HashError, KmerHasher, S, SuffixHasher};
+use packed_seq::{ChunkIt, Delay, PaddedIt, Seq};
+use std::array::from_fn;
+
+const ROUNDS: usize = 4;
+const C: u32 = 0x9E37_79B1;
+
+/// The round function on a 16-bit half.
+#[inline(always)]
+fn round(x: u32, key: u32) -> u32 {
+    (x ^ key).wrapping_mul(C) >> 16
+}
+
+#[inline(always)]
+fn permute(keys: &[u32; ROUNDS], h: u32) -> u32 {
+    let (mut l, mut r) = (h >> 16, h & 0xFFFF);
+    for &key in keys {
+        (l, r) = (r, l ^ round(r, key));
+    }
+    (l << 16) | r
+}
+
+#[inline(always)]
+fn permute_simd(keys: &[u32; ROUNDS], h: S) -> S {
+    let (mut l, mut r): (S, S) = (h >> 16, h & S::splat(0xFFFF));
+    for &key in keys {
+        let f: S = ((r ^ S::splat(key)) * S::splat(C)) >> 16;
+        (l, r) = (r, l ^ f);
+    }
+    (l << 16) | r
+}
+
+/// A [`KmerHasher`] wrapper that applies a keyed permutation of `u32` to each hash of the wrapped hasher.
+///
+/// The permutation is a 4-round Feistel network on the 16-bit halves of the hash, with round keys derived from a 64-bit key.
+/// Collaborators that share the key get hash sets (or sketches) that are comparable with each other, as with the wrapped hasher,
+/// while others cannot match shared hashes against candidate k-mers by hashing them themselves.
+/// Since the output is a bijection of the wrapped hash, collisions and canonicality are unchanged,
+/// and key holders can recover the original hash with [`Self::unpermute`].
+///
+/// This obfuscates rather than encrypts: the network is not a cryptographic cipher,
+/// so do not rely on it against an adversary with many known k-mer/hash pairs.
+/// For cryptographic digests, see `kmer_digests` (`blake3` feature).
+#[derive(Clone)]
+pub struct FeistelHasher<H: KmerHasher> {
+    hasher: H,
+    keys: [u32; ROUNDS],
+}
+
+impl<H: KmerHasher> FeistelHasher<H> {
+    /// Wrap `hasher`, permuting its hashes with the given `key`, which should be kept secret.
+    pub fn with_key(hasher: H, key: u64) -> Self {
+        Self {
+            hasher,
+            keys: from_fn(|i| mix64(key.wrapping_add(i as u64)) as u32),
+        }
+    }
+
+    /// The wrapped hasher.
+    pub fn inner(&self) -> &H {
+        &self.hasher
+    }
+
+    /// The permuted version of a hash `h` of the wrapped hasher.
+    #[inline(always)]
+    pub fn permute(&self, h: u32) -> u32 {
+        permute(&self.keys, h)
+    }
+
+    /// SIMD version of [`Self::permute`].
+    #[inline(always)]
+    pub fn permute_simd(&self, h: S) -> S {
+        permute_simd(&self.keys, h)
+    }
+
+    /// The inverse of [`Self::permute`], recovering the hash of the wrapped hasher.
+    pub fn unpermute(&self, h: u32) -> u32 {
+        let (mut l, mut r) = (h >> 16, h & 0xFFFF);
+        for &key in self.keys.iter().rev() {
+            (l, r) = (r ^ round(l, key), l);
+        }
+        (l << 16) | r
+    }
+}
+
+impl<H: KmerHasher> KmerHasher for FeistelHasher<H> {
+    const CANONICAL: bool = H::CANONICAL;
+
+    /// Wrap a new hasher with the public key `0`.
+    ///
+    /// `new` is for testing only, since anyone can undo this permutation; use [`FeistelHasher::with_key`] with a secret key.
+    fn new(k: usize) -> Self {
+        Self::with_key(H::new(k), 0)
+    }
+
+    #[inline(always)]
+    fn is_canonical(&self) -> bool {
+        self.hasher.is_canonical()
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        self.hasher.max_bits_per_char()
+    }
+
+    #[inline(always)]
+    fn check_alphabet<'s>(&self, seq: impl Seq<'s>) -> Result<(), HashError> {
+        self.hasher.check_alphabet(seq)
+    }
+
+    #[inline(always)]
+    fn delay(&self) -> Delay {
+        self.hasher.delay()
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        let keys = self.keys;
+        let mut mapper = self.hasher.in_out_mapper_scalar(seq);
+        move |io| permute(&keys, mapper(io))
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        let keys = self.keys;
+        let mut mapper = self.hasher.in_out_mapper_simd(seq);
+        move |io| permute_simd(&keys, mapper(io))
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        let keys = self.keys;
+        let mut mapper = self.hasher.mapper(seq);
+        move |a| permute(&keys, mapper(a))
+    }
+
+    #[inline(always)]
+    fn hash_kmers_scalar<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        let keys = self.keys;
+        self.hasher
+            .hash_kmers_scalar(seq)
+            .map(move |h| permute(&keys, h))
+    }
+
+    #[inline(always)]
+    fn hash_kmers_simd<'s>(&self, seq: impl Seq<'s>, context: usize) -> PaddedIt<impl ChunkIt<S>> {
+        let keys = self.keys;
+        self.hasher
+            .hash_kmers_simd(seq, context)
+            .map(move |h| permute_simd(&keys, h))
+    }
+
+    #[inline(always)]
+    fn hash_prefixes_simd<'s>(&self, seq: impl Seq<'s>) -> PaddedIt<impl ChunkIt<S>> {
+        let keys = self.keys;
+        self.hasher
+            .hash_prefixes_simd(seq)
+            .map(move |h| permute_simd(&keys, h))
+    }
+}
+
+impl<H: SuffixHasher> SuffixHasher for FeistelHasher<H> {
+    #[inline(always)]
+    fn hash_suffixes<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        let keys = self.keys;
+        self.hasher
+            .hash_suffixes(seq)
+            .map(move |h| permute(&keys, h))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::HashSet;
+
+    #[test]
+    fn permutation() {
+        let a = FeistelHasher::with_key(<NtHasher>::new(21), 1);
+        let b = FeistelHasher::with_key(<NtHasher>::new(21), 2);
+        let hs: Vec<u32> = (0..10000).map(|_| rand::random()).collect();
+        for &h in &hs {
+            assert_eq!(a.unpermute(a.permute(h)), h);
+            assert_eq!(a.permute(a.unpermute(h)), h);
+        }
+        let lanes: [u32; 8] = from_fn(|i| hs[i]);
+        assert_eq!(
+            a.permute_simd(S::new(lanes)).to_array(),
+            lanes.map(|h| a.permute(h))
+        );
+        // All 2^16 low values map to distinct outputs, and keys give different permutations.
+        assert_eq!(
+            (0..1 << 16)
+                .map(|h| a.permute(h))
+                .collect::<HashSet<_>>()
+                .len(),
+            1 << 16
+        );
+        assert!(hs.iter().filter(|&&h| a.permute(h) == b.permute(h)).count() < 10);
+    }
+
+    #[test]
+    fn shared_key_is_comparable() {
+        let seq = PackedSeqVec::random(1000);
+        let rc = seq.as_slice().to_revcomp();
+        let inner = NtHasher::<true>::new(21);
+        let alice = FeistelHasher::with_key(NtHasher::<true>::new(21), 42);
+        let bob = FeistelHasher::with_key(NtHasher::<true>::new(21), 42);
+        let ha: Vec<u32> = alice.hash_kmers_scalar(seq.as_slice()).collect();
+        let mut hb: Vec<u32> = bob.hash_kmers_scalar(rc.as_slice()).collect();
+        hb.reverse();
+        assert_eq!(ha, hb);
+        let plain: Vec<u32> = inner.hash_kmers_scalar(seq.as_slice()).collect();
+        assert_eq!(
+            ha.iter().map(|&h| alice.unpermute(h)).collect::<Vec<_>>(),
+            plain
+        );
+        assert_ne!(ha, plain);
+    }
+}
@@ -0,0 +1,85 @@
+//! Hashing many sequences of the same length.
+
+use packed_seq::Seq;
+
+/// Hashes sequences of a fixed length, like [`crate::KmerHasher::hash_seq`],
+/// without recomputing length-dependent constants for each sequence.
+///
+/// Construct it via [`crate::KmerHasher::fixed_len_mapper`].
+/// Useful for hashing e.g. millions of barcodes or probes of the same length.
+#[derive(Clone)]
+pub struct FixedLenMapper<M> {
+    len: usize,
+    bits_per_char: usize,
+    /// A fresh mapper, cloned for each sequence.
+    mapper: M,
+}
+
+impl<M: FnMut(u8) -> u32 + Clone> FixedLenMapper<M> {
+    pub(crate) fn new(len: usize, bits_per_char: usize, mapper: M) -> Self {
+        Self {
+            len,
+            bits_per_char,
+            mapper,
+        }
+    }
+
+    /// The length of the sequences to hash.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the sequences to hash are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hash `seq`, which must have the same length as the template, and an alphabet at most as wide.
+    #[inline(always)]
+    pub fn hash<'s>(&self, seq: impl Seq<'s>) -> u32 {
+        assert_eq!(
+            seq.len(),
+            self.len,
+            "Sequence length differs from the template."
+        );
+        assert!(
+            seq.bits_per_char() <= self.bits_per_char,
+            "Alphabet is wider than that of the template."
+        );
+        seq.iter_bp().map(self.mapper.clone()).last().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AntiLexHasher, KmerHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn matches_hash_seq() {
+        let seq = PackedSeqVec::random(1000);
+        for len in [1, 12, 16, 20] {
+            let anti_lex = AntiLexHasher::<false>::new(31);
+            let nthash = <NtHasher>::new(31);
+            let template = seq.slice(0..len);
+            let fixed_anti_lex = anti_lex.fixed_len_mapper(template);
+            let fixed_nthash = nthash.fixed_len_mapper(template);
+            assert_eq!(fixed_anti_lex.len(), len);
+            for i in (0..1000 - len).step_by(37) {
+                let s = seq.slice(i..i + len);
+                assert_eq!(fixed_anti_lex.hash(s), anti_lex.hash_seq(s), "len={len}");
+                assert_eq!(fixed_nthash.hash(s), nthash.hash_seq(s), "len={len}");
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        let seq = PackedSeqVec::random(100);
+        let hasher = AntiLexHasher::<false>::new(31);
+        hasher
+            .fixed_len_mapper(seq.slice(0..10))
+            .hash(seq.slice(0..11));
+    }
+}
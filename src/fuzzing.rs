@@ -0,0 +1,115 @@
+//! Property-testing and fuzzing support: [`arbitrary::Arbitrary`] hasher configurations and [`proptest`] strategies.
+//!
+//! Downstream crates can use these to check their use of seq-hash against [`KmerHasher::hash_kmers_scalar`],
+//! or against [`KmerHasher::hash_seq`] on each k-mer, which are the reference implementations.
+
+use crate::{KmerHasher, RuntimeCanonical, nthash::CharHasher};
+use arbitrary::{Arbitrary, Unstructured};
+use packed_seq::{PackedSeqVec, SeqVec};
+use proptest::prelude::*;
+
+/// The largest `k` generated by [`HasherConfig`] and [`arb_k`].
+pub const MAX_FUZZ_K: usize = 64;
+
+/// The parameters of a seeded hasher, e.g. [`crate::DynNtHasher`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HasherConfig {
+    /// The k-mer length, in `1..=MAX_FUZZ_K`.
+    pub k: usize,
+    /// The seed, or `None` for the default tables.
+    pub seed: Option<u32>,
+    /// Whether hashes are canonical.
+    pub canonical: bool,
+}
+
+impl HasherConfig {
+    /// The runtime-canonical hasher with this configuration.
+    pub fn build<CH: CharHasher>(&self) -> RuntimeCanonical<CH> {
+        match self.seed {
+            Some(seed) => RuntimeCanonical::new_with_seed(self.k, seed, self.canonical),
+            None => RuntimeCanonical::new(self.k, self.canonical),
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for HasherConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            k: u.int_in_range(1..=MAX_FUZZ_K)?,
+            seed: u.arbitrary()?,
+            canonical: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a, CH: CharHasher> Arbitrary<'a> for RuntimeCanonical<CH> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(HasherConfig::arbitrary(u)?.build())
+    }
+}
+
+/// A strategy for `k` in `1..=MAX_FUZZ_K`, biased towards small values and the boundaries of SIMD words.
+pub fn arb_k() -> impl Strategy<Value = usize> {
+    prop_oneof![
+        1..=8usize,
+        prop::sample::select(vec![15, 16, 17, 31, 32, 33, 63, 64]),
+        1..=MAX_FUZZ_K,
+    ]
+}
+
+/// A strategy for random packed DNA sequences with length in `len`.
+pub fn arb_packed_seq(len: std::ops::Range<usize>) -> impl Strategy<Value = PackedSeqVec> {
+    prop::collection::vec(prop::sample::select(b"ACGT".to_vec()), len)
+        .prop_map(|ascii| PackedSeqVec::from_ascii(&ascii))
+}
+
+/// A strategy for `(seq, k, seed)` triples, with sequences of up to `max_len` bases.
+pub fn arb_seq_k_seed(max_len: usize) -> impl Strategy<Value = (PackedSeqVec, usize, u32)> {
+    (arb_packed_seq(0..max_len + 1), arb_k(), any::<u32>())
+}
+
+/// A strategy for [`HasherConfig`]s.
+pub fn arb_hasher_config() -> impl Strategy<Value = HasherConfig> {
+    (arb_k(), any::<Option<u32>>(), any::<bool>()).prop_map(|(k, seed, canonical)| HasherConfig {
+        k,
+        seed,
+        canonical,
+    })
+}
+
+/// Whether the SIMD hashes of `hasher` on `seq` equal the scalar reference.
+pub fn matches_scalar_reference<H: KmerHasher>(hasher: &H, seq: &PackedSeqVec) -> bool {
+    let scalar: Vec<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+    hasher.hash_kmers_simd(seq.as_slice(), 1).collect() == scalar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DynNtHasher, NtHasher};
+
+    proptest! {
+        #[test]
+        fn simd_matches_scalar((seq, k, seed) in arb_seq_k_seed(300)) {
+            prop_assert!(matches_scalar_reference(&NtHasher::<true>::new_with_seed(k, seed), &seq));
+        }
+
+        #[test]
+        fn config_matches_scalar(config in arb_hasher_config(), seq in arb_packed_seq(0..300)) {
+            let hasher: DynNtHasher = config.build();
+            prop_assert_eq!(hasher.k(), config.k);
+            prop_assert_eq!(hasher.is_canonical(), config.canonical);
+            prop_assert!(matches_scalar_reference(&hasher, &seq));
+        }
+    }
+
+    #[test]
+    fn arbitrary_config() {
+        let bytes: Vec<u8> = (0..64).map(|_| rand::random()).collect();
+        let mut u = Unstructured::new(&bytes);
+        let config = HasherConfig::arbitrary(&mut u).unwrap();
+        assert!((1..=MAX_FUZZ_K).contains(&config.k));
+        let hasher = <DynNtHasher>::arbitrary(&mut u).unwrap();
+        assert!((1..=MAX_FUZZ_K).contains(&hasher.k()));
+    }
+}
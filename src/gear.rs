@@ -0,0 +1,182 @@
+//! Gear hashing and FastCDC-style content-defined chunking over raw bytes.
+//!
+//! The gear hash is updated as `h = (h << 1) + G[b]` for a random table `G`,
+//! so that bit `i` of the hash only depends on the last `i+1` bytes.
+//! Boundaries are hence detected using the high bits of the hash.
+
+use std::array::from_fn;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::ops::Range;
+
+type SeedHasher = BuildHasherDefault<DefaultHasher>;
+
+/// A rolling gear hash over bytes, with a seeded random table of 256 `u64`s.
+#[derive(Clone)]
+pub struct GearHasher {
+    table: [u64; 256],
+}
+
+impl Default for GearHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GearHasher {
+    pub fn new() -> Self {
+        Self::new_with_seed(0)
+    }
+
+    pub fn new_with_seed(seed: u32) -> Self {
+        let hasher = SeedHasher::new();
+        Self {
+            table: from_fn(|i| hasher.hash_one((seed, i as u8))),
+        }
+    }
+
+    /// Add byte `b` to the rolling hash `h`.
+    #[inline(always)]
+    pub fn roll(&self, h: u64, b: u8) -> u64 {
+        (h << 1).wrapping_add(self.table[b as usize])
+    }
+
+    /// The rolling hash after each byte of `data`.
+    /// Each value effectively depends on the last 64 bytes only.
+    #[inline(always)]
+    pub fn hashes<'a>(&'a self, data: &'a [u8]) -> impl ExactSizeIterator<Item = u64> + 'a {
+        let mut h = 0;
+        data.iter().map(move |&b| {
+            h = self.roll(h, b);
+            h
+        })
+    }
+}
+
+/// FastCDC-style content-defined chunker on top of [`GearHasher`].
+///
+/// Chunks are at least `min_size` and at most `max_size` bytes long.
+/// Using normalized chunking, boundaries are harder to find before `avg_size`
+/// and easier after, concentrating chunk sizes around `avg_size`.
+#[derive(Clone)]
+pub struct GearChunker {
+    hasher: GearHasher,
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    /// Stricter mask used before `avg_size`.
+    mask_s: u64,
+    /// Looser mask used after `avg_size`.
+    mask_l: u64,
+}
+
+impl GearChunker {
+    /// Requires `0 < min_size <= avg_size <= max_size`.
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Self {
+        Self::with_hasher(GearHasher::new(), min_size, avg_size, max_size)
+    }
+
+    pub fn with_hasher(
+        hasher: GearHasher,
+        min_size: usize,
+        avg_size: usize,
+        max_size: usize,
+    ) -> Self {
+        assert!(
+            0 < min_size && min_size <= avg_size && avg_size <= max_size,
+            "Chunk sizes must satisfy 0 < min <= avg <= max."
+        );
+        let bits = avg_size.ilog2().clamp(2, 62);
+        let high_bits = |b: u32| !0u64 << (64 - b);
+        Self {
+            hasher,
+            min_size,
+            avg_size,
+            max_size,
+            mask_s: high_bits(bits + 1),
+            mask_l: high_bits(bits - 1),
+        }
+    }
+
+    /// The length of the first chunk of `data`.
+    pub fn cut(&self, data: &[u8]) -> usize {
+        let n = data.len();
+        if n <= self.min_size {
+            return n;
+        }
+        let n = n.min(self.max_size);
+        let normal = self.avg_size.min(n);
+
+        let mut h = 0;
+        // Bytes before `min_size` are skipped, but the hash is warmed up on the preceding 64 bytes.
+        for &b in &data[self.min_size.saturating_sub(64)..self.min_size] {
+            h = self.hasher.roll(h, b);
+        }
+        for (i, &b) in data.iter().enumerate().take(n).skip(self.min_size) {
+            h = self.hasher.roll(h, b);
+            let mask = if i < normal { self.mask_s } else { self.mask_l };
+            if h & mask == 0 {
+                return i + 1;
+            }
+        }
+        n
+    }
+
+    /// Split `data` into consecutive content-defined chunks.
+    pub fn chunks<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = Range<usize>> + 'a {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            if start == data.len() {
+                return None;
+            }
+            let end = start + self.cut(&data[start..]);
+            let range = start..end;
+            start = end;
+            Some(range)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_bytes(n: usize) -> Vec<u8> {
+        (0..n).map(|_| rand::random()).collect()
+    }
+
+    #[test]
+    fn chunk_sizes() {
+        let data = random_bytes(1 << 20);
+        let chunker = GearChunker::new(1024, 4096, 16384);
+        let chunks: Vec<_> = chunker.chunks(&data).collect();
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, data.len());
+        for w in chunks.windows(2) {
+            assert_eq!(w[0].end, w[1].start);
+        }
+        for c in &chunks[..chunks.len() - 1] {
+            assert!((1024..=16384).contains(&c.len()), "{c:?}");
+        }
+        let avg = data.len() / chunks.len();
+        assert!((2048..8192).contains(&avg), "avg {avg}");
+    }
+
+    #[test]
+    fn shift_resistant() {
+        let data = random_bytes(1 << 18);
+        let chunker = GearChunker::new(256, 1024, 4096);
+        let mut shifted = vec![42];
+        shifted.extend_from_slice(&data);
+
+        let ends = |d: &[u8], offset: usize| {
+            chunker
+                .chunks(d)
+                .map(|r| r.end - offset)
+                .collect::<std::collections::HashSet<_>>()
+        };
+        let a = ends(&data, 0);
+        let b = ends(&shifted, 1);
+        // All but the first few boundaries are preserved.
+        assert!(a.intersection(&b).count() + 5 >= a.len());
+    }
+}
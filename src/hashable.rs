@@ -0,0 +1,47 @@
+//! A minimal sequence interface for third-party sequence containers.
+
+use packed_seq::{Delay, Seq};
+use std::iter::{repeat_n, zip};
+
+/// The minimal interface needed for scalar k-mer hashing via [`KmerHasher::hash_kmers_hashable`](crate::KmerHasher::hash_kmers_hashable).
+///
+/// This is implemented for all [`packed_seq::Seq`] types,
+/// and can be implemented by custom sequence containers without depending on the full [`Seq`] trait.
+///
+/// Method names differ from those of [`Seq`] to avoid ambiguity when both traits are in scope.
+pub trait HashableSeq<'s>: Copy {
+    /// The length of the sequence in characters.
+    fn char_len(&self) -> usize;
+
+    /// The number of bits per character, e.g. 2 for DNA or 8 for arbitrary bytes.
+    fn char_bits(&self) -> usize;
+
+    /// Iterate the characters of the sequence.
+    fn iter_chars(self) -> impl Iterator<Item = u8>;
+
+    /// Iterate `(in, out)` pairs, where `out` is the character `delay` positions before `in`,
+    /// or `0` for the first `delay` characters.
+    fn iter_chars_delayed(self, delay: Delay) -> impl Iterator<Item = (u8, u8)> {
+        zip(
+            self.iter_chars(),
+            repeat_n(0, delay.0).chain(self.iter_chars()),
+        )
+    }
+}
+
+impl<'s, T: Seq<'s>> HashableSeq<'s> for T {
+    #[inline(always)]
+    fn char_len(&self) -> usize {
+        self.len()
+    }
+
+    #[inline(always)]
+    fn char_bits(&self) -> usize {
+        self.bits_per_char()
+    }
+
+    #[inline(always)]
+    fn iter_chars(self) -> impl Iterator<Item = u8> {
+        self.iter_bp()
+    }
+}
@@ -0,0 +1,170 @@
+//! Substring hashing via polynomial prefix hashes.
+//!
+//! A string `c_0 .. c_{n-1}` is hashed as `sum_i (c_i+1) * B^(n-1-i) mod P`, with `P = 2^61-1`
+//! and a random base `B`. These hashes compose: the hash of any substring follows from two prefix hashes,
+//! and two distinct strings of length `n` collide with probability at most `n/P` over the choice of `B`.
+
+use packed_seq::Seq;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::ops::Range;
+
+type SeedHasher = BuildHasherDefault<DefaultHasher>;
+
+/// The Mersenne prime `2^61-1`.
+const P: u64 = (1 << 61) - 1;
+
+/// `a * b mod P`, for `a, b < P`.
+#[inline(always)]
+fn mul_mod(a: u64, b: u64) -> u64 {
+    let x = a as u128 * b as u128;
+    let r = (x as u64 & P) + (x >> 61) as u64;
+    if r >= P { r - P } else { r }
+}
+
+/// A sequence preprocessed for constant-time substring hashes.
+///
+/// Stores two `u64` per character.
+#[derive(Clone, Debug)]
+pub struct HashedSeq {
+    base: u64,
+    /// `prefix[i]`: hash of the first `i` characters.
+    prefix: Vec<u64>,
+    /// `pow[i]`: `B^i mod P`.
+    pow: Vec<u64>,
+}
+
+impl HashedSeq {
+    /// Preprocess `seq` with a fixed base.
+    pub fn new<'s>(seq: impl Seq<'s>) -> Self {
+        Self::new_with_seed(seq, 0)
+    }
+
+    /// Preprocess `seq` with a random base derived from `seed`.
+    ///
+    /// Only [`HashedSeq`]s with the same seed can be compared, see [`Self::lce_with`].
+    pub fn new_with_seed<'s>(seq: impl Seq<'s>, seed: u32) -> Self {
+        // Exclude small bases, for which short strings trivially collide.
+        let base = SeedHasher::new().hash_one(seed) % (P - 256) + 256;
+        let n = seq.len();
+        let mut prefix = Vec::with_capacity(n + 1);
+        let mut pow = Vec::with_capacity(n + 1);
+        let (mut h, mut p) = (0, 1);
+        prefix.push(h);
+        pow.push(p);
+        for c in seq.iter_bp() {
+            h = mul_mod(h, base) + c as u64 + 1;
+            if h >= P {
+                h -= P;
+            }
+            p = mul_mod(p, base);
+            prefix.push(h);
+            pow.push(p);
+        }
+        Self { base, prefix, pow }
+    }
+
+    /// The length of the underlying sequence.
+    pub fn len(&self) -> usize {
+        self.prefix.len() - 1
+    }
+
+    /// Whether the underlying sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The hash of the substring `seq[range]`, in `O(1)`.
+    ///
+    /// Equal substrings, also at different positions or in different [`HashedSeq`]s with the same seed,
+    /// have equal hashes.
+    #[inline(always)]
+    pub fn hash(&self, range: Range<usize>) -> u64 {
+        let Range { start, end } = range;
+        assert!(start <= end && end <= self.len(), "Range out of bounds.");
+        let h = self.prefix[end] + P - mul_mod(self.prefix[start], self.pow[end - start]);
+        if h >= P { h - P } else { h }
+    }
+
+    /// The longest common extension of positions `i` and `j`:
+    /// the length of the longest common prefix of `seq[i..]` and `seq[j..]`.
+    ///
+    /// Uses `O(log lce)` hash comparisons, and may overestimate with probability at most `n/2^61` per comparison.
+    pub fn lce(&self, i: usize, j: usize) -> usize {
+        self.lce_with(i, self, j)
+    }
+
+    /// The length of the longest common prefix of `self[i..]` and `other[j..]`.
+    ///
+    /// Both must have been constructed with the same seed.
+    pub fn lce_with(&self, i: usize, other: &HashedSeq, j: usize) -> usize {
+        assert_eq!(self.base, other.base, "Seeds must be equal.");
+        let max = (self.len() - i).min(other.len() - j);
+        let eq = |l: usize| self.hash(i..i + l) == other.hash(j..j + l);
+        // Exponential search for an upper bound, followed by binary search.
+        let mut lo = 0;
+        let mut step = 1;
+        while lo + step <= max && eq(lo + step) {
+            lo += step;
+            step *= 2;
+        }
+        let mut hi = (lo + step).min(max + 1);
+        // Invariant: `eq(lo)` holds, `eq(hi)` does not (or `hi > max`).
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if eq(mid) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn substring_hashes() {
+        let ascii: Vec<u8> = (0..500)
+            .map(|_| b"ACGT"[rand::random_range(0..2)])
+            .collect();
+        let seq = PackedSeqVec::from_ascii(&ascii);
+        let hashed = HashedSeq::new(seq.as_slice());
+        assert_eq!(hashed.len(), 500);
+        for _ in 0..1000 {
+            let i = rand::random_range(0..490);
+            let j = rand::random_range(0..490);
+            let l = rand::random_range(0..=10);
+            assert_eq!(
+                hashed.hash(i..i + l) == hashed.hash(j..j + l),
+                ascii[i..i + l] == ascii[j..j + l]
+            );
+        }
+        // Hashes do not depend on the position in the sequence.
+        let other = HashedSeq::new(seq.slice(100..200));
+        assert_eq!(other.hash(10..50), hashed.hash(110..150));
+        assert_eq!(other.hash(0..100), hashed.hash(100..200));
+    }
+
+    #[test]
+    fn lce_naive() {
+        // A periodic sequence has long common extensions.
+        let ascii: Vec<u8> = (0..300)
+            .map(|i| if i % 50 == 0 { b'C' } else { b'A' })
+            .collect();
+        let hashed = HashedSeq::new(ascii.as_slice());
+        for i in 0..300 {
+            for j in (0..300).step_by(7) {
+                let naive = std::iter::zip(&ascii[i..], &ascii[j..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                assert_eq!(hashed.lce(i, j), naive, "i={i} j={j}");
+            }
+        }
+        let other = HashedSeq::new(&ascii[25..]);
+        assert_eq!(hashed.lce_with(75, &other, 0), 225);
+    }
+}
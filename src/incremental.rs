@@ -0,0 +1,138 @@
+//! An incremental `update`/`finalize` interface over whole-sequence hashes.
+
+use crate::KmerHasher;
+use packed_seq::AsciiSeq;
+use std::hash::Hasher;
+
+/// Computes [`KmerHasher::hash_seq`] of a sequence that is fed in parts, like a cryptographic digest.
+///
+/// Input bytes are buffered, since for some hashers (e.g. [`crate::AntiLexHasher`])
+/// the hash depends on the total length, which is only known when finalizing.
+/// Bytes are either interpreted as raw 8-bit characters, as for `hash_seq(&[u8])` ([`Self::new`]),
+/// or as ASCII DNA, as for `hash_seq(AsciiSeq)` ([`Self::new_ascii_dna`]).
+///
+/// Also implements [`std::hash::Hasher`], so it can be used by code generic over that trait.
+///
+/// ```
+/// use seq_hash::{KmerHasher, NtHasher, SeqDigest};
+/// use seq_hash::packed_seq::AsciiSeq;
+/// let hasher = <NtHasher>::new(21);
+/// let mut digest = SeqDigest::new_ascii_dna(&hasher);
+/// digest.update(b"ACGTTG");
+/// digest.update(b"CAT");
+/// assert_eq!(digest.finalize(), hasher.hash_seq(AsciiSeq(b"ACGTTGCAT")));
+/// ```
+#[derive(Clone)]
+pub struct SeqDigest<'h, H> {
+    hasher: &'h H,
+    ascii_dna: bool,
+    buf: Vec<u8>,
+}
+
+impl<'h, H: KmerHasher> SeqDigest<'h, H> {
+    /// Hash the input as raw 8-bit characters. Requires a hasher supporting 8-bit alphabets, e.g. [`crate::MulHasher`].
+    pub fn new(hasher: &'h H) -> Self {
+        hasher.check_bits_per_char(8).unwrap();
+        Self {
+            hasher,
+            ascii_dna: false,
+            buf: vec![],
+        }
+    }
+
+    /// Hash the input as ASCII DNA, i.e., as a [`packed_seq::AsciiSeq`].
+    pub fn new_ascii_dna(hasher: &'h H) -> Self {
+        Self {
+            hasher,
+            ascii_dna: true,
+            buf: vec![],
+        }
+    }
+
+    /// Append `data` to the input.
+    pub fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.buf.extend_from_slice(data.as_ref());
+    }
+
+    /// Append `data` to the input, by value.
+    pub fn chain_update(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.update(data);
+        self
+    }
+
+    /// The hash of the input so far, without consuming it.
+    pub fn hash(&self) -> u32 {
+        if self.ascii_dna {
+            self.hasher.hash_seq(AsciiSeq(&self.buf))
+        } else {
+            self.hasher.hash_seq(self.buf.as_slice())
+        }
+    }
+
+    /// The hash of the input.
+    pub fn finalize(self) -> u32 {
+        self.hash()
+    }
+
+    /// The hash of the input, after which the input is cleared.
+    pub fn finalize_reset(&mut self) -> u32 {
+        let hash = self.hash();
+        self.reset();
+        hash
+    }
+
+    /// Clear the input.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+    }
+}
+
+impl<H: KmerHasher> Hasher for SeqDigest<'_, H> {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    /// The 32-bit hash, zero-extended.
+    fn finish(&self) -> u64 {
+        self.hash() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AntiLexHasher, MulHasher, NtHasher};
+    use packed_seq::{AsciiSeqVec, SeqVec};
+
+    #[test]
+    fn matches_hash_seq() {
+        let seq = AsciiSeqVec::random(300).seq;
+        let nt = <NtHasher>::new(21);
+        let mul = MulHasher::<false>::new(21);
+        let anti_lex = AntiLexHasher::<false>::new(21);
+        for split in [0, 1, 100, 300] {
+            let (a, b) = seq.split_at(split);
+            let digest = SeqDigest::new_ascii_dna(&nt)
+                .chain_update(a)
+                .chain_update(b);
+            assert_eq!(digest.finalize(), nt.hash_seq(AsciiSeq(&seq)));
+            let digest = SeqDigest::new_ascii_dna(&anti_lex)
+                .chain_update(a)
+                .chain_update(b);
+            assert_eq!(digest.finalize(), anti_lex.hash_seq(AsciiSeq(&seq)));
+
+            let mut digest = SeqDigest::new(&mul);
+            digest.write(a);
+            digest.write(b);
+            assert_eq!(digest.finish(), mul.hash_seq(seq.as_slice()) as u64);
+            assert_eq!(digest.finalize_reset(), mul.hash_seq(seq.as_slice()));
+            assert_eq!(digest.finalize(), mul.hash_seq(&[][..]));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn bytes_need_wide_alphabet() {
+        SeqDigest::new(&<NtHasher>::new(21));
+    }
+}
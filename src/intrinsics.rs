@@ -12,12 +12,15 @@ pub fn table_lookup(t: S, idx: S) -> S {
 fn _table_lookup(t: S, idx: S) -> S {
     unsafe {
         #[cfg(target_arch = "x86")]
-        use core::arch::x86::_mm256_permutevar_ps;
+        use core::arch::x86::{__m256, __m256i, _mm256_permutevar_ps};
         #[cfg(target_arch = "x86_64")]
-        use core::arch::x86_64::_mm256_permutevar_ps;
+        use core::arch::x86_64::{__m256, __m256i, _mm256_permutevar_ps};
         use core::mem::transmute;
 
-        transmute(_mm256_permutevar_ps(transmute(t), transmute(idx)))
+        transmute::<__m256, S>(_mm256_permutevar_ps(
+            transmute::<S, __m256>(t),
+            transmute::<S, __m256i>(idx),
+        ))
     }
 }
 
@@ -49,6 +52,195 @@ fn _table_lookup(t: S, idx: S) -> S {
     }
 }
 
+/// Look up each of the 8 indices in `idx` in the 256-entry table `t`.
+///
+/// Only the low 8 bits of each index are used, so that all loads are in bounds.
+///
+/// Uses an AVX2 gather when available. There is no NEON version: `tbl` only reaches 64 bytes,
+/// and a 1KiB table would need a 16-deep chain, so aarch64 (like all other platforms)
+/// falls back to lane-wise loads.
+#[inline(always)]
+pub fn gather(t: &[u32; 256], idx: S) -> S {
+    _gather(t, idx & S::splat(0xff))
+}
+
+#[inline(always)]
+#[cfg(target_feature = "avx2")]
+fn _gather(t: &[u32; 256], idx: S) -> S {
+    unsafe {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{__m256i, _mm256_i32gather_epi32};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{__m256i, _mm256_i32gather_epi32};
+        use core::mem::transmute;
+
+        transmute::<__m256i, S>(_mm256_i32gather_epi32::<4>(
+            t.as_ptr() as *const i32,
+            transmute::<S, __m256i>(idx),
+        ))
+    }
+}
+
+#[inline(always)]
+#[cfg(not(target_feature = "avx2"))]
+fn _gather(t: &[u32; 256], idx: S) -> S {
+    unsafe { S::new(idx.to_array().map(|i| *t.get_unchecked(i as usize))) }
+}
+
+/// Look up each of the 8 indices in `idx` in the slice `t`, like [`gather`].
+///
+/// # Safety
+/// All indices must be less than `t.len()`, and less than `2^31`.
+#[inline(always)]
+pub(crate) unsafe fn gather_slice(t: &[u32], idx: S) -> S {
+    debug_assert!(idx.to_array().iter().all(|&i| (i as usize) < t.len()));
+    unsafe { _gather_slice(t, idx) }
+}
+
+#[inline(always)]
+#[cfg(target_feature = "avx2")]
+unsafe fn _gather_slice(t: &[u32], idx: S) -> S {
+    unsafe {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{__m256i, _mm256_i32gather_epi32};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{__m256i, _mm256_i32gather_epi32};
+        use core::mem::transmute;
+
+        transmute::<__m256i, S>(_mm256_i32gather_epi32::<4>(
+            t.as_ptr() as *const i32,
+            transmute::<S, __m256i>(idx),
+        ))
+    }
+}
+
+#[inline(always)]
+#[cfg(not(target_feature = "avx2"))]
+unsafe fn _gather_slice(t: &[u32], idx: S) -> S {
+    unsafe { S::new(idx.to_array().map(|i| *t.get_unchecked(i as usize))) }
+}
+
+/// Append the lanes of `vals` for which `mask` is set (all ones) to `out`, preserving their order.
+///
+/// This is a stream-compaction (compress-store) primitive, e.g. for sampling from a SIMD hash stream.
+/// `mask` is typically the result of a lane-wise comparison.
+///
+/// With AVX2, this uses a permutation table and a single unaligned store.
+/// With NEON, each half is compacted using a byte shuffle (`tbl`) and stored separately.
+/// Other platforms fall back to lane-wise pushes.
+///
+/// ```
+/// use seq_hash::{append_filtered, packed_seq::u32x8};
+/// let vals = u32x8::new([10, 11, 12, 13, 14, 15, 16, 17]);
+/// let mask = u32x8::new([0, u32::MAX, 0, 0, u32::MAX, u32::MAX, 0, 0]);
+/// let mut out = vec![];
+/// append_filtered(vals, mask, &mut out);
+/// assert_eq!(out, [11, 14, 15]);
+/// ```
+#[inline(always)]
+pub fn append_filtered(vals: S, mask: S, out: &mut Vec<u32>) {
+    _append_filtered(vals, mask, out)
+}
+
+/// For each 8-bit mask, the indices of its set bits, padded with zeros.
+#[cfg(target_feature = "avx2")]
+const COMPRESS: [[u32; 8]; 256] = {
+    let mut table = [[0; 8]; 256];
+    let mut m = 0;
+    while m < 256 {
+        let mut j = 0;
+        let mut i = 0;
+        while i < 8 {
+            if m & (1 << i) != 0 {
+                table[m][j] = i as u32;
+                j += 1;
+            }
+            i += 1;
+        }
+        m += 1;
+    }
+    table
+};
+
+#[inline(always)]
+#[cfg(target_feature = "avx2")]
+fn _append_filtered(vals: S, mask: S, out: &mut Vec<u32>) {
+    unsafe {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::{__m256i, _mm256_permutevar8x32_epi32, _mm256_storeu_si256};
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::{__m256i, _mm256_permutevar8x32_epi32, _mm256_storeu_si256};
+        use core::mem::transmute;
+
+        let m = mask.to_bitmask() as usize;
+        let perm = COMPRESS[m];
+        let packed = _mm256_permutevar8x32_epi32(
+            transmute::<S, __m256i>(vals),
+            transmute::<[u32; 8], __m256i>(perm),
+        );
+        let len = out.len();
+        out.reserve(8);
+        // Write all 8 lanes, but only keep the selected ones.
+        _mm256_storeu_si256(out.as_mut_ptr().add(len) as *mut __m256i, packed);
+        out.set_len(len + m.count_ones() as usize);
+    }
+}
+
+/// For each 4-bit mask, the byte indices that move the selected `u32` lanes to the front.
+#[cfg(all(target_feature = "neon", not(target_feature = "avx2")))]
+const COMPRESS_BYTES: [[u8; 16]; 16] = {
+    let mut table = [[0; 16]; 16];
+    let mut m = 0;
+    while m < 16 {
+        let mut j = 0;
+        let mut i = 0;
+        while i < 4 {
+            if m & (1 << i) != 0 {
+                let mut b = 0;
+                while b < 4 {
+                    table[m][4 * j + b] = (4 * i + b) as u8;
+                    b += 1;
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        m += 1;
+    }
+    table
+};
+
+#[inline(always)]
+#[cfg(all(target_feature = "neon", not(target_feature = "avx2")))]
+fn _append_filtered(vals: S, mask: S, out: &mut Vec<u32>) {
+    unsafe {
+        use core::arch::aarch64::{uint8x16_t, vld1q_u8, vqtbl1q_u8, vst1q_u8};
+        use core::mem::transmute;
+
+        let m = mask.to_bitmask() as usize;
+        let (lo, hi): (uint8x16_t, uint8x16_t) = transmute(vals);
+        out.reserve(8);
+        let mut len = out.len();
+        for (half, m) in [(lo, m & 0xf), (hi, m >> 4)] {
+            let idx = vld1q_u8(COMPRESS_BYTES[m].as_ptr());
+            vst1q_u8(out.as_mut_ptr().add(len) as *mut u8, vqtbl1q_u8(half, idx));
+            len += m.count_ones() as usize;
+        }
+        out.set_len(len);
+    }
+}
+
+#[inline(always)]
+#[cfg(not(any(target_feature = "avx2", target_feature = "neon")))]
+fn _append_filtered(vals: S, mask: S, out: &mut Vec<u32>) {
+    let mask = mask.to_array();
+    for (i, v) in vals.to_array().into_iter().enumerate() {
+        if mask[i] != 0 {
+            out.push(v);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +256,40 @@ mod tests {
             [1002, 1000, 1003, 1001, 1000, 1002, 1001, 1000]
         );
     }
+
+    #[test]
+    fn test_gather() {
+        let t: [u32; 256] = std::array::from_fn(|i| 1000 + i as u32);
+        let idx = S::new([2, 0, 255, 1, 128, 2, 17, 0]);
+        let res = gather(&t, idx);
+
+        assert_eq!(
+            res.to_array(),
+            [1002, 1000, 1255, 1001, 1128, 1002, 1017, 1000]
+        );
+
+        // Out-of-range indices wrap around instead of reading past the table.
+        let idx = S::new([256, 257, 511, u32::MAX, 0x1_0000, 3, 0x8000_0001, 255]);
+        assert_eq!(
+            gather(&t, idx).to_array(),
+            [1000, 1001, 1255, 1255, 1000, 1003, 1001, 1255]
+        );
+    }
+
+    #[test]
+    fn test_append_filtered() {
+        let vals = S::new([10, 11, 12, 13, 14, 15, 16, 17]);
+        let mut out = vec![1];
+        for m in 0..256u32 {
+            let mask = S::new(std::array::from_fn(|i| {
+                if m >> i & 1 != 0 { u32::MAX } else { 0 }
+            }));
+            out.truncate(1);
+            append_filtered(vals, mask, &mut out);
+            let expected: Vec<u32> = std::iter::once(1)
+                .chain((0..8).filter(|i| m >> i & 1 != 0).map(|i| 10 + i))
+                .collect();
+            assert_eq!(out, expected, "m={m:08b}");
+        }
+    }
 }
@@ -0,0 +1,197 @@
+//! Marker traits for the algebraic structure of hashers, so generic code can require exactly what it needs.
+
+use crate::KmerHasher;
+use crate::colex::ColexHasher;
+use crate::multiply_shift::MultiplyShiftHasher;
+use crate::neighbors::SubstitutionHasher;
+use crate::nthash::{CharHasher, RuntimeCanonical};
+use crate::sip::SipKmerHasher;
+use packed_seq::Seq;
+
+/// A hasher that combines per-character hashes, so that states of strings of any length can be
+/// extended by one character and concatenated in `O(1)`, and (via [`SubstitutionHasher`]) updated under substitutions.
+///
+/// States are those of [`SubstitutionHasher`]: for a string of length `k`,
+/// the state built from [`Self::empty_state`] equals [`SubstitutionHasher::kmer_state`],
+/// and [`SubstitutionHasher::finish`] gives the hash of the k-mer.
+/// Implemented by [`crate::NtHasher`], [`crate::MulHasher`], [`crate::TableHasher`], and their runtime-canonical variants.
+pub trait RollingHasher: SubstitutionHasher {
+    /// The state of the empty string.
+    fn empty_state(&self) -> Self::State;
+
+    /// The state of the string of length `len` with state `state`, followed by character `b`.
+    fn extend(&self, state: Self::State, len: usize, b: u8) -> Self::State;
+
+    /// The state of the concatenation of a string of length `a_len` with state `a`
+    /// and a string of length `b_len` with state `b`.
+    fn concat(&self, a: Self::State, a_len: usize, b: Self::State, b_len: usize) -> Self::State;
+}
+
+/// A hasher that hashes each k-mer as a whole, independently of its neighbours.
+///
+/// Only the hashes of complete k-mers are defined, but any single k-mer can be hashed directly in `O(1)`.
+/// Implemented by [`MultiplyShiftHasher`], [`SipKmerHasher`], and [`ColexHasher`], which only support 2-bit DNA and `k <= 32`.
+pub trait BlockHasher: KmerHasher {
+    /// The hash of `kmer`, which must have length `k`.
+    fn hash_kmer<'s>(&self, kmer: impl Seq<'s>) -> u32;
+}
+
+// With `R` the rotation, the forward state of `b_0 .. b_{n-1}` is `xor_i rotl(f[b_i], (n-1-i)*R)`,
+// and the reverse-complement state is `xor_i rotl(c[b_i], i*R)`.
+
+#[inline(always)]
+fn char_extend<CH: CharHasher>(h: &CH, (fw, rc): (u32, u32), len: usize, b: u8) -> (u32, u32) {
+    (
+        fw.rotate_left(CH::R) ^ h.f(b),
+        rc ^ h.c(b).rotate_left((len as u32).wrapping_mul(CH::R)),
+    )
+}
+
+#[inline(always)]
+fn char_concat<CH: CharHasher>(
+    (a_fw, a_rc): (u32, u32),
+    a_len: usize,
+    (b_fw, b_rc): (u32, u32),
+    b_len: usize,
+) -> (u32, u32) {
+    (
+        a_fw.rotate_left((b_len as u32).wrapping_mul(CH::R)) ^ b_fw,
+        a_rc ^ b_rc.rotate_left((a_len as u32).wrapping_mul(CH::R)),
+    )
+}
+
+impl<CH: CharHasher> RollingHasher for CH {
+    #[inline(always)]
+    fn empty_state(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    #[inline(always)]
+    fn extend(&self, state: (u32, u32), len: usize, b: u8) -> (u32, u32) {
+        char_extend(self, state, len, b)
+    }
+
+    #[inline(always)]
+    fn concat(&self, a: (u32, u32), a_len: usize, b: (u32, u32), b_len: usize) -> (u32, u32) {
+        char_concat::<CH>(a, a_len, b, b_len)
+    }
+}
+
+impl<CH: CharHasher> RollingHasher for RuntimeCanonical<CH> {
+    #[inline(always)]
+    fn empty_state(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    #[inline(always)]
+    fn extend(&self, state: (u32, u32), len: usize, b: u8) -> (u32, u32) {
+        char_extend(self.inner(), state, len, b)
+    }
+
+    #[inline(always)]
+    fn concat(&self, a: (u32, u32), a_len: usize, b: (u32, u32), b_len: usize) -> (u32, u32) {
+        char_concat::<CH>(a, a_len, b, b_len)
+    }
+}
+
+/// The packed code of `kmer`, or its canonical code when `canonical` is set, as in [`crate::canonical_kmer_codes`].
+#[inline(always)]
+fn kmer_code<'s>(hasher: &impl KmerHasher, kmer: impl Seq<'s>, canonical: bool) -> u64 {
+    assert_eq!(kmer.len(), hasher.k(), "Expected a k-mer of length k.");
+    hasher.check_alphabet(kmer).unwrap();
+    let fw = kmer.as_u64();
+    if canonical {
+        fw.min(kmer.revcomp_as_u64())
+    } else {
+        fw
+    }
+}
+
+impl<const CANONICAL: bool> BlockHasher for MultiplyShiftHasher<CANONICAL> {
+    #[inline(always)]
+    fn hash_kmer<'s>(&self, kmer: impl Seq<'s>) -> u32 {
+        self.hash_code(kmer_code(self, kmer, CANONICAL))
+    }
+}
+
+impl<const CANONICAL: bool> BlockHasher for SipKmerHasher<CANONICAL> {
+    #[inline(always)]
+    fn hash_kmer<'s>(&self, kmer: impl Seq<'s>) -> u32 {
+        self.hash_code(kmer_code(self, kmer, CANONICAL))
+    }
+}
+
+impl BlockHasher for ColexHasher {
+    #[inline(always)]
+    fn hash_kmer<'s>(&self, kmer: impl Seq<'s>) -> u32 {
+        // Map the packed `ACTG` encoding to `ACGT` rank in each 2-bit group.
+        let code = kmer_code(self, kmer, false) as u32;
+        code ^ (code >> 1 & 0x5555_5555)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DynMulHasher, MulHasher, NtHasher};
+    use packed_seq::{AsciiSeq, PackedSeqVec, SeqVec};
+
+    fn check_rolling<H: RollingHasher<State = (u32, u32)>>(hasher: &H) {
+        let k = hasher.k();
+        let seq = PackedSeqVec::random(200);
+        let seq = seq.as_slice();
+        for (i, hash) in hasher.hash_kmers_scalar(seq).enumerate() {
+            let kmer = seq.slice(i..i + k);
+            let mut state = hasher.empty_state();
+            for (len, b) in kmer.iter_bp().enumerate() {
+                state = hasher.extend(state, len, b);
+            }
+            assert_eq!(state, hasher.kmer_state(kmer));
+            assert_eq!(hasher.finish(state), hash);
+
+            for split in 0..=k {
+                let build = |part: packed_seq::PackedSeq| {
+                    part.iter_bp()
+                        .enumerate()
+                        .fold(hasher.empty_state(), |s, (len, b)| hasher.extend(s, len, b))
+                };
+                let a = build(kmer.slice(0..split));
+                let b = build(kmer.slice(split..k));
+                assert_eq!(hasher.concat(a, split, b, k - split), state);
+            }
+        }
+    }
+
+    #[test]
+    fn rolling() {
+        for k in [1, 5, 21, 40] {
+            check_rolling(&NtHasher::<true>::new(k));
+            check_rolling(&NtHasher::<false, 1>::new_with_seed(k, 3));
+            check_rolling(&MulHasher::<true>::new(k));
+            check_rolling(&<DynMulHasher>::new(k, true));
+        }
+    }
+
+    fn check_block<H: BlockHasher>(hasher: &H) {
+        let k = hasher.k();
+        let packed = PackedSeqVec::random(200);
+        let ascii = packed.as_slice().unpack();
+        for (i, hash) in hasher.hash_kmers_scalar(packed.as_slice()).enumerate() {
+            assert_eq!(hasher.hash_kmer(packed.slice(i..i + k)), hash);
+            assert_eq!(hasher.hash_kmer(AsciiSeq(&ascii[i..i + k])), hash);
+        }
+    }
+
+    #[test]
+    fn block() {
+        for k in [1, 7, 16] {
+            check_block(&ColexHasher::new(k));
+        }
+        for k in [1, 7, 21, 32] {
+            check_block(&MultiplyShiftHasher::<true>::new_with_seed(k, 5));
+            check_block(&MultiplyShiftHasher::<false>::new(k));
+            check_block(&SipKmerHasher::<true>::new_with_key(k, [7; 16]));
+            check_block(&SipKmerHasher::<false>::new(k));
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Canonical 2-bit k-mer codes, e.g. as keys for minimal perfect hash construction or to find unique k-mers.
+
+use crate::signature::mix64;
+use crate::{PaddedLayout, S};
+use packed_seq::Seq;
+use std::collections::{HashMap, HashSet};
+
+/// The 2-bit packed canonical codes of all k-mers in `seq`, for `k <= 32`.
+///
+/// Character `i` of a k-mer occupies bits `2i..2i+2`, matching [`Seq::as_u64`].
+/// The canonical code is the minimum of the forward and reverse-complement codes.
+pub fn canonical_kmer_codes<'s>(seq: impl Seq<'s>, k: usize) -> impl ExactSizeIterator<Item = u64> {
+    assert!(0 < k && k <= 32, "k must be in 1..=32.");
+    assert!(
+        seq.bits_per_char() <= 2,
+        "Only DNA sequences are supported."
+    );
+    let mut mapper = code_mapper::<true>(k);
+    let mut it = seq.iter_bp();
+    it.by_ref().take(k - 1).for_each(|a| {
+        mapper(a);
+    });
+    it.map(mapper)
+}
+
+/// A mapper from characters to the 2-bit packed code of the last `len <= 32` characters,
+/// or its canonical code when `CANONICAL` is set.
+///
+/// Codes are as in [`canonical_kmer_codes`]; the first `len-1` outputs only cover a partial k-mer.
+#[inline(always)]
+pub(crate) fn code_mapper<const CANONICAL: bool>(len: usize) -> impl FnMut(u8) -> u64 + Clone {
+    let shift = 2 * (len - 1);
+    let mask = u64::MAX >> (64 - 2 * len);
+    let mut fw = 0u64;
+    let mut rc = 0u64;
+    move |a| {
+        fw = (fw >> 2) | ((a as u64) << shift);
+        if CANONICAL {
+            // ^2 for complement.
+            rc = ((rc << 2) | (a as u64 ^ 2)) & mask;
+            fw.min(rc)
+        } else {
+            fw
+        }
+    }
+}
+
+/// The distinct canonical k-mer codes (see [`canonical_kmer_codes`]) of a set of sequences.
+///
+/// To bound memory, codes are hash-partitioned into `partitions` parts,
+/// and the input is scanned once per part. Each part is deduplicated in memory,
+/// so peak memory is roughly the number of distinct k-mers divided by `partitions`.
+///
+/// Codes are returned sorted within each part, but parts are interleaved.
+/// This is the usual input for minimal perfect hash construction (e.g. PTHash or BBHash).
+pub fn distinct_canonical_kmers<'a, 's: 'a, S: Seq<'s>>(
+    seqs: &'a [S],
+    k: usize,
+    partitions: usize,
+) -> impl Iterator<Item = u64> + 'a {
+    assert!(partitions > 0, "Need at least one partition.");
+    (0..partitions as u64).flat_map(move |part| {
+        let mut set = HashSet::new();
+        for &seq in seqs {
+            set.extend(
+                canonical_kmer_codes(seq, k)
+                    .filter(|&code| mix64(code) % partitions as u64 == part),
+            );
+        }
+        let mut codes: Vec<u64> = set.into_iter().collect();
+        codes.sort_unstable();
+        codes
+    })
+}
+
+/// For each distinct canonical k-mer of `seq`, its code (see [`canonical_kmer_codes`]) and first position,
+/// ordered by position. Requires `k <= 32`.
+pub fn first_occurrences<'s>(seq: impl Seq<'s>, k: usize) -> Vec<(u64, usize)> {
+    first_occurrences_with_counts(seq, k)
+        .into_iter()
+        .map(|(code, pos, _)| (code, pos))
+        .collect()
+}
+
+/// Like [`first_occurrences`], but also returns the number of occurrences of each k-mer on either strand,
+/// as `(code, first_pos, count)`.
+///
+/// K-mers with count `1` are unique, and make good anchors e.g. for assembly polishing.
+pub fn first_occurrences_with_counts<'s>(seq: impl Seq<'s>, k: usize) -> Vec<(u64, usize, usize)> {
+    let mut index = HashMap::<u64, usize>::new();
+    let mut out: Vec<(u64, usize, usize)> = vec![];
+    for (pos, code) in canonical_kmer_codes(seq, k).enumerate() {
+        let i = *index.entry(code).or_insert_with(|| {
+            out.push((code, pos, 0));
+            out.len() - 1
+        });
+        out[i].2 += 1;
+    }
+    out
+}
+
+/// A SIMD mapper from characters to a mask that is all ones when the forward code of the last `k <= 32` characters
+/// is at most their reverse-complement code. The first `k-1` outputs only cover a partial k-mer.
+pub(crate) fn strand_mapper_simd(k: usize) -> impl FnMut(S) -> S {
+    let shift = 2 * (k as u32 - 1);
+    let mask = u64::MAX >> (64 - 2 * k);
+    let (mask_lo, mask_hi) = (S::splat(mask as u32), S::splat((mask >> 32) as u32));
+    let (mut fw_lo, mut fw_hi) = (S::ZERO, S::ZERO);
+    let (mut rc_lo, mut rc_hi) = (S::ZERO, S::ZERO);
+    move |a: S| {
+        fw_lo = (fw_lo >> 2) | (fw_hi << 30);
+        fw_hi = fw_hi >> 2;
+        if shift >= 32 {
+            fw_hi |= a << (shift - 32);
+        } else {
+            fw_lo |= a << shift;
+        }
+        // ^2 for complement.
+        rc_hi = ((rc_hi << 2) | (rc_lo >> 30)) & mask_hi;
+        rc_lo = ((rc_lo << 2) | (a ^ S::splat(2))) & mask_lo;
+        fw_hi.simd_lt(rc_hi) | (fw_hi.simd_eq(rc_hi) & !rc_lo.simd_lt(fw_lo))
+    }
+}
+
+/// For each k-mer of `seq`, whether its forward code is at most its reverse-complement code,
+/// i.e. whether it equals its canonical code (see [`canonical_kmer_codes`]). Requires `k <= 32`.
+///
+/// Returns a bitvector with bit `i % 64` of word `i / 64` set when the `i`th k-mer is canonical.
+/// Computed in SIMD by keeping the forward and reverse-complement codes as pairs of 32-bit halves.
+pub fn strand_flags<'s>(seq: impl Seq<'s>, k: usize) -> Vec<u64> {
+    assert!(0 < k && k <= 32, "k must be in 1..=32.");
+    assert!(
+        seq.bits_per_char() <= 2,
+        "Only DNA sequences are supported."
+    );
+    let flags = seq.par_iter_bp(k).map(strand_mapper_simd(k)).advance(k - 1);
+
+    let mut bits = vec![0u64; flags.num_positions(1).div_ceil(64)];
+    let positions = flags.lane_positions(1);
+    for (flags, (pos, mask)) in flags.it.zip(positions) {
+        let flags = (flags & mask).to_bitmask();
+        for (j, p) in pos.to_array().into_iter().enumerate() {
+            if flags >> j & 1 != 0 {
+                bits[p as usize / 64] |= 1 << (p % 64);
+            }
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn codes_naive() {
+        let seq = PackedSeqVec::random(500);
+        for offset in 0..4 {
+            let seq = seq.slice(offset..500);
+            for k in [1, 2, 7, 31, 32] {
+                let naive: Vec<u64> = (0..=seq.len() - k)
+                    .map(|i| {
+                        let kmer = seq.slice(i..i + k);
+                        kmer.as_u64().min(kmer.revcomp_as_u64())
+                    })
+                    .collect();
+                let codes: Vec<u64> = canonical_kmer_codes(seq, k).collect();
+                assert_eq!(codes, naive, "k={k}");
+            }
+        }
+    }
+
+    #[test]
+    fn distinct() {
+        let seqs: Vec<PackedSeqVec> = (0..20).map(|_| PackedSeqVec::random(300)).collect();
+        let mut slices: Vec<_> = seqs.iter().map(|s| s.as_slice()).collect();
+        // Add exact and reverse-complement duplicates.
+        let rc = seqs[3].as_slice().to_revcomp();
+        slices.push(seqs[0].as_slice());
+        slices.push(rc.as_slice());
+        for k in [3, 15, 32] {
+            let mut expected: Vec<u64> = slices
+                .iter()
+                .flat_map(|&s| canonical_kmer_codes(s, k))
+                .collect();
+            expected.sort_unstable();
+            expected.dedup();
+            for partitions in [1, 7] {
+                let mut codes: Vec<u64> =
+                    distinct_canonical_kmers(&slices, k, partitions).collect();
+                codes.sort_unstable();
+                assert_eq!(codes, expected, "k={k} partitions={partitions}");
+            }
+        }
+    }
+
+    #[test]
+    fn first_occurrences_naive() {
+        let seq = PackedSeqVec::random(400);
+        for k in [1, 4, 21] {
+            let codes: Vec<u64> = canonical_kmer_codes(seq.as_slice(), k).collect();
+            let naive: Vec<(u64, usize, usize)> = codes
+                .iter()
+                .enumerate()
+                .filter(|&(i, c)| !codes[..i].contains(c))
+                .map(|(i, &c)| (c, i, codes.iter().filter(|&&c2| c2 == c).count()))
+                .collect();
+            assert_eq!(first_occurrences_with_counts(seq.as_slice(), k), naive);
+            let first: Vec<(u64, usize)> = naive.iter().map(|&(c, i, _)| (c, i)).collect();
+            assert_eq!(first_occurrences(seq.as_slice(), k), first);
+        }
+        // At most 10 distinct canonical 2-mers.
+        assert!(first_occurrences(seq.as_slice(), 2).len() <= 10);
+    }
+
+    #[test]
+    fn strand_flags_naive() {
+        let seq = PackedSeqVec::random(1000);
+        for len in [0, 1, 20, 100, 1000] {
+            let seq = seq.slice(3..3 + len.min(997));
+            for k in [1, 2, 15, 16, 17, 31, 32] {
+                let flags = strand_flags(seq, k);
+                let n = seq.len().saturating_sub(k - 1);
+                assert_eq!(flags.len(), n.div_ceil(64));
+                for i in 0..n {
+                    let kmer = seq.slice(i..i + k);
+                    let expected = kmer.as_u64() <= kmer.revcomp_as_u64();
+                    assert_eq!(flags[i / 64] >> (i % 64) & 1 != 0, expected, "k={k} i={i}");
+                }
+            }
+        }
+    }
+}
@@ -4,18 +4,24 @@
 //!
 //! The default [`NtHasher`] is canonical.
 //! If that's not needed, [`NtHasher<false>`] will be slightly faster.
-//! For non-DNA sequences with >2-bit alphabets, use [`MulHasher`] instead.
+//! When canonicality is only known at runtime, use [`DynNtHasher`] to avoid compiling both variants.
+//! For non-DNA sequences with >2-bit alphabets, use [`MulHasher`] instead,
+//! or [`TableHasher`] for stronger hashes via a 256-entry lookup table.
 //!
 //! Note that [`KmerHasher`] objects need `k` on their construction, so that they can precompute required constants.
 //! Prefer reusing the same [`KmerHasher`].
 //!
 //! This crate also includes [`AntiLexHasher`], see [this blogpost](https://curiouscoding.nl/posts/practical-minimizers/).
 //!
+//! For content-defined chunking of raw bytes, see [`GearHasher`] and [`GearChunker`].
+//! For algebraic fingerprints of byte windows, see [`RabinHasher`].
+//!
 //! ## Typical usage
 //!
 //! Construct a default [`NtHasher`] via `let hasher = <NtHasher>::new(k)`.
 //! Then call either `hasher.hash_kmers_simd(seq, context)`,
 //! or use the underlying 'mapper' via `hasher.in_out_mapper_simd(seq)`.
+//! The SIMD output can be transformed further via the [`PaddedIt`] combinators, see [`PaddedItExt`].
 //! ```
 //! use seq_hash::packed_seq;
 //! use packed_seq::{AsciiSeqVec, PackedSeqVec, SeqVec};
@@ -54,36 +60,198 @@
 //! let hashes_4: Vec<_> = hasher.hash_kmers_simd(packed_seq.as_slice(), 1).collect();
 //! assert_eq!(hashes_1, hashes_3);
 //! assert_eq!(hashes_1, hashes_4);
+//!
+//! // Or equivalently, via the `HashSeqExt` extension trait.
+//! use seq_hash::HashSeqExt;
+//! assert_eq!(hashes_1, packed_seq.as_slice().kmer_hashes(&hasher));
 //! ```
 
+mod aligned;
+mod anchors;
 mod anti_lex;
+mod batch;
+#[cfg(feature = "bio")]
+mod bio_text;
+#[cfg(feature = "roaring")]
+mod bitmap;
+mod blocks;
+mod bloom;
+mod chunked;
+mod colex;
+mod collisions;
+mod compact;
+mod cuckoo;
+mod cursor;
+mod dedup;
+#[cfg(feature = "blake3")]
+mod digest;
+mod dump;
+mod elias_fano;
+mod error;
+mod ext;
+mod feistel;
+mod fixed;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
+mod gear;
+mod hashable;
+mod hashed;
+mod incremental;
 mod intrinsics;
+mod kinds;
+mod kmers;
+#[cfg(feature = "mphf")]
+mod mphf;
+mod multi;
+mod multiply_shift;
+mod neighbors;
 mod nthash;
+mod overlap;
+mod padded;
+mod partition;
+mod period;
+mod positions;
+mod quotient;
+mod rabin;
+mod registry;
+mod salt;
+mod screen;
+mod seed_index;
+mod signature;
+mod sink;
+mod sip;
+mod small_k;
+mod sorted;
+mod spaced;
+#[cfg(feature = "stats")]
+mod stats;
 #[cfg(test)]
 mod test;
+mod validating;
+mod weighted;
+mod window;
+mod xor_fold;
 
-pub use anti_lex::AntiLexHasher;
-pub use nthash::{MulHasher, NtHasher};
+pub use aligned::{AlignedHashes, hash_kmers_aligned};
+pub use anchors::{anchors, sample_fraction, shared_anchors};
+pub use anti_lex::{AntiLexHasher, Extremum};
+pub use batch::hash_reads_batched;
+#[cfg(feature = "bio")]
+pub use bio_text::BioText;
+#[cfg(feature = "roaring")]
+pub use bitmap::{collect_roaring, intersect_roaring, union_roaring};
+pub use blocks::HashBlocks;
+pub use bloom::BloomFilter;
+pub use chunked::ChunkedHasher;
+pub use colex::ColexHasher;
+pub use collisions::{CollisionReport, audit_collisions};
+pub use compact::{CompactHash, hash_kmers_compact_scalar, hash_kmers_compact_simd};
+pub use cuckoo::CuckooFilter;
+pub use cursor::{KmerCursor, MapperFactory};
+pub use dedup::DuplicateDetector;
+#[cfg(feature = "blake3")]
+pub use digest::kmer_digests;
+pub use dump::{DumpEncoding, read_hashes, write_hashes};
+pub use elias_fano::EliasFanoSet;
+pub use error::HashError;
+pub use ext::{HashSeqExt, hash_ascii_kmers, hash_kmers_into_ptr};
+pub use feistel::FeistelHasher;
+pub use fixed::FixedLenMapper;
+#[cfg(feature = "fuzzing")]
+pub use fuzzing::{
+    HasherConfig, MAX_FUZZ_K, arb_hasher_config, arb_k, arb_packed_seq, arb_seq_k_seed,
+    matches_scalar_reference,
+};
+pub use gear::{GearChunker, GearHasher};
+pub use hashable::HashableSeq;
+pub use hashed::HashedSeq;
+pub use incremental::SeqDigest;
+pub use intrinsics::append_filtered;
+pub use kinds::{BlockHasher, RollingHasher};
+pub use kmers::{
+    canonical_kmer_codes, distinct_canonical_kmers, first_occurrences,
+    first_occurrences_with_counts, strand_flags,
+};
+#[cfg(feature = "mphf")]
+pub use mphf::{KmerMphf, build_kmer_mphf};
+pub use multi::{MultiHasher, MultiNtHasher};
+pub use multiply_shift::MultiplyShiftHasher;
+pub use neighbors::{SubstitutionHasher, hamming_neighbors, snv_hashes};
+pub use nthash::{
+    DynMulHasher, DynNtHasher, DynTableHasher, MulHasher, NtHasher, RevCompHasher,
+    RuntimeCanonical, TableHasher, best_rotation, hash_kmers_combined_scalar,
+    hash_kmers_combined_simd, in_out_mapper_combined_scalar, in_out_mapper_combined_simd,
+    rolling_states_scalar, rolling_states_simd,
+};
+pub use overlap::{OverlapCandidate, overlap_candidates};
+pub use padded::{LanePositions, PaddedItExt, PaddedLayout};
+pub use partition::{PartitionWriter, Partitions};
+pub use period::periodicity;
+pub use positions::hash_at_positions;
+pub use quotient::QuotientFilter;
+pub use rabin::{RabinHasher, is_irreducible};
+pub use registry::hasher_for;
+pub use salt::record_seed;
+pub use screen::{KmerSet, containment, screen};
+pub use seed_index::SeedIndex;
+pub use signature::{ReadHasher, fingerprint_seq, simhash, simhash_distance};
+pub use sink::{HashSink, hash_into_sink, hash_into_sink_chunked};
+pub use sip::SipKmerHasher;
+pub use small_k::SmallKHasher;
+pub use sorted::{
+    difference_sorted, intersect_sorted, merge_sorted, union_sorted, unique_sorted_hashes,
+};
+pub use spaced::SpacedHasher;
+#[cfg(feature = "stats")]
+pub use stats::{HashStats, StatsHasher};
+pub use validating::ValidatingHasher;
+pub use weighted::{
+    hash_kmers_weighted, hash_kmers_weighted_scalar, quality_penalties, weight_hash,
+};
+pub use window::{
+    argmin_hash, min_hash, sliding_min, sliding_min_mapper, sliding_min_scalar, window_complexity,
+    window_ranks, winnow,
+};
+pub use xor_fold::XorFold;
 
 /// Re-export of the `packed-seq` crate.
 pub use packed_seq;
+pub use packed_seq::{ChunkIt, PaddedIt};
+#[cfg(feature = "roaring")]
+pub use roaring;
 
-use packed_seq::{ChunkIt, Delay, PackedNSeq, PaddedIt, Seq};
+use packed_seq::{AsciiSeq, Delay, PackedNSeq, Seq, SeqVec};
+use std::array::from_fn;
+use std::collections::VecDeque;
 use std::iter::{repeat, zip};
+use std::num::NonZeroU32;
+use std::ops::{ControlFlow, Range};
 
 type S = packed_seq::u32x8;
 
+/// The default number of k-mers per chunk for [`KmerHasher::hash_kmers_chunked_into`] and [`hash_into_sink_chunked`].
+///
+/// At 64 Ki k-mers, the 8 SIMD lanes of a chunk read 16 KiB of packed DNA and write 256 KiB of hashes.
+pub const DEFAULT_CHUNK_LEN: usize = 1 << 16;
+
 /// A hasher that can hash all k-mers in a string.
 ///
 /// Note that a `KmerHasher` must be initialized with a specific `k`,
 /// so that it can precompute associated constants.
+///
+/// All `hash_kmers_*` and `hash_valid_kmers_*` methods yield no hashes for sequences shorter than `k`.
 pub trait KmerHasher {
-    /// True when the hash function is invariant under reverse-complement.
+    /// True when the hash function is always invariant under reverse-complement.
+    ///
+    /// This is only a compile-time hint: hashers with runtime canonicality (e.g. [`DynNtHasher`])
+    /// set it to `false` even when they return canonical hashes.
+    /// [`Self::is_canonical()`] is authoritative, and generic code must branch on it rather than on this constant.
     const CANONICAL: bool;
 
     fn new(k: usize) -> Self;
 
-    /// Helper function returning [`Self::CANONICAL`].
+    /// Whether this hasher is invariant under reverse-complement.
+    /// Defaults to [`Self::CANONICAL`]; wrappers must forward it to the wrapped hasher.
     #[inline(always)]
     fn is_canonical(&self) -> bool {
         Self::CANONICAL
@@ -92,6 +260,32 @@ pub trait KmerHasher {
     /// The value of `k` for this hasher.
     fn k(&self) -> usize;
 
+    /// The maximum number of bits per character this hasher supports.
+    /// Defaults to 8, i.e. any alphabet.
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        8
+    }
+
+    /// Check that this hasher can handle the alphabet of `seq`.
+    #[inline(always)]
+    fn check_alphabet<'s>(&self, seq: impl Seq<'s>) -> Result<(), HashError> {
+        self.check_bits_per_char(seq.bits_per_char())
+    }
+
+    /// Check that this hasher can handle an alphabet with `bits_per_char` bits per character.
+    #[inline(always)]
+    fn check_bits_per_char(&self, bits_per_char: usize) -> Result<(), HashError> {
+        if bits_per_char <= self.max_bits_per_char() {
+            Ok(())
+        } else {
+            Err(HashError::UnsupportedAlphabet {
+                bits_per_char,
+                max_bits_per_char: self.max_bits_per_char(),
+            })
+        }
+    }
+
     /// The delay of the 'out' character passed to the `in_out_mapper` functions.
     /// Defaults to `k-1`.
     #[inline(always)]
@@ -110,6 +304,48 @@ pub trait KmerHasher {
     /// `seq` is only used to ensure that the hasher can handle the underlying alphabet.
     fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S;
 
+    /// Checked version of [`Self::in_out_mapper_scalar`] that returns an error instead of panicking
+    /// when the alphabet of `seq` is not supported.
+    #[inline(always)]
+    fn try_in_out_mapper_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+    ) -> Result<impl FnMut((u8, u8)) -> u32, HashError> {
+        self.check_alphabet(seq)?;
+        Ok(self.in_out_mapper_scalar(seq))
+    }
+
+    /// Checked version of [`Self::in_out_mapper_simd`] that returns an error instead of panicking
+    /// when the alphabet of `seq` is not supported.
+    #[inline(always)]
+    fn try_in_out_mapper_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+    ) -> Result<impl FnMut((S, S)) -> S, HashError> {
+        self.check_alphabet(seq)?;
+        Ok(self.in_out_mapper_simd(seq))
+    }
+
+    /// Like [`Self::in_out_mapper_scalar`], but also returns the incoming base with each hash,
+    /// for callers that need both without iterating `seq` twice.
+    #[inline(always)]
+    fn in_out_mapper_with_base_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+    ) -> impl FnMut((u8, u8)) -> (u8, u32) {
+        let mut mapper = self.in_out_mapper_scalar(seq);
+        move |(a, r)| (a, mapper((a, r)))
+    }
+
+    /// SIMD version of [`Self::in_out_mapper_with_base_scalar`].
+    #[inline(always)]
+    fn in_out_mapper_with_base_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> (S, S) {
+        let mut mapper = self.in_out_mapper_simd(seq);
+        move |(a, r)| (a, mapper((a, r)))
+    }
+
+    /// Like [`Self::in_out_mapper_scalar`], but returns `u32::MAX` for ambiguous kmers,
+    /// and remaps valid kmers hashing to `u32::MAX` to `u32::MAX - 1`.
     fn in_out_mapper_ambiguous_scalar<'s>(
         &self,
         nseq: PackedNSeq<'s>,
@@ -126,10 +362,15 @@ pub trait KmerHasher {
                 false
             };
             i += 1;
-            if ambiguous { u32::MAX } else { hash }
+            if ambiguous {
+                u32::MAX
+            } else {
+                hash.min(u32::MAX - 1)
+            }
         }
     }
 
+    /// SIMD version of [`Self::in_out_mapper_ambiguous_scalar`].
     #[inline(always)]
     fn in_out_mapper_ambiguous_simd<'s>(
         &self,
@@ -141,7 +382,7 @@ pub trait KmerHasher {
         move |(a, r)| {
             let hash = mapper((a, r));
             let ambiguous = ambiguous.it.next().unwrap();
-            ambiguous.blend(S::MAX, hash)
+            ambiguous.blend(S::MAX, hash.min(S::splat(u32::MAX - 1)))
         }
     }
 
@@ -164,6 +405,74 @@ pub trait KmerHasher {
         zip(add, remove).map(mapper)
     }
 
+    /// A [`KmerCursor`] over all k-mer hashes in `seq`, like [`Self::hash_kmers_scalar`],
+    /// that can seek to any k-mer in `O(k)` time.
+    ///
+    /// Requires `self.delay() <= k-1`.
+    #[inline(always)]
+    fn kmer_cursor<'h, 's, Q: Seq<'s> + 'h>(
+        &'h self,
+        seq: Q,
+    ) -> KmerCursor<Q, impl MapperFactory + 'h> {
+        KmerCursor::new(seq, self.k(), self.delay().0, move || {
+            self.in_out_mapper_scalar(seq)
+        })
+    }
+
+    /// A scalar iterator over all k-mer hashes in `seq`, reserving `0` for absent values.
+    ///
+    /// Hashes equal to `0` are mapped to `1`, so that e.g. `Option<NonZeroU32>` takes only 4 bytes.
+    #[inline(always)]
+    fn hash_kmers_nonzero_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+    ) -> impl ExactSizeIterator<Item = NonZeroU32> {
+        self.hash_kmers_scalar(seq)
+            .map(|h| NonZeroU32::new(h).unwrap_or(NonZeroU32::MIN))
+    }
+
+    /// A SIMD-parallel iterator over all k-mer hashes in `seq`, where hashes equal to `0` are mapped to `1`.
+    ///
+    /// See [`Self::hash_kmers_nonzero_scalar`].
+    #[inline(always)]
+    fn hash_kmers_nonzero_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<S>> {
+        self.hash_kmers_simd(seq, context).map(|h: S| h.max(S::ONE))
+    }
+
+    /// A scalar iterator over all k-mer hashes of a stream of 2-bit encoded bases.
+    ///
+    /// For sources that are not [`packed_seq`] types, such as decoded records or generators.
+    /// Hashes equal those of [`Self::hash_kmers_scalar`] on the corresponding [`packed_seq::PackedSeq`].
+    #[inline(always)]
+    fn hash_kmers_iter(&self, bases: impl IntoIterator<Item = u8>) -> impl Iterator<Item = u32> {
+        let delay = self.delay().0;
+        let mut buf = VecDeque::with_capacity(delay + 1);
+        let in_out = bases.into_iter().map(move |a| {
+            debug_assert!(a < 4, "Bases must be 2-bit encoded.");
+            buf.push_back(a);
+            let r = if buf.len() > delay {
+                buf.pop_front().unwrap()
+            } else {
+                0
+            };
+            (a, r)
+        });
+        hash_in_out(self, in_out)
+    }
+
+    /// A scalar iterator over all k-mer hashes of a custom [`HashableSeq`].
+    ///
+    /// Hashes equal those of [`Self::hash_kmers_scalar`] on a [`Seq`] with the same characters.
+    #[inline(always)]
+    fn hash_kmers_hashable<'s>(&self, seq: impl HashableSeq<'s>) -> impl Iterator<Item = u32> {
+        self.check_bits_per_char(seq.char_bits()).unwrap();
+        hash_in_out(self, seq.iter_chars_delayed(self.delay()))
+    }
+
     /// A SIMD-parallel iterator over all k-mer hashes in `seq`.
     #[inline(always)]
     fn hash_kmers_simd<'s>(&self, seq: impl Seq<'s>, context: usize) -> PaddedIt<impl ChunkIt<S>> {
@@ -174,8 +483,264 @@ pub trait KmerHasher {
             .advance(k - 1)
     }
 
+    /// The hashes of 8 independent k-mers of length exactly `k`, one per lane, as [`Self::hash_kmers_scalar`] returns for each.
+    ///
+    /// The k-mers are rolled in parallel in `k` SIMD steps,
+    /// for query-side lookups where per-k-mer latency rather than stream throughput matters.
+    /// Takes e.g. `&[u8]` for hashers supporting 8-bit characters, or [`AsciiSeq`] or [`packed_seq::PackedSeq`] for DNA.
+    #[inline(always)]
+    fn hash_kmers_batch8<'s, Q: Seq<'s>>(&self, kmers: [Q; 8]) -> S {
+        let k = self.k();
+        assert!(
+            kmers.iter().all(|kmer| kmer.len() == k),
+            "All k-mers must have length k={k}."
+        );
+        let delay = self.delay().0;
+        let mut mapper = self.in_out_mapper_simd(kmers[0]);
+        let mut h = S::splat(0);
+        for i in 0..k {
+            let a = S::new(from_fn(|j| kmers[j].get(i) as u32));
+            let r = if i >= delay {
+                S::new(from_fn(|j| kmers[j].get(i - delay) as u32))
+            } else {
+                S::splat(0)
+            };
+            h = mapper((a, r));
+        }
+        h
+    }
+
+    /// The number of k-mers of `seq`, i.e., `len - k + 1`, or `0` when `seq` is shorter than `k`.
+    #[inline(always)]
+    fn num_kmers<'s>(&self, seq: impl Seq<'s>) -> usize {
+        (seq.len() + 1).saturating_sub(self.k())
+    }
+
+    /// A scalar iterator over the hashes of the k-mers of `seq` starting at positions in `range`.
+    ///
+    /// Equals the corresponding part of [`Self::hash_kmers_scalar`] on all of `seq`, without hashing the other k-mers:
+    /// the `k-1` characters before `range.end` are rolled in internally.
+    /// Disjoint ranges covering `0..self.num_kmers(seq)` thus split the work of hashing `seq`,
+    /// e.g. over distributed workers, without overlap bookkeeping.
+    #[inline(always)]
+    fn hash_kmer_range_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        range: Range<usize>,
+    ) -> impl ExactSizeIterator<Item = u32> {
+        self.hash_kmers_scalar(kmer_range_slice(self, seq, range))
+    }
+
+    /// SIMD version of [`Self::hash_kmer_range_scalar`], like [`Self::hash_kmers_simd`] on the k-mers in `range`.
+    #[inline(always)]
+    fn hash_kmer_range_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        range: Range<usize>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<S>> {
+        self.hash_kmers_simd(kmer_range_slice(self, seq, range), context)
+    }
+
+    /// All k-mer hashes of `seq` in order, computed with [`Self::hash_kmers_simd`] on consecutive chunks of `chunk_len` k-mers.
+    ///
+    /// On a single long sequence the 8 SIMD lanes are far apart, and their reads and writes can miss the cache.
+    /// Within a chunk they are only `chunk_len / 8` apart; chunks overlap by `k-1` characters.
+    /// [`DEFAULT_CHUNK_LEN`] is a reasonable default; tune it to the L1/L2 sizes of the CPU.
+    /// `out` is overwritten.
+    fn hash_kmers_chunked_into<'s>(&self, seq: impl Seq<'s>, chunk_len: usize, out: &mut Vec<u32>) {
+        assert!(chunk_len > 0, "Chunk length must be positive.");
+        let num_kmers = self.num_kmers(seq);
+        out.clear();
+        out.reserve(num_kmers);
+        let mut buf = vec![];
+        for start in (0..num_kmers).step_by(chunk_len) {
+            let end = (start + chunk_len).min(num_kmers);
+            self.hash_kmer_range_simd(seq, start..end, 1)
+                .collect_into(&mut buf);
+            out.extend_from_slice(&buf);
+        }
+    }
+
+    /// Call `f(pos, hash)` for each k-mer of `seq` in order, until it returns [`ControlFlow::Break`].
+    ///
+    /// Returns the `Break` value, or `Continue` when all k-mers were visited.
+    /// Hashes are computed with [`Self::hash_kmers_simd`] in chunks of 1024 k-mers,
+    /// so that stopping early skips hashing the remaining chunks.
+    ///
+    /// ```
+    /// use seq_hash::{KmerHasher, NtHasher, packed_seq::AsciiSeq};
+    /// use std::ops::ControlFlow;
+    /// let hasher = <NtHasher>::new(3);
+    /// let seq = AsciiSeq(b"ACGTACGTTT");
+    /// let target = hasher.hash_seq(AsciiSeq(b"GTT"));
+    /// let first = hasher.for_each_kmer_hash(seq, |pos, h| {
+    ///     if h == target { ControlFlow::Break(pos) } else { ControlFlow::Continue(()) }
+    /// });
+    /// assert_eq!(first, ControlFlow::Break(6));
+    /// ```
+    fn for_each_kmer_hash<'s, B>(
+        &self,
+        seq: impl Seq<'s>,
+        mut f: impl FnMut(usize, u32) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        const CHUNK_LEN: usize = 1024;
+        let num_kmers = self.num_kmers(seq);
+        let mut buf = vec![];
+        for start in (0..num_kmers).step_by(CHUNK_LEN) {
+            let end = (start + CHUNK_LEN).min(num_kmers);
+            self.hash_kmer_range_simd(seq, start..end, 1)
+                .collect_into(&mut buf);
+            for (i, &h) in buf.iter().enumerate() {
+                f(start + i, h)?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The k-mer hashes of each sequence in `seqs`, in order, computed with [`Self::hash_kmers_simd`].
+    ///
+    /// A single scratch buffer is reused for all sequences, so each output is allocated once at its exact size.
+    fn hash_all<V: SeqVec>(&self, seqs: &[V]) -> Vec<Vec<u32>> {
+        let mut buf = vec![];
+        seqs.iter()
+            .map(|seq| {
+                self.hash_kmers_simd(seq.as_slice(), 1)
+                    .collect_into(&mut buf);
+                buf.to_vec()
+            })
+            .collect()
+    }
+
+    /// Feed the k-mer hashes of all sequences in `seqs` to `sink`, as [`hash_into_sink`] does for each of them.
+    fn hash_all_into_sink<V: SeqVec>(&self, seqs: &[V], sink: &mut impl HashSink)
+    where
+        Self: Sized,
+    {
+        for seq in seqs {
+            hash_into_sink(self, seq.as_slice(), sink);
+        }
+    }
+
+    /// A scalar iterator over all k-mer hashes in `seq`, together with a second 'out' character `delay2` positions
+    /// before the last character of each k-mer, or `None` when that lies before the start of `seq`.
+    ///
+    /// For windowed schemes over `w` k-mers, use `delay2 = w+k-1` to get the character leaving the window.
+    #[inline(always)]
+    fn hash_kmers_delayed_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        delay2: Delay,
+    ) -> impl ExactSizeIterator<Item = (u32, Option<u8>)> {
+        // Index of the last character of the current k-mer.
+        let mut i = self.k() - 1;
+        self.hash_kmers_scalar(seq).map(move |hash| {
+            let out2 = (i >= delay2.0).then(|| seq.get(i - delay2.0));
+            i += 1;
+            (hash, out2)
+        })
+    }
+
+    /// A SIMD-parallel iterator over all k-mer hashes in `seq`, together with a second 'out' character `delay2` positions
+    /// before the last character of each k-mer.
+    ///
+    /// For windowed schemes over `w` k-mers, use `delay2 = w+k-1` and `context = w`
+    /// to get the character leaving each window. Requires `delay2 >= self.delay()`.
+    ///
+    /// The second out character is `None` for the first `delay2-(k-1)` k-mers of each lane,
+    /// since it lies before the start of the lane. With `context = w`, this includes the first window of each lane,
+    /// so use `context = w+1` when the character leaving it is needed.
+    #[inline(always)]
+    fn hash_kmers_delayed_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        context: usize,
+        delay2: Delay,
+    ) -> PaddedIt<impl ChunkIt<(S, Option<S>)>> {
+        let k = self.k();
+        let delay = self.delay();
+        assert!(delay.0 <= delay2.0, "Requires delay2 >= delay.");
+        let mut mapper = self.in_out_mapper_simd(seq);
+        // Index of the current character within each lane.
+        let mut i = 0;
+        seq.par_iter_bp_delayed_2(context + k - 1, delay, delay2)
+            .map(
+                #[inline(always)]
+                move |(a, r, r2)| {
+                    let out2 = (i >= delay2.0).then_some(r2);
+                    i += 1;
+                    (mapper((a, r)), out2)
+                },
+            )
+            .advance(k - 1)
+    }
+
+    /// A scalar iterator over all k-mer hashes in the DNA sequence `seq`, together with the number of `C`/`G` bases of each k-mer.
+    ///
+    /// The count is rolled alongside the hash, so e.g. QC pipelines get both from a single pass.
+    #[inline(always)]
+    fn hash_kmers_with_gc_scalar<'s>(
+        &self,
+        seq: impl Seq<'s>,
+    ) -> impl ExactSizeIterator<Item = (u32, u32)> {
+        assert!(
+            seq.bits_per_char() <= 2,
+            "Only DNA sequences are supported."
+        );
+        let k = self.k();
+        // `C` and `G` are the 2-bit codes with the low bit set.
+        let mut add = seq.iter_bp();
+        let mut gc: u32 = add.by_ref().take(k - 1).map(|a| a as u32 & 1).sum();
+        let gc_counts = zip(add, seq.iter_bp()).map(move |(a, r)| {
+            gc += a as u32 & 1;
+            let out = gc;
+            gc -= r as u32 & 1;
+            out
+        });
+        zip(self.hash_kmers_scalar(seq), gc_counts)
+    }
+
+    /// A SIMD-parallel iterator over all k-mer hashes in the DNA sequence `seq`,
+    /// together with the number of `C`/`G` bases of each k-mer. See [`Self::hash_kmers_with_gc_scalar`].
+    ///
+    /// Requires `self.delay() <= k-1`.
+    #[inline(always)]
+    fn hash_kmers_with_gc_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<(S, S)>> {
+        assert!(
+            seq.bits_per_char() <= 2,
+            "Only DNA sequences are supported."
+        );
+        let k = self.k();
+        let delay = self.delay();
+        assert!(delay.0 < k, "Requires delay <= k-1.");
+        let mut mapper = self.in_out_mapper_simd(seq);
+        let mut gc = S::ZERO;
+        let mut t = 0;
+        seq.par_iter_bp_delayed_2(context + k - 1, delay, Delay(k - 1))
+            .map(
+                #[inline(always)]
+                move |(a, r, first)| {
+                    let hash = mapper((a, r));
+                    gc += a & S::ONE;
+                    let out = gc;
+                    // Before the first full k-mer of the lane, `first` lies before the lane.
+                    if t >= k - 1 {
+                        gc -= first & S::ONE;
+                    }
+                    t += 1;
+                    (hash, out)
+                },
+            )
+            .advance(k - 1)
+    }
+
     /// An iterator over all k-mer hashes in `seq`.
     /// Ambiguous kmers get hash `u32::MAX`.
+    /// Valid kmers that would hash to `u32::MAX` get `u32::MAX - 1` instead, so the two never collide.
     #[inline(always)]
     fn hash_valid_kmers_scalar<'s>(
         &self,
@@ -206,17 +771,28 @@ pub trait KmerHasher {
                 },
             );
 
-        zip(zip(a, r), nseq.ambiguous.iter_kmer_ambiguity(k)).map(
+        // `iter_kmer_ambiguity` only supports small `k`, but short sequences have no k-mers anyway.
+        let ambiguity = if nseq.seq.len() < k {
+            nseq.ambiguous.slice(0..0).iter_kmer_ambiguity(1)
+        } else {
+            nseq.ambiguous.iter_kmer_ambiguity(k)
+        };
+        zip(zip(a, r), ambiguity).map(
             #[inline(always)]
             move |(ar, ambiguous)| {
                 let hash = mapper(ar);
-                if ambiguous { u32::MAX } else { hash }
+                if ambiguous {
+                    u32::MAX
+                } else {
+                    hash.min(u32::MAX - 1)
+                }
             },
         )
     }
 
     /// A SIMD-parallel iterator over all k-mer hashes in `seq`.
     /// Ambiguous kmers get hash `u32::MAX`.
+    /// Valid kmers that would hash to `u32::MAX` get `u32::MAX - 1` instead, so the two never collide.
     #[inline(always)]
     fn hash_valid_kmers_simd<'s, 't>(
         &'t self,
@@ -237,7 +813,7 @@ pub trait KmerHasher {
                     // SAFETY: these iterators have the same length.
                     let is_ambiguous = unsafe { ambiguity_it.it.next().unwrap_unchecked() };
                     let hash = hash_mapper((a, r));
-                    is_ambiguous.blend(S::MAX, hash)
+                    is_ambiguous.blend(S::MAX, hash.min(S::splat(u32::MAX - 1)))
                 },
             )
             .advance(k - 1)
@@ -245,19 +821,113 @@ pub trait KmerHasher {
 
     /// Hash a sequence one character at a time. Ignores `k`.
     ///
-    /// `seq` is only used to ensure that the hasher can handle the underlying alphabet.
-    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32;
+    /// `seq` is only used to ensure that the hasher can handle the underlying alphabet,
+    /// and by some hashers (e.g. [`AntiLexHasher`]) for its length.
+    /// The mapper is `Clone`, so that a fresh copy can be reused, see [`Self::fixed_len_mapper`].
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone;
+
+    /// Like [`Self::mapper`], but also returns the incoming base with each hash.
+    #[inline(always)]
+    fn mapper_with_base<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> (u8, u32) + Clone {
+        let mut mapper = self.mapper(seq);
+        move |a| (a, mapper(a))
+    }
+
+    /// Checked version of [`Self::mapper`] that returns an error instead of panicking
+    /// when the alphabet of `seq` is not supported.
+    #[inline(always)]
+    fn try_mapper<'s>(&self, seq: impl Seq<'s>) -> Result<impl FnMut(u8) -> u32, HashError> {
+        self.check_alphabet(seq)?;
+        Ok(self.mapper(seq))
+    }
 
     /// Hash the given sequence. Ignores `k`.
     ///
     /// This is slightly inefficient because it recomputes the constants based on the sequence length.
+    /// For many sequences of the same length, use [`Self::fixed_len_mapper`] instead.
     #[inline(always)]
     fn hash_seq<'s>(&self, seq: impl Seq<'s>) -> u32 {
         seq.iter_bp().map(self.mapper(seq)).last().unwrap_or(0)
     }
+
+    /// A [`FixedLenMapper`] hashing sequences with the length and alphabet of `template`, like [`Self::hash_seq`].
+    ///
+    /// Length-dependent constants and alphabet checks are done once, here.
+    #[inline(always)]
+    fn fixed_len_mapper<'s>(
+        &self,
+        template: impl Seq<'s>,
+    ) -> FixedLenMapper<impl FnMut(u8) -> u32 + Clone> {
+        FixedLenMapper::new(
+            template.len(),
+            template.bits_per_char(),
+            self.mapper(template),
+        )
+    }
+
     /// Hash all non-empty prefixes of the given sequence. Ignores `k`.
     #[inline(always)]
     fn hash_prefixes<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
         seq.iter_bp().map(self.mapper(seq))
     }
+
+    /// A SIMD-parallel iterator over the hashes of all non-empty prefixes of `seq`. Ignores `k`.
+    ///
+    /// Lane `j` yields the hashes of the prefixes ending in the `j`th chunk of `seq`.
+    /// The default implementation transposes the output of [`Self::hash_prefixes`];
+    /// the ntHash-style hashers override it with a SIMD implementation.
+    #[inline(always)]
+    fn hash_prefixes_simd<'s>(&self, seq: impl Seq<'s>) -> PaddedIt<impl ChunkIt<S>> {
+        into_lanes(self.hash_prefixes(seq).collect())
+    }
+}
+
+/// A [`KmerHasher`] that can hash all suffixes of a sequence by rolling in characters from the right.
+///
+/// This needs the structure of the rolling hash, so it is implemented for the ntHash-style hashers
+/// (and wrappers around them), rather than falling back to hashing each suffix separately.
+pub trait SuffixHasher: KmerHasher {
+    /// Hash all non-empty suffixes of the given sequence, from shortest to longest, in linear time. Ignores `k`.
+    ///
+    /// The `i`th value is the hash of `seq[len-1-i..]`, as given by [`KmerHasher::hash_seq`].
+    fn hash_suffixes<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32>;
+}
+
+/// The part of `seq` covering the k-mers starting in `range`.
+#[inline(always)]
+pub(crate) fn kmer_range_slice<'s, Q: Seq<'s>>(
+    hasher: &(impl KmerHasher + ?Sized),
+    seq: Q,
+    range: Range<usize>,
+) -> Q {
+    let num_kmers = hasher.num_kmers(seq);
+    assert!(
+        range.start <= range.end && range.end <= num_kmers,
+        "K-mer range {range:?} is out of bounds for {num_kmers} k-mers."
+    );
+    if range.is_empty() {
+        return seq.slice(0..0);
+    }
+    seq.slice(range.start..range.end + hasher.k() - 1)
+}
+
+/// Hash a stream of `(in, out)` characters, with `out` delayed by [`KmerHasher::delay`].
+///
+/// The alphabet must already have been checked, since the mapper only sees an empty 2-bit sequence.
+#[inline(always)]
+fn hash_in_out<H: KmerHasher + ?Sized>(
+    hasher: &H,
+    in_out: impl Iterator<Item = (u8, u8)>,
+) -> impl Iterator<Item = u32> {
+    let mapper = hasher.in_out_mapper_scalar(AsciiSeq(&[]));
+    in_out.map(mapper).skip(hasher.k() - 1)
+}
+
+/// Lay out `values` in 8 SIMD lanes of consecutive chunks, like [`PaddedIt::collect`] expects.
+fn into_lanes(values: Vec<u32>) -> PaddedIt<impl ChunkIt<S>> {
+    let len = values.len().div_ceil(8);
+    let padding = 8 * len - values.len();
+    let it =
+        (0..len).map(move |i| S::new(from_fn(|j| values.get(j * len + i).copied().unwrap_or(0))));
+    PaddedIt { it, padding }
 }
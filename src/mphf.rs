@@ -0,0 +1,189 @@
+//! A minimal perfect hash function over the canonical k-mers of a sequence set.
+//!
+//! This follows BBHash (Limasset et al., 2017): each level is a bit vector of size `GAMMA * n`,
+//! and keys that collide on a level are passed on to the next level.
+//! It is self-contained, so the `mphf` feature pulls in no dependencies.
+
+use crate::HashError;
+use crate::kmers::{canonical_kmer_codes, distinct_canonical_kmers};
+use crate::signature::mix64;
+use packed_seq::Seq;
+
+/// Size of each level relative to its number of keys.
+const GAMMA: f64 = 2.0;
+/// Give up after this many levels, which is practically unreachable for distinct keys.
+const MAX_LEVELS: usize = 64;
+
+struct Level {
+    bits: Vec<u64>,
+    /// Number of set bits before each word, including all previous levels.
+    ranks: Vec<usize>,
+}
+
+impl Level {
+    #[inline(always)]
+    fn pos(&self, code: u64, level: usize) -> usize {
+        (mix64(code ^ mix64(level as u64)) % (self.bits.len() as u64 * 64)) as usize
+    }
+}
+
+/// A minimal perfect hash function mapping each distinct canonical k-mer of a sequence set to a unique index in `0..len()`.
+///
+/// Build via [`build_kmer_mphf`]. Each k-mer and its reverse complement map to the same index.
+/// K-mers not in the original set map to an arbitrary index or to `None`.
+pub struct KmerMphf {
+    k: usize,
+    len: usize,
+    levels: Vec<Level>,
+}
+
+/// Build a [`KmerMphf`] over the distinct canonical k-mers of `seqs`, for `k <= 32`.
+///
+/// Returns [`HashError::MphfNotConverged`] when keys still collide after the maximum number of levels.
+pub fn build_kmer_mphf<'s, S: Seq<'s>>(seqs: &[S], k: usize) -> Result<KmerMphf, HashError> {
+    let keys: Vec<u64> = distinct_canonical_kmers(seqs, k, 1).collect();
+    let (len, levels) = build_levels(keys, MAX_LEVELS)?;
+    Ok(KmerMphf { k, len, levels })
+}
+
+/// Build the levels over `keys`, returning the number of distinct keys and the levels.
+///
+/// Duplicate keys collide on every level, so they are removed first.
+fn build_levels(mut keys: Vec<u64>, max_levels: usize) -> Result<(usize, Vec<Level>), HashError> {
+    keys.sort_unstable();
+    keys.dedup();
+    let len = keys.len();
+    let mut levels = vec![];
+    let mut offset = 0;
+    while !keys.is_empty() {
+        if levels.len() == max_levels {
+            return Err(HashError::MphfNotConverged { levels: max_levels });
+        }
+        let words = ((keys.len() as f64 * GAMMA) as usize).div_ceil(64).max(1);
+        let mut level = Level {
+            bits: vec![0; words],
+            ranks: vec![],
+        };
+        let mut collisions = vec![0u64; words];
+        for &key in &keys {
+            let p = level.pos(key, levels.len());
+            let (w, b) = (p / 64, 1 << (p % 64));
+            if level.bits[w] & b != 0 {
+                collisions[w] |= b;
+            }
+            level.bits[w] |= b;
+        }
+        for (w, c) in level.bits.iter_mut().zip(&collisions) {
+            *w &= !c;
+        }
+        keys.retain(|&key| {
+            let p = level.pos(key, levels.len());
+            level.bits[p / 64] & (1 << (p % 64)) == 0
+        });
+        level.ranks = level
+            .bits
+            .iter()
+            .scan(offset, |rank, w| {
+                let r = *rank;
+                *rank += w.count_ones() as usize;
+                Some(r)
+            })
+            .collect();
+        offset += level
+            .bits
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        levels.push(level);
+    }
+    debug_assert_eq!(offset, len);
+    Ok((len, levels))
+}
+
+impl KmerMphf {
+    /// The value of `k`.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The number of distinct canonical k-mers, i.e., the size of the index range.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The index of a canonical k-mer code, as returned by [`canonical_kmer_codes`].
+    #[inline(always)]
+    pub fn index_code(&self, code: u64) -> Option<usize> {
+        for (l, level) in self.levels.iter().enumerate() {
+            let p = level.pos(code, l);
+            let (w, b) = (p / 64, p % 64);
+            let word = level.bits[w];
+            if word & (1 << b) != 0 {
+                return Some(level.ranks[w] + (word & ((1 << b) - 1)).count_ones() as usize);
+            }
+        }
+        None
+    }
+
+    /// The index of a single k-mer of length `k`.
+    pub fn index<'s>(&self, kmer: impl Seq<'s>) -> Option<usize> {
+        assert_eq!(kmer.len(), self.k, "K-mer length must equal k.");
+        self.index_code(canonical_kmer_codes(kmer, self.k).next().unwrap())
+    }
+
+    /// The indices of all k-mers in `seq`.
+    pub fn indices<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = Option<usize>> {
+        canonical_kmer_codes(seq, self.k).map(|code| self.index_code(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn minimal_perfect() {
+        let seqs: Vec<PackedSeqVec> = (0..50).map(|_| PackedSeqVec::random(1000)).collect();
+        let slices: Vec<_> = seqs.iter().map(|s| s.as_slice()).collect();
+        for k in [5, 21, 32] {
+            let mphf = build_kmer_mphf(&slices, k).unwrap();
+            let mut seen = vec![false; mphf.len()];
+            for code in distinct_canonical_kmers(&slices, k, 1) {
+                let i = mphf.index_code(code).unwrap();
+                assert!(!seen[i], "k={k}");
+                seen[i] = true;
+            }
+            assert!(seen.iter().all(|&s| s));
+
+            // Both strands map to the same index.
+            let rc = slices[7].to_revcomp();
+            let fw: Vec<_> = mphf.indices(slices[7]).collect();
+            let mut rc: Vec<_> = mphf.indices(rc.as_slice()).collect();
+            rc.reverse();
+            assert_eq!(fw, rc);
+            assert_eq!(mphf.index(slices[7].slice(10..10 + k)), fw[10]);
+        }
+    }
+
+    #[test]
+    fn duplicates_and_failure() {
+        let keys: Vec<u64> = (0..1000).flat_map(|i| [i, i, 7]).collect();
+        let (len, levels) = build_levels(keys.clone(), MAX_LEVELS).unwrap();
+        assert_eq!(len, 1000);
+        let mphf = KmerMphf { k: 1, len, levels };
+        let mut indices: Vec<usize> = (0..1000).map(|i| mphf.index_code(i).unwrap()).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..1000).collect::<Vec<_>>());
+
+        // A single level can not place 1000 keys without collisions.
+        assert!(matches!(
+            build_levels(keys, 1),
+            Err(HashError::MphfNotConverged { levels: 1 })
+        ));
+    }
+}
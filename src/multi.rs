@@ -0,0 +1,157 @@
+//! Eight independent hash functions in a single pass, one per SIMD lane.
+
+use crate::nthash::{CharHasher, NtHasher};
+use crate::{HashError, S};
+use packed_seq::Seq;
+use std::array::from_fn;
+use std::iter::{repeat, zip};
+
+/// Computes 8 differently-seeded versions of a [`CharHasher`] at once, one per SIMD lane.
+///
+/// Each character is broadcast to all lanes, and each lane looks it up in its own table.
+/// Lane `l` of the output equals the hash of `CH::new_with_seed(k, seeds[l])`.
+/// This is a building block for Bloom filters and LSH that need several hash functions.
+#[derive(Clone)]
+pub struct MultiHasher<CH = NtHasher> {
+    k: usize,
+    seeds: [u32; 8],
+    f: Vec<S>,
+    c: Vec<S>,
+    f_rot: Vec<S>,
+    c_rot: Vec<S>,
+    fw_init: S,
+    rc_init: S,
+    _ch: std::marker::PhantomData<CH>,
+}
+
+/// [`MultiHasher`] for [`NtHasher`], with 8 differently-seeded tables.
+pub type MultiNtHasher<const CANONICAL: bool = true, const R: u32 = 7> =
+    MultiHasher<NtHasher<CANONICAL, R>>;
+
+impl<CH: CharHasher> MultiHasher<CH> {
+    /// Use seeds `0..8`.
+    pub fn new(k: usize) -> Self {
+        Self::new_with_seeds(k, from_fn(|l| l as u32))
+    }
+
+    pub fn new_with_seeds(k: usize, seeds: [u32; 8]) -> Self {
+        let hashers: [CH; 8] = seeds.map(|seed| CH::new_with_seed(k, Some(seed)));
+        let table = |g: &dyn Fn(&CH, u8) -> u32| -> Vec<S> {
+            (0..1usize << CH::BITS_PER_CHAR)
+                .map(|b| S::new(from_fn(|l| g(&hashers[l], b as u8))))
+                .collect()
+        };
+        Self {
+            k,
+            seeds,
+            f: table(&|h, b| h.f(b)),
+            c: table(&|h, b| h.c(b)),
+            f_rot: table(&|h, b| h.f_rot(b)),
+            c_rot: table(&|h, b| h.c_rot(b)),
+            fw_init: S::new(from_fn(|l| hashers[l].fw_init())),
+            rc_init: S::new(from_fn(|l| hashers[l].rc_init())),
+            _ch: std::marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The seed used by each lane.
+    pub fn seeds(&self) -> [u32; 8] {
+        self.seeds
+    }
+
+    /// Check that the underlying `CH` can handle the alphabet of `seq`.
+    #[inline(always)]
+    pub fn check_alphabet<'s>(&self, seq: impl Seq<'s>) -> Result<(), HashError> {
+        let bits_per_char = seq.bits_per_char();
+        if bits_per_char <= CH::BITS_PER_CHAR {
+            Ok(())
+        } else {
+            Err(HashError::UnsupportedAlphabet {
+                bits_per_char,
+                max_bits_per_char: CH::BITS_PER_CHAR,
+            })
+        }
+    }
+
+    /// A mapper that should be called with each `(in, out)` character, with delay `k-1`.
+    #[inline(always)]
+    pub fn in_out_mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> S {
+        self.check_alphabet(seq).unwrap();
+        let mut fw = self.fw_init;
+        let mut rc = self.rc_init;
+        let shl = S::splat(CH::R);
+        let shr = S::splat(32 - CH::R);
+
+        move |(a, r)| {
+            let (a, r) = (a as usize, r as usize);
+            let fw_out = ((fw << shl) | (fw >> shr)) ^ self.f[a];
+            fw = fw_out ^ self.f_rot[r];
+            if CH::CANONICAL {
+                let rc_out = ((rc >> shl) | (rc << shr)) ^ self.c_rot[a];
+                rc = rc_out ^ self.c[r];
+                // Wrapping SIMD add
+                fw_out + rc_out
+            } else {
+                fw_out
+            }
+        }
+    }
+
+    /// For each k-mer in `seq`, its 8 hashes.
+    #[inline(always)]
+    pub fn hash_kmers<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = S> {
+        let k = self.k;
+        let mut add = seq.iter_bp();
+        let remove = seq.iter_bp();
+        let mut mapper = self.in_out_mapper(seq);
+        zip(add.by_ref().take(k - 1), repeat(0)).for_each(|a| {
+            mapper(a);
+        });
+        zip(add, remove).map(mapper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KmerHasher, MulHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn lanes_match_seeded() {
+        fn f<CH: CharHasher + KmerHasher>() {
+            let seq = PackedSeqVec::random(1000);
+            for k in [1, 2, 5, 21, 32, 33, 64] {
+                let seeds = from_fn(|l| 31415 * l as u32);
+                let multi = MultiHasher::<CH>::new_with_seeds(k, seeds);
+                let hashes: Vec<S> = multi.hash_kmers(seq.as_slice()).collect();
+                for (l, seed) in seeds.into_iter().enumerate() {
+                    let expected: Vec<u32> = CH::new_with_seed(k, Some(seed))
+                        .hash_kmers_scalar(seq.as_slice())
+                        .collect();
+                    let lane: Vec<u32> = hashes.iter().map(|h| h.to_array()[l]).collect();
+                    assert_eq!(lane, expected, "k={k} lane={l}");
+                }
+            }
+        }
+        f::<NtHasher<true>>();
+        f::<NtHasher<false>>();
+        f::<MulHasher<true>>();
+    }
+
+    #[test]
+    fn unsupported_alphabet() {
+        let multi = MultiNtHasher::<true>::new(5);
+        assert!(multi.check_alphabet(b"ACGTN".as_slice()).is_err());
+        assert!(
+            MultiHasher::<MulHasher>::new(5)
+                .check_alphabet(b"ACGTN".as_slice())
+                .is_ok()
+        );
+    }
+}
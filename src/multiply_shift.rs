@@ -0,0 +1,135 @@
+//! Multiply-add-shift hashing of packed k-mer codes, a strongly universal hash family.
+
+use crate::kmers::code_mapper;
+use crate::{KmerHasher, S};
+use packed_seq::Seq;
+use std::array::from_fn;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+
+type SeedHasher = BuildHasherDefault<DefaultHasher>;
+
+/// Hashes the 2-bit packed code of each k-mer (for `k <= 32`) using multiply-add-shift (Dietzfelbinger, 1996).
+///
+/// For a 64-bit code `x`, the hash is `((a*x + b) mod 2^96) >> 64`, with `a` and `b` drawn from `[0, 2^96)` by the seed.
+/// Over a uniformly random choice of `(a, b)`, this family is 2-independent (strongly universal):
+/// any two distinct codes hash to any given pair of values with probability exactly `2^-64`.
+/// In particular, two distinct k-mers collide with probability `2^-32`.
+///
+/// Unlike [`crate::NtHasher`], the code is hashed as a whole, so its guarantees hold for any input.
+/// The canonical version hashes the minimum of the forward and reverse-complement codes.
+///
+/// Only supports 2-bit DNA sequences ([`packed_seq::AsciiSeq`] and [`packed_seq::PackedSeq`]).
+/// The SIMD version computes the 8 lanes one at a time.
+#[derive(Clone, Copy)]
+pub struct MultiplyShiftHasher<const CANONICAL: bool = true> {
+    k: usize,
+    a: u128,
+    b: u128,
+}
+
+const MASK_96: u128 = (1 << 96) - 1;
+
+impl<const CANONICAL: bool> MultiplyShiftHasher<CANONICAL> {
+    /// Create a new [`MultiplyShiftHasher`] for kmers of length `k <= 32`.
+    pub fn new(k: usize) -> Self {
+        Self::new_with_seed(k, 0)
+    }
+
+    /// Create a new [`MultiplyShiftHasher`] with parameters `(a, b)` derived from `seed`.
+    pub fn new_with_seed(k: usize, seed: u32) -> Self {
+        assert!(0 < k && k <= 32, "MultiplyShiftHasher requires k <= 32.");
+        let hasher = SeedHasher::new();
+        let word = |i: u32| hasher.hash_one((seed, i)) as u128;
+        Self {
+            k,
+            a: (word(0) | word(1) << 64) & MASK_96,
+            b: (word(2) | word(3) << 64) & MASK_96,
+        }
+    }
+
+    /// Hash a packed k-mer code.
+    #[inline(always)]
+    pub fn hash_code(&self, x: u64) -> u32 {
+        ((self.a.wrapping_mul(x as u128).wrapping_add(self.b) & MASK_96) >> 64) as u32
+    }
+}
+
+impl<const CANONICAL: bool> KmerHasher for MultiplyShiftHasher<CANONICAL> {
+    const CANONICAL: bool = CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        2
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        let mut mapper = code_mapper::<CANONICAL>(self.k);
+        move |(a, _r)| self.hash_code(mapper(a))
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        let mut mappers: [_; 8] = from_fn(|_| code_mapper::<CANONICAL>(self.k));
+        move |(a, _r)| {
+            let a = a.to_array();
+            S::new(from_fn(|l| self.hash_code(mappers[l](a[l] as u8))))
+        }
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        assert!(seq.len() <= 32, "MultiplyShiftHasher requires k <= 32.");
+        let mut mapper = code_mapper::<CANONICAL>(seq.len().max(1));
+        move |a| self.hash_code(mapper(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical_kmer_codes;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn hashes_codes() {
+        let seq = PackedSeqVec::random(1000);
+        let seq = seq.as_slice();
+        for k in [1, 2, 16, 31, 32] {
+            let fw = MultiplyShiftHasher::<false>::new_with_seed(k, 31415);
+            let expected: Vec<u32> = (0..=seq.len() - k)
+                .map(|i| fw.hash_code(seq.slice(i..i + k).as_u64()))
+                .collect();
+            assert_eq!(fw.hash_kmers_scalar(seq).collect::<Vec<_>>(), expected);
+            assert_eq!(fw.hash_kmers_simd(seq, 1).collect(), expected);
+            assert_eq!(fw.hash_seq(seq.slice(7..7 + k)), expected[7]);
+
+            let canonical = MultiplyShiftHasher::<true>::new(k);
+            let expected: Vec<u32> = canonical_kmer_codes(seq, k)
+                .map(|code| canonical.hash_code(code))
+                .collect();
+            assert_eq!(
+                canonical.hash_kmers_scalar(seq).collect::<Vec<_>>(),
+                expected
+            );
+            assert_eq!(canonical.hash_kmers_simd(seq, 1).collect(), expected);
+            let mut rc = canonical
+                .hash_kmers_scalar(seq.to_revcomp().as_slice())
+                .collect::<Vec<_>>();
+            rc.reverse();
+            assert_eq!(rc, expected);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! O(1) single-character substitution updates, and Hamming-distance-1 neighbourhoods of k-mers.
+
+use crate::KmerHasher;
+use crate::nthash::{CharHasher, RuntimeCanonical, rolling_states_scalar};
+use packed_seq::Seq;
+use std::rc::Rc;
+
+/// A [`KmerHasher`] whose k-mer hashes can be updated in `O(1)` when a single character is substituted.
+///
+/// The hash of a k-mer is [`Self::finish`] applied to its [`Self::State`],
+/// which is first computed using [`Self::kmer_state`].
+pub trait SubstitutionHasher: KmerHasher {
+    /// The internal (un-combined) state of a k-mer, e.g. the forward and reverse-complement hashes.
+    type State: Copy;
+
+    /// The state of the given k-mer, which must have length `k`.
+    fn kmer_state<'s>(&self, kmer: impl Seq<'s>) -> Self::State;
+
+    /// Update `state` by replacing character `old` at position `pos` of the k-mer by `new`.
+    fn substitute(&self, state: Self::State, pos: usize, old: u8, new: u8) -> Self::State;
+
+    /// The hash corresponding to `state`.
+    fn finish(&self, state: Self::State) -> u32;
+
+    /// The states of all k-mers of `seq`, in order.
+    ///
+    /// By default each state is computed from scratch, taking `O(k)` time per k-mer.
+    fn kmer_states<'s>(&self, seq: impl Seq<'s>) -> Vec<Self::State> {
+        let k = self.k();
+        (0..(seq.len() + 1).saturating_sub(k))
+            .map(|i| self.kmer_state(seq.slice(i..i + k)))
+            .collect()
+    }
+}
+
+// The forward hash is `xor_i rotl(f[b_i], (k-1-i)*R)`,
+// and the reverse-complement hash is `xor_i rotl(c[b_i], i*R)`.
+
+#[inline(always)]
+fn char_kmer_state<'s, CH: CharHasher>(h: &CH, kmer: impl Seq<'s>) -> (u32, u32) {
+    assert_eq!(kmer.len(), h.k(), "Expected a k-mer of length k.");
+    kmer.iter_bp().fold((0u32, 0u32), |(fw, rc), b| {
+        (
+            fw.rotate_left(CH::R) ^ h.f(b),
+            rc.rotate_right(CH::R) ^ h.c_rot(b),
+        )
+    })
+}
+
+#[inline(always)]
+fn char_substitute<CH: CharHasher>(
+    h: &CH,
+    (fw, rc): (u32, u32),
+    pos: usize,
+    old: u8,
+    new: u8,
+) -> (u32, u32) {
+    let fw_rot = ((h.k() - 1 - pos) as u32).wrapping_mul(CH::R);
+    let rc_rot = (pos as u32).wrapping_mul(CH::R);
+    (
+        fw ^ (h.f(old) ^ h.f(new)).rotate_left(fw_rot),
+        rc ^ (h.c(old) ^ h.c(new)).rotate_left(rc_rot),
+    )
+}
+
+impl<CH: CharHasher> SubstitutionHasher for CH {
+    type State = (u32, u32);
+
+    #[inline(always)]
+    fn kmer_state<'s>(&self, kmer: impl Seq<'s>) -> (u32, u32) {
+        char_kmer_state(self, kmer)
+    }
+
+    #[inline(always)]
+    fn substitute(&self, state: (u32, u32), pos: usize, old: u8, new: u8) -> (u32, u32) {
+        char_substitute(self, state, pos, old, new)
+    }
+
+    #[inline(always)]
+    fn finish(&self, (fw, rc): (u32, u32)) -> u32 {
+        if CH::CANONICAL {
+            fw.wrapping_add(rc)
+        } else {
+            fw
+        }
+    }
+
+    fn kmer_states<'s>(&self, seq: impl Seq<'s>) -> Vec<(u32, u32)> {
+        rolling_states_scalar(self, seq).collect()
+    }
+}
+
+impl<CH: CharHasher> SubstitutionHasher for RuntimeCanonical<CH> {
+    type State = (u32, u32);
+
+    #[inline(always)]
+    fn kmer_state<'s>(&self, kmer: impl Seq<'s>) -> (u32, u32) {
+        char_kmer_state(self.inner(), kmer)
+    }
+
+    #[inline(always)]
+    fn substitute(&self, state: (u32, u32), pos: usize, old: u8, new: u8) -> (u32, u32) {
+        char_substitute(self.inner(), state, pos, old, new)
+    }
+
+    #[inline(always)]
+    fn finish(&self, (fw, rc): (u32, u32)) -> u32 {
+        if self.is_canonical() {
+            fw.wrapping_add(rc)
+        } else {
+            fw
+        }
+    }
+
+    fn kmer_states<'s>(&self, seq: impl Seq<'s>) -> Vec<(u32, u32)> {
+        rolling_states_scalar(self.inner(), seq).collect()
+    }
+}
+
+/// Hashes of all `3k` k-mers at Hamming distance 1 from the 2-bit DNA `kmer`, in `O(k)` total time.
+///
+/// Yields `(pos, base, hash)`, where `base` is the 2-bit character substituted at `pos`.
+/// To get the neighbourhood of the k-mer at position `i` of a sequence, pass `seq.slice(i..i + k)`.
+pub fn hamming_neighbors<'s, H: SubstitutionHasher>(
+    hasher: &H,
+    kmer: impl Seq<'s>,
+) -> impl Iterator<Item = (usize, u8, u32)> {
+    assert!(kmer.bits_per_char() <= 2, "Only DNA k-mers are supported.");
+    let state = hasher.kmer_state(kmer);
+    kmer.iter_bp().enumerate().flat_map(move |(pos, old)| {
+        (0..4u8).filter(move |&new| new != old).map(move |new| {
+            let hash = hasher.finish(hasher.substitute(state, pos, old, new));
+            (pos, new, hash)
+        })
+    })
+}
+
+/// For each position of the 2-bit DNA `seq` and each alternative base there,
+/// the hashes of all k-mers covering that position after substituting the base.
+///
+/// Yields `(pos, base, start, hash)`, ordered by `pos`, then `base`, then `start`,
+/// where `hash` is the hash of the k-mer starting at `start` with `base` substituted at `pos`.
+/// Starts range over `pos+1-k..=pos`, clipped to the sequence, so there are up to `3k` hashes per position.
+/// The k-mer states are computed once, and each hash takes `O(1)` time,
+/// e.g. to look up seeds overlapping candidate SNVs in a genotyper.
+pub fn snv_hashes<'s, H: SubstitutionHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+) -> impl Iterator<Item = (usize, u8, usize, u32)> {
+    assert!(
+        seq.bits_per_char() <= 2,
+        "Only DNA sequences are supported."
+    );
+    let k = hasher.k();
+    let states: Rc<[H::State]> = hasher.kmer_states(seq).into();
+    let num_kmers = states.len();
+    (0..seq.len()).flat_map(move |pos| {
+        let old = seq.get(pos);
+        let starts = pos.saturating_sub(k - 1)..(pos + 1).min(num_kmers);
+        let states = states.clone();
+        (0..4u8)
+            .filter(move |&new| new != old)
+            .flat_map(move |new| {
+                let states = states.clone();
+                starts.clone().map(move |start| {
+                    let state = hasher.substitute(states[start], pos - start, old, new);
+                    (pos, new, start, hasher.finish(state))
+                })
+            })
+    })
+}
@@ -1,12 +1,18 @@
 //! NtHash the kmers in a sequence.
 use std::array::from_fn;
+use std::collections::HashSet;
 use std::hash::BuildHasher;
 use std::hash::BuildHasherDefault;
 use std::hash::DefaultHasher;
+use std::iter::zip;
 
 use super::intrinsics;
+use crate::Delay;
 use crate::KmerHasher;
 use crate::S;
+use crate::SuffixHasher;
+use packed_seq::ChunkIt;
+use packed_seq::PaddedIt;
 use packed_seq::Seq;
 use packed_seq::complement_base;
 use packed_seq::u32x8;
@@ -25,7 +31,7 @@ const HASHES_F: [u32; 4] = [
 /// A helper trait that hashes a single character.
 ///
 /// Can be either via [`NtHasher`], which only works for 2-bit alphabets,
-/// or [`MulHasher`], which always works but is slightly slower.
+/// or [`MulHasher`] or [`TableHasher`], which always work but are slightly slower.
 pub trait CharHasher: Clone {
     /// Whether the underlying hasher is invariant under reverse-complement.
     const CANONICAL: bool;
@@ -60,6 +66,13 @@ pub trait CharHasher: Clone {
 
     fn fw_init(&self) -> u32;
     fn rc_init(&self) -> u32;
+    /// The seed, or `None` for the default tables.
+    ///
+    /// Defaults to `None`, for hashers that are not seeded.
+    #[inline(always)]
+    fn seed(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// `u32` variant of NtHash.
@@ -67,7 +80,9 @@ pub trait CharHasher: Clone {
 /// `CANONICAL` by default by summing forward and reverse-complement hash values.
 /// Instead of the classical 1-bit rotation, this rotates by `R=7` bits by default,
 /// to reduce correlation between high bits of consecutive hashes.
+// `repr(C)`, so that the canonical and forward variants have the same layout.
 #[derive(Clone)]
+#[repr(C)]
 pub struct NtHasher<const CANONICAL: bool = true, const R: u32 = 7> {
     k: usize,
     f: [u32; 4],
@@ -80,51 +95,105 @@ pub struct NtHasher<const CANONICAL: bool = true, const R: u32 = 7> {
     simd_c_rot: u32x8,
     fw_init: u32,
     rc_init: u32,
+    seed: Option<u32>,
 }
 
 impl<const CANONICAL: bool, const R: u32> NtHasher<CANONICAL, R> {
+    /// An unseeded hasher. This is a `const fn`, so that hashers for a fixed `k` can be `static`:
+    ///
+    /// ```
+    /// use seq_hash::NtHasher;
+    /// static HASHER: NtHasher = NtHasher::new(21);
+    /// ```
     #[inline(always)]
-    pub fn new(k: usize) -> Self {
-        CharHasher::new(k)
+    pub const fn new(k: usize) -> Self {
+        Self::from_table(k, HASHES_F, None)
     }
     #[inline(always)]
     pub fn new_with_seed(k: usize, seed: u32) -> Self {
         CharHasher::new_with_seed(k, Some(seed))
     }
-}
+    /// A hasher with a seed drawn from the OS random number generator, e.g. to randomize hashes per process.
+    ///
+    /// Use [`Self::seed`] to reproduce it via [`Self::new_with_seed`].
+    #[cfg(feature = "getrandom")]
+    pub fn new_random(k: usize) -> Self {
+        Self::new_with_seed(k, getrandom::u32().expect("Failed to get a random seed."))
+    }
+    /// The seed, or `None` when constructed without one.
+    #[inline(always)]
+    pub fn seed(&self) -> Option<u32> {
+        CharHasher::seed(self)
+    }
+    /// A view of this hasher as a forward hasher, sharing its tables.
+    ///
+    /// The tables do not depend on `CANONICAL`, so code needing both views for the same `k` only needs a single hasher.
+    #[inline(always)]
+    pub fn as_forward(&self) -> &NtHasher<false, R> {
+        // SAFETY: `NtHasher` is `repr(C)` and its fields do not depend on `CANONICAL`.
+        unsafe { &*(self as *const Self as *const NtHasher<false, R>) }
+    }
+    /// A view of this hasher as a canonical hasher, sharing its tables. See [`Self::as_forward`].
+    #[inline(always)]
+    pub fn as_canonical(&self) -> &NtHasher<true, R> {
+        // SAFETY: As for `as_forward`.
+        unsafe { &*(self as *const Self as *const NtHasher<true, R>) }
+    }
 
-impl<const CANONICAL: bool, const R: u32> CharHasher for NtHasher<CANONICAL, R> {
-    const CANONICAL: bool = CANONICAL;
-    const R: u32 = R;
-    const BITS_PER_CHAR: usize = 2;
+    /// Pick the seed (out of 16 candidates) that minimizes the measured correlation between consecutive hashes,
+    /// see [`best_rotation`].
+    ///
+    /// Since `R` is a type parameter, it is not chosen here.
+    /// Instead, use e.g. `NtHasher::<true, { best_rotation(31) }>::new_auto(31)` to also tune `R` at compile time.
+    pub fn new_auto(k: usize) -> Self {
+        let seeds = std::iter::once(None).chain((1..16).map(Some));
+        let best = seeds
+            .min_by_key(|&seed| correlation_score(&Self::new_with_seed_opt(k, seed).f, k, R))
+            .unwrap();
+        Self::new_with_seed_opt(k, best)
+    }
+
+    /// Construct `n` hashers with pairwise distinct tables, using seeds derived from `master_seed`.
+    ///
+    /// Candidate seeds whose tables collide with those of an earlier hasher are rejected.
+    pub fn family(k: usize, n: usize, master_seed: u32) -> Vec<Self> {
+        let hasher = SeedHasher::new();
+        let mut seen = HashSet::new();
+        (0u32..)
+            .map(|i| Self::new_with_seed(k, hasher.hash_one((master_seed, i)) as u32))
+            .filter(|h| seen.insert(h.f))
+            .take(n)
+            .collect()
+    }
 
     #[inline(always)]
-    fn new_with_seed(k: usize, seed: Option<u32>) -> Self {
+    fn new_with_seed_opt(k: usize, seed: Option<u32>) -> Self {
+        CharHasher::new_with_seed(k, seed)
+    }
+
+    /// Construct the hasher from the forward hashes `f` of the 4 bases.
+    const fn from_table(k: usize, f: [u32; 4], seed: Option<u32>) -> Self {
         let rot = k as u32 - 1;
-        let hasher = SeedHasher::new();
-        let f = match seed {
-            None => HASHES_F,
-            Some(seed) => from_fn(|i| hasher.hash_one(HASHES_F[i] ^ seed) as u32),
-        };
-        let c = from_fn(|i| f[complement_base(i as u8) as usize]);
-        let f_rot = f.map(|h| h.rotate_left(rot * R));
-        let c_rot = c.map(|h| h.rotate_left(rot * R));
-        let idx = [0, 1, 2, 3, 0, 1, 2, 3];
-        let simd_f = idx.map(|i| f[i]).into();
-        let simd_c = idx.map(|i| c[i]).into();
-        let simd_f_rot = idx.map(|i| f_rot[i]).into();
-        let simd_c_rot = idx.map(|i| c_rot[i]).into();
+        let mut c = [0; 4];
+        let mut f_rot = [0; 4];
+        let mut c_rot = [0; 4];
+        let mut i = 0;
+        while i < 4 {
+            c[i] = f[complement_base(i as u8) as usize];
+            f_rot[i] = f[i].rotate_left(rot * R);
+            c_rot[i] = c[i].rotate_left(rot * R);
+            i += 1;
+        }
 
         // Initial value of hashing `k-1` zeros.
         let mut fw_init = 0u32;
-        for _ in 0..k - 1 {
-            fw_init = fw_init.rotate_left(Self::R) ^ f[0];
-        }
-
         // Initial value of reverse-complement-hashing `k-1` zeros.
         let mut rc_init = 0u32;
-        for _ in 0..k - 1 {
-            rc_init = rc_init.rotate_right(Self::R) ^ c_rot[0];
+        let mut i = 0;
+        while i < k - 1 {
+            fw_init = fw_init.rotate_left(R) ^ f[0];
+            rc_init = rc_init.rotate_right(R) ^ c_rot[0];
+            i += 1;
         }
 
         Self {
@@ -133,13 +202,88 @@ impl<const CANONICAL: bool, const R: u32> CharHasher for NtHasher<CANONICAL, R>
             c,
             f_rot,
             c_rot,
-            simd_f,
-            simd_c,
-            simd_f_rot,
-            simd_c_rot,
+            simd_f: twice(f),
+            simd_c: twice(c),
+            simd_f_rot: twice(f_rot),
+            simd_c_rot: twice(c_rot),
             fw_init,
             rc_init,
+            seed,
+        }
+    }
+}
+
+/// Repeat a table of 4 values in both halves of a SIMD vector.
+const fn twice(t: [u32; 4]) -> u32x8 {
+    u32x8::new([t[0], t[1], t[2], t[3], t[0], t[1], t[2], t[3]])
+}
+
+/// Measure how much consecutive forward ntHash values with tables `f` and rotation `r` are correlated,
+/// on a fixed pseudo-random sequence.
+///
+/// For independent hashes, consecutive hashes increase half of the time, and a third of hashes are local minima.
+/// The score is the total deviation from these counts.
+const fn correlation_score(f: &[u32; 4], k: usize, r: u32) -> u64 {
+    const N: usize = 1024;
+    /// The `i`th character of the pseudo-random sequence.
+    const fn base(i: usize) -> usize {
+        let x = (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        ((x ^ (x >> 29)).wrapping_mul(0xbf58_476d_1ce4_e5b9) >> 62) as usize
+    }
+    let out_rot = ((k as u64 * r as u64) % 32) as u32;
+
+    let mut h = 0u32;
+    let mut i = 0;
+    while i < k {
+        h = h.rotate_left(r) ^ f[base(i)];
+        i += 1;
+    }
+    let (mut prev2, mut prev) = (0u32, h);
+    let mut increases = 0u64;
+    let mut local_minima = 0u64;
+    let mut j = 0;
+    while j < N {
+        h = h.rotate_left(r) ^ f[base(i)] ^ f[base(i - k)].rotate_left(out_rot);
+        i += 1;
+        increases += (h > prev) as u64;
+        local_minima += (j > 0 && prev < prev2 && prev < h) as u64;
+        (prev2, prev) = (prev, h);
+        j += 1;
+    }
+    increases.abs_diff(N as u64 / 2) + local_minima.abs_diff(N as u64 / 3)
+}
+
+/// The rotation `R` in `1..32` for which consecutive [`NtHasher`] hashes of k-mers are the least correlated.
+///
+/// This is a `const fn`, so it can be used as a type parameter: `NtHasher<true, { best_rotation(31) }>`.
+pub const fn best_rotation(k: usize) -> u32 {
+    let mut best = 1;
+    let mut best_score = u64::MAX;
+    let mut r = 1;
+    while r < 32 {
+        let score = correlation_score(&HASHES_F, k, r);
+        if score < best_score {
+            best = r;
+            best_score = score;
         }
+        r += 1;
+    }
+    best
+}
+
+impl<const CANONICAL: bool, const R: u32> CharHasher for NtHasher<CANONICAL, R> {
+    const CANONICAL: bool = CANONICAL;
+    const R: u32 = R;
+    const BITS_PER_CHAR: usize = 2;
+
+    #[inline(always)]
+    fn new_with_seed(k: usize, seed: Option<u32>) -> Self {
+        let hasher = SeedHasher::new();
+        let f = match seed {
+            None => HASHES_F,
+            Some(seed) => from_fn(|i| hasher.hash_one(HASHES_F[i] ^ seed) as u32),
+        };
+        Self::from_table(k, f, seed)
     }
 
     #[inline(always)]
@@ -188,6 +332,11 @@ impl<const CANONICAL: bool, const R: u32> CharHasher for NtHasher<CANONICAL, R>
     fn rc_init(&self) -> u32 {
         self.rc_init
     }
+
+    #[inline(always)]
+    fn seed(&self) -> Option<u32> {
+        self.seed
+    }
 }
 
 /// `MulHasher` multiplies each character by a constant and xor's them together under rotations.
@@ -195,7 +344,9 @@ impl<const CANONICAL: bool, const R: u32> CharHasher for NtHasher<CANONICAL, R>
 /// `CANONICAL` by default by summing forward and reverse-complement hash values.
 /// Instead of the classical 1-bit rotation, this rotates by `R=7` bits by default,
 /// to reduce correlation between high bits of consecutive hashes.
+// `repr(C)`, so that the canonical and forward variants have the same layout.
 #[derive(Clone)]
+#[repr(C)]
 pub struct MulHasher<const CANONICAL: bool = true, const R: u32 = 7> {
     k: usize,
     rot: u32,
@@ -203,6 +354,7 @@ pub struct MulHasher<const CANONICAL: bool = true, const R: u32 = 7> {
     simd_mul: u32x8,
     fw_init: u32,
     rc_init: u32,
+    seed: Option<u32>,
 }
 
 impl<const CANONICAL: bool, const R: u32> MulHasher<CANONICAL, R> {
@@ -214,6 +366,30 @@ impl<const CANONICAL: bool, const R: u32> MulHasher<CANONICAL, R> {
     pub fn new_with_seed(k: usize, seed: u32) -> Self {
         CharHasher::new_with_seed(k, Some(seed))
     }
+    /// A hasher with a seed drawn from the OS random number generator, e.g. to randomize hashes per process.
+    ///
+    /// Use [`Self::seed`] to reproduce it via [`Self::new_with_seed`].
+    #[cfg(feature = "getrandom")]
+    pub fn new_random(k: usize) -> Self {
+        Self::new_with_seed(k, getrandom::u32().expect("Failed to get a random seed."))
+    }
+    /// The seed, or `None` when constructed without one.
+    #[inline(always)]
+    pub fn seed(&self) -> Option<u32> {
+        CharHasher::seed(self)
+    }
+    /// A view of this hasher as a forward hasher, sharing its tables. See [`NtHasher::as_forward`].
+    #[inline(always)]
+    pub fn as_forward(&self) -> &MulHasher<false, R> {
+        // SAFETY: `MulHasher` is `repr(C)` and its fields do not depend on `CANONICAL`.
+        unsafe { &*(self as *const Self as *const MulHasher<false, R>) }
+    }
+    /// A view of this hasher as a canonical hasher, sharing its tables. See [`NtHasher::as_forward`].
+    #[inline(always)]
+    pub fn as_canonical(&self) -> &MulHasher<true, R> {
+        // SAFETY: As for `as_forward`.
+        unsafe { &*(self as *const Self as *const MulHasher<true, R>) }
+    }
 }
 
 // Mixing constant.
@@ -256,6 +432,7 @@ impl<const CANONICAL: bool, const R: u32> CharHasher for MulHasher<CANONICAL, R>
             simd_mul,
             fw_init,
             rc_init,
+            seed,
         }
     }
 
@@ -311,75 +488,655 @@ impl<const CANONICAL: bool, const R: u32> CharHasher for MulHasher<CANONICAL, R>
     fn rc_init(&self) -> u32 {
         self.rc_init
     }
+
+    #[inline(always)]
+    fn seed(&self) -> Option<u32> {
+        self.seed
+    }
 }
 
-impl<CH: CharHasher> KmerHasher for CH {
-    const CANONICAL: bool = CH::CANONICAL;
+/// `TableHasher` looks up each character in a seeded random table of 256 entries
+/// and xor's them together under rotations.
+///
+/// This is a stronger alternative to [`MulHasher`] for 8-bit alphabets,
+/// at the cost of 4KiB of tables. The SIMD version uses AVX2 gathers when available.
+///
+/// `CANONICAL` by default by summing forward and reverse-complement hash values.
+/// Instead of the classical 1-bit rotation, this rotates by `R=7` bits by default,
+/// to reduce correlation between high bits of consecutive hashes.
+// `repr(C)`, so that the canonical and forward variants have the same layout.
+#[derive(Clone)]
+#[repr(C)]
+pub struct TableHasher<const CANONICAL: bool = true, const R: u32 = 7> {
+    k: usize,
+    f: [u32; 256],
+    c: [u32; 256],
+    f_rot: [u32; 256],
+    c_rot: [u32; 256],
+    fw_init: u32,
+    rc_init: u32,
+    seed: Option<u32>,
+}
+
+impl<const CANONICAL: bool, const R: u32> TableHasher<CANONICAL, R> {
+    #[inline(always)]
+    pub fn new(k: usize) -> Self {
+        CharHasher::new(k)
+    }
+    #[inline(always)]
+    pub fn new_with_seed(k: usize, seed: u32) -> Self {
+        CharHasher::new_with_seed(k, Some(seed))
+    }
+    /// A hasher with a seed drawn from the OS random number generator, e.g. to randomize hashes per process.
+    ///
+    /// Use [`Self::seed`] to reproduce it via [`Self::new_with_seed`].
+    #[cfg(feature = "getrandom")]
+    pub fn new_random(k: usize) -> Self {
+        Self::new_with_seed(k, getrandom::u32().expect("Failed to get a random seed."))
+    }
+    /// The seed, or `None` when constructed without one.
+    #[inline(always)]
+    pub fn seed(&self) -> Option<u32> {
+        CharHasher::seed(self)
+    }
+    /// A view of this hasher as a forward hasher, sharing its tables. See [`NtHasher::as_forward`].
+    #[inline(always)]
+    pub fn as_forward(&self) -> &TableHasher<false, R> {
+        // SAFETY: `TableHasher` is `repr(C)` and its fields do not depend on `CANONICAL`.
+        unsafe { &*(self as *const Self as *const TableHasher<false, R>) }
+    }
+    /// A view of this hasher as a canonical hasher, sharing its tables. See [`NtHasher::as_forward`].
+    #[inline(always)]
+    pub fn as_canonical(&self) -> &TableHasher<true, R> {
+        // SAFETY: As for `as_forward`.
+        unsafe { &*(self as *const Self as *const TableHasher<true, R>) }
+    }
+}
+
+impl<const CANONICAL: bool, const R: u32> CharHasher for TableHasher<CANONICAL, R> {
+    const CANONICAL: bool = CANONICAL;
+    const R: u32 = R;
+    const BITS_PER_CHAR: usize = 8;
+
+    #[inline(always)]
+    fn new_with_seed(k: usize, seed: Option<u32>) -> Self {
+        let rot = k as u32 - 1;
+        let hasher = SeedHasher::new();
+        let f: [u32; 256] = from_fn(|i| hasher.hash_one((seed.unwrap_or(0), i as u8)) as u32);
+        let c = from_fn(|i| f[complement_base(i as u8) as usize]);
+        let f_rot = f.map(|h| h.rotate_left(rot * R));
+        let c_rot = c.map(|h| h.rotate_left(rot * R));
 
+        // Initial value of hashing `k-1` zeros.
+        let mut fw_init = 0u32;
+        for _ in 0..k - 1 {
+            fw_init = fw_init.rotate_left(Self::R) ^ f[0];
+        }
+
+        // Initial value of reverse-complement-hashing `k-1` zeros.
+        let mut rc_init = 0u32;
+        for _ in 0..k - 1 {
+            rc_init = rc_init.rotate_right(Self::R) ^ c_rot[0];
+        }
+
+        Self {
+            k,
+            f,
+            c,
+            f_rot,
+            c_rot,
+            fw_init,
+            rc_init,
+            seed,
+        }
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    #[inline(always)]
+    fn f(&self, b: u8) -> u32 {
+        self.f[b as usize]
+    }
+    #[inline(always)]
+    fn c(&self, b: u8) -> u32 {
+        self.c[b as usize]
+    }
+    #[inline(always)]
+    fn f_rot(&self, b: u8) -> u32 {
+        self.f_rot[b as usize]
+    }
+    #[inline(always)]
+    fn c_rot(&self, b: u8) -> u32 {
+        self.c_rot[b as usize]
+    }
+
+    #[inline(always)]
+    fn simd_f(&self, b: u32x8) -> u32x8 {
+        intrinsics::gather(&self.f, b)
+    }
+    #[inline(always)]
+    fn simd_c(&self, b: u32x8) -> u32x8 {
+        intrinsics::gather(&self.c, b)
+    }
+    #[inline(always)]
+    fn simd_f_rot(&self, b: u32x8) -> u32x8 {
+        intrinsics::gather(&self.f_rot, b)
+    }
+    #[inline(always)]
+    fn simd_c_rot(&self, b: u32x8) -> u32x8 {
+        intrinsics::gather(&self.c_rot, b)
+    }
+    #[inline(always)]
+    fn fw_init(&self) -> u32 {
+        self.fw_init
+    }
+    #[inline(always)]
+    fn rc_init(&self) -> u32 {
+        self.rc_init
+    }
+
+    #[inline(always)]
+    fn seed(&self) -> Option<u32> {
+        self.seed
+    }
+}
+
+/// A [`CharHasher`] whose canonicality is chosen at runtime instead of via a const generic.
+///
+/// This avoids instantiating the SIMD code twice when canonicality is selected from e.g. a config file.
+/// The `CANONICAL` parameter of the wrapped hasher is ignored; see [`DynNtHasher`], [`DynMulHasher`], and [`DynTableHasher`].
+///
+/// Since [`KmerHasher::CANONICAL`] must be known at compile time, it is `false` here,
+/// even when the hashes are canonical. [`KmerHasher::is_canonical()`] is authoritative.
+#[derive(Clone)]
+pub struct RuntimeCanonical<CH> {
+    hasher: CH,
+    canonical: bool,
+}
+
+/// [`NtHasher`] with runtime canonicality.
+pub type DynNtHasher<const R: u32 = 7> = RuntimeCanonical<NtHasher<false, R>>;
+/// [`MulHasher`] with runtime canonicality.
+pub type DynMulHasher<const R: u32 = 7> = RuntimeCanonical<MulHasher<false, R>>;
+/// [`TableHasher`] with runtime canonicality.
+pub type DynTableHasher<const R: u32 = 7> = RuntimeCanonical<TableHasher<false, R>>;
+
+impl<CH: CharHasher> RuntimeCanonical<CH> {
+    #[inline(always)]
+    pub fn new(k: usize, canonical: bool) -> Self {
+        Self {
+            hasher: CH::new(k),
+            canonical,
+        }
+    }
+    #[inline(always)]
+    pub fn new_with_seed(k: usize, seed: u32, canonical: bool) -> Self {
+        Self {
+            hasher: CH::new_with_seed(k, Some(seed)),
+            canonical,
+        }
+    }
+
+    /// See [`NtHasher::new_random`].
+    #[cfg(feature = "getrandom")]
+    pub fn new_random(k: usize, canonical: bool) -> Self {
+        Self::new_with_seed(
+            k,
+            getrandom::u32().expect("Failed to get a random seed."),
+            canonical,
+        )
+    }
+    /// The seed, or `None` when constructed without one.
+    #[inline(always)]
+    pub fn seed(&self) -> Option<u32> {
+        self.hasher.seed()
+    }
+
+    /// The wrapped hasher.
+    #[inline(always)]
+    pub(crate) fn inner(&self) -> &CH {
+        &self.hasher
+    }
+}
+
+impl<CH: CharHasher> KmerHasher for RuntimeCanonical<CH> {
+    const CANONICAL: bool = false;
+
+    /// Canonical by default, like [`NtHasher`].
     fn new(k: usize) -> Self {
-        Self::new(k)
+        Self::new(k, true)
+    }
+
+    #[inline(always)]
+    fn is_canonical(&self) -> bool {
+        self.canonical
     }
 
     fn k(&self) -> usize {
-        self.k()
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        CH::BITS_PER_CHAR
     }
 
     #[inline(always)]
     fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
-        assert!(seq.bits_per_char() <= CH::BITS_PER_CHAR);
+        self.check_alphabet(seq).unwrap();
+        in_out_mapper_scalar(&self.hasher, self.canonical)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        in_out_mapper_simd(&self.hasher, self.canonical)
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        mapper(&self.hasher, self.canonical)
+    }
+
+    #[inline(always)]
+    fn hash_prefixes_simd<'s>(&self, seq: impl Seq<'s>) -> PaddedIt<impl ChunkIt<S>> {
+        self.check_alphabet(seq).unwrap();
+        hash_prefixes_simd(&self.hasher, self.canonical, seq)
+    }
+}
 
-        let mut fw = self.fw_init();
-        let mut rc = self.rc_init();
+impl<CH: CharHasher> SuffixHasher for RuntimeCanonical<CH> {
+    #[inline(always)]
+    fn hash_suffixes<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        self.check_alphabet(seq).unwrap();
+        hash_suffixes(&self.hasher, self.canonical, seq)
+    }
+}
 
+/// A [`CharHasher`] that hashes, at each position, the reverse complement of the k-mer there.
+///
+/// The `i`th hash of [`KmerHasher::hash_kmers_scalar`] equals the forward hash of the reverse complement of `seq[i..i+k]`,
+/// i.e. the hash of the k-mer on the opposite strand at the same locus.
+/// This gives a reverse-complement track aligned to forward coordinates, without materializing the reverse complement.
+/// The `CANONICAL` parameter of the wrapped hasher is ignored.
+#[derive(Clone)]
+pub struct RevCompHasher<CH = NtHasher<false>> {
+    hasher: CH,
+}
+
+impl<CH: CharHasher> RevCompHasher<CH> {
+    #[inline(always)]
+    pub fn new(k: usize) -> Self {
+        Self { hasher: CH::new(k) }
+    }
+    #[inline(always)]
+    pub fn new_with_seed(k: usize, seed: u32) -> Self {
+        Self {
+            hasher: CH::new_with_seed(k, Some(seed)),
+        }
+    }
+}
+
+impl<CH: CharHasher> KmerHasher for RevCompHasher<CH> {
+    const CANONICAL: bool = false;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    fn k(&self) -> usize {
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        CH::BITS_PER_CHAR
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        let mut rc = h.rc_init();
         move |(a, r)| {
-            let fw_out = fw.rotate_left(CH::R) ^ self.f(a);
-            fw = fw_out ^ self.f_rot(r);
-            if Self::CANONICAL {
-                let rc_out = rc.rotate_right(CH::R) ^ self.c_rot(a);
-                rc = rc_out ^ self.c(r);
-                fw_out.wrapping_add(rc_out)
-            } else {
-                fw_out
-            }
+            let rc_out = rc.rotate_right(CH::R) ^ h.c_rot(a);
+            rc = rc_out ^ h.c(r);
+            rc_out
         }
     }
 
     #[inline(always)]
     fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
-        assert!(seq.bits_per_char() <= CH::BITS_PER_CHAR);
-        let mut fw = S::splat(self.fw_init());
-        let mut rc = S::splat(self.rc_init());
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        let mut rc = S::splat(h.rc_init());
         let shl = S::splat(CH::R);
         let shr = S::splat(32 - CH::R);
-
         move |(a, r)| {
-            let fw_out = ((fw << shl) | (fw >> shr)) ^ self.simd_f(a);
-            fw = fw_out ^ self.simd_f_rot(r);
-            if Self::CANONICAL {
-                let rc_out = ((rc >> shl) | (rc << shr)) ^ self.simd_c_rot(a);
-                rc = rc_out ^ self.simd_c(r);
-                // Wrapping SIMD add
-                fw_out + rc_out
-            } else {
-                fw_out
-            }
+            let rc_out = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+            rc = rc_out ^ h.simd_c(r);
+            rc_out
         }
     }
 
+    /// The last hash is the forward hash of the reverse complement of `seq`, for any length of `seq`.
     #[inline(always)]
-    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 {
-        assert!(seq.bits_per_char() <= CH::BITS_PER_CHAR);
-
-        let mut fw = 0u32;
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        // Like `c_rot`, but rotated by `(len-1)*R` instead of `(k-1)*R`.
+        let rot = ((seq.len().max(1) - 1) * CH::R as usize % 32) as u32;
         let mut rc = 0u32;
         move |a| {
-            fw = fw.rotate_left(CH::R) ^ self.f(a);
-            if Self::CANONICAL {
-                rc = rc.rotate_right(CH::R) ^ self.c_rot(a);
-                fw.wrapping_add(rc)
-            } else {
-                fw
-            }
+            rc = rc.rotate_right(CH::R) ^ h.c(a).rotate_left(rot);
+            rc
+        }
+    }
+}
+
+impl<CH: CharHasher> KmerHasher for CH {
+    const CANONICAL: bool = CH::CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    fn k(&self) -> usize {
+        self.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        CH::BITS_PER_CHAR
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        in_out_mapper_scalar(self, CH::CANONICAL)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        in_out_mapper_simd(self, CH::CANONICAL)
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        mapper(self, CH::CANONICAL)
+    }
+
+    #[inline(always)]
+    fn hash_prefixes_simd<'s>(&self, seq: impl Seq<'s>) -> PaddedIt<impl ChunkIt<S>> {
+        self.check_alphabet(seq).unwrap();
+        hash_prefixes_simd(self, CH::CANONICAL, seq)
+    }
+}
+
+impl<CH: CharHasher> SuffixHasher for CH {
+    #[inline(always)]
+    fn hash_suffixes<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        self.check_alphabet(seq).unwrap();
+        hash_suffixes(self, CH::CANONICAL, seq)
+    }
+}
+
+/// The raw forward and reverse-complement rolling values `(fw, rc)` of each k-mer in `seq`, before combining.
+///
+/// For a forward hasher the hash is `fw`, and for a canonical hasher it is `fw.wrapping_add(rc)`,
+/// but `rc` is always computed, and the `CANONICAL` parameter of `hasher` is ignored.
+/// `rc` equals the forward value of the reverse complement of the k-mer.
+/// Useful to experiment with other ways to combine the two strands.
+#[inline(always)]
+pub fn rolling_states_scalar<'s, CH: CharHasher>(
+    hasher: &CH,
+    seq: impl Seq<'s>,
+) -> impl ExactSizeIterator<Item = (u32, u32)> {
+    hasher.check_alphabet(seq).unwrap();
+    let k = hasher.k();
+    let mut add = seq.iter_bp();
+    let remove = seq.iter_bp();
+    let mut mapper = in_out_state_mapper_scalar(hasher);
+    add.by_ref().take(k - 1).for_each(|a| {
+        mapper((a, 0));
+    });
+    zip(add, remove).map(mapper)
+}
+
+/// SIMD version of [`rolling_states_scalar`].
+#[inline(always)]
+pub fn rolling_states_simd<'s, CH: CharHasher>(
+    hasher: &CH,
+    seq: impl Seq<'s>,
+) -> PaddedIt<impl ChunkIt<(S, S)>> {
+    hasher.check_alphabet(seq).unwrap();
+    let k = hasher.k();
+    seq.par_iter_bp_delayed(k, Delay(k - 1))
+        .map(in_out_state_mapper_simd(hasher))
+        .advance(k - 1)
+}
+
+/// Like [`KmerHasher::in_out_mapper_scalar`], but combines the forward and reverse-complement values with `combine(fw, rc)`.
+///
+/// With a symmetric `combine`, such as `min`, `max`, or `^`, the hash is invariant under reverse complement.
+/// `combine = u32::wrapping_add` gives the default canonical hash. The `CANONICAL` parameter of `hasher` is ignored.
+/// The delay is `k-1`.
+#[inline(always)]
+pub fn in_out_mapper_combined_scalar<'a, CH: CharHasher>(
+    hasher: &'a CH,
+    combine: impl Fn(u32, u32) -> u32 + 'a,
+) -> impl FnMut((u8, u8)) -> u32 + 'a {
+    let mut mapper = in_out_state_mapper_scalar(hasher);
+    move |ar| {
+        let (fw, rc) = mapper(ar);
+        combine(fw, rc)
+    }
+}
+
+/// SIMD version of [`in_out_mapper_combined_scalar`].
+#[inline(always)]
+pub fn in_out_mapper_combined_simd<'a, CH: CharHasher>(
+    hasher: &'a CH,
+    combine: impl Fn(S, S) -> S + 'a,
+) -> impl FnMut((S, S)) -> S + 'a {
+    let mut mapper = in_out_state_mapper_simd(hasher);
+    move |ar| {
+        let (fw, rc) = mapper(ar);
+        combine(fw, rc)
+    }
+}
+
+/// The hashes of all k-mers in `seq`, combining the values of [`rolling_states_scalar`] with `combine(fw, rc)`.
+///
+/// See [`in_out_mapper_combined_scalar`].
+#[inline(always)]
+pub fn hash_kmers_combined_scalar<'s, CH: CharHasher>(
+    hasher: &CH,
+    seq: impl Seq<'s>,
+    combine: impl Fn(u32, u32) -> u32,
+) -> impl ExactSizeIterator<Item = u32> {
+    rolling_states_scalar(hasher, seq).map(move |(fw, rc)| combine(fw, rc))
+}
+
+/// SIMD version of [`hash_kmers_combined_scalar`].
+#[inline(always)]
+pub fn hash_kmers_combined_simd<'s, CH: CharHasher>(
+    hasher: &CH,
+    seq: impl Seq<'s>,
+    combine: impl Fn(S, S) -> S,
+) -> PaddedIt<impl ChunkIt<S>> {
+    rolling_states_simd(hasher, seq).map(move |(fw, rc)| combine(fw, rc))
+}
+
+// The mappers below are shared by the const-generic and runtime-canonical hashers.
+// For the former, `canonical` is a constant and the branch is optimized away.
+
+#[inline(always)]
+fn in_out_mapper_scalar<CH: CharHasher>(
+    h: &CH,
+    canonical: bool,
+) -> impl FnMut((u8, u8)) -> u32 + '_ {
+    let mut fw = h.fw_init();
+    let mut rc = h.rc_init();
+
+    move |(a, r)| {
+        let fw_out = fw.rotate_left(CH::R) ^ h.f(a);
+        fw = fw_out ^ h.f_rot(r);
+        if canonical {
+            let rc_out = rc.rotate_right(CH::R) ^ h.c_rot(a);
+            rc = rc_out ^ h.c(r);
+            fw_out.wrapping_add(rc_out)
+        } else {
+            fw_out
+        }
+    }
+}
+
+#[inline(always)]
+fn in_out_mapper_simd<CH: CharHasher>(h: &CH, canonical: bool) -> impl FnMut((S, S)) -> S + '_ {
+    let mut fw = S::splat(h.fw_init());
+    let mut rc = S::splat(h.rc_init());
+    let shl = S::splat(CH::R);
+    let shr = S::splat(32 - CH::R);
+
+    move |(a, r)| {
+        let fw_out = ((fw << shl) | (fw >> shr)) ^ h.simd_f(a);
+        fw = fw_out ^ h.simd_f_rot(r);
+        if canonical {
+            let rc_out = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+            rc = rc_out ^ h.simd_c(r);
+            // Wrapping SIMD add
+            fw_out + rc_out
+        } else {
+            fw_out
+        }
+    }
+}
+
+/// Like [`in_out_mapper_scalar`], but returns the uncombined `(fw, rc)` values.
+#[inline(always)]
+pub(crate) fn in_out_state_mapper_scalar<CH: CharHasher>(
+    h: &CH,
+) -> impl FnMut((u8, u8)) -> (u32, u32) + '_ {
+    let mut fw = h.fw_init();
+    let mut rc = h.rc_init();
+    move |(a, r)| {
+        let fw_out = fw.rotate_left(CH::R) ^ h.f(a);
+        fw = fw_out ^ h.f_rot(r);
+        let rc_out = rc.rotate_right(CH::R) ^ h.c_rot(a);
+        rc = rc_out ^ h.c(r);
+        (fw_out, rc_out)
+    }
+}
+
+/// Like [`in_out_mapper_simd`], but returns the uncombined `(fw, rc)` values.
+#[inline(always)]
+pub(crate) fn in_out_state_mapper_simd<CH: CharHasher>(
+    h: &CH,
+) -> impl FnMut((S, S)) -> (S, S) + '_ {
+    let mut fw = S::splat(h.fw_init());
+    let mut rc = S::splat(h.rc_init());
+    let shl = S::splat(CH::R);
+    let shr = S::splat(32 - CH::R);
+    move |(a, r)| {
+        let fw_out = ((fw << shl) | (fw >> shr)) ^ h.simd_f(a);
+        fw = fw_out ^ h.simd_f_rot(r);
+        let rc_out = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+        rc = rc_out ^ h.simd_c(r);
+        (fw_out, rc_out)
+    }
+}
+
+/// Suffix hashes consistent with [`mapper`], from shortest to longest.
+///
+/// Prepending a character to a suffix XORs in its contribution, rotated by the length of the suffix.
+#[inline(always)]
+fn hash_suffixes<'s, CH: CharHasher>(
+    h: &CH,
+    canonical: bool,
+    seq: impl Seq<'s>,
+) -> impl ExactSizeIterator<Item = u32> {
+    let mut fw = 0u32;
+    let mut rc = 0u32;
+    // Rotations are modulo 32, so wrapping is fine.
+    let mut rot = 0u32;
+    (0..seq.len()).rev().map(move |i| {
+        let a = seq.get(i);
+        fw ^= h.f(a).rotate_left(rot);
+        if canonical {
+            rc ^= h.c_rot(a).rotate_right(rot);
+        }
+        rot = rot.wrapping_add(CH::R);
+        if canonical { fw.wrapping_add(rc) } else { fw }
+    })
+}
+
+/// SIMD version of the prefix hashes of [`mapper`].
+///
+/// The recurrence is linear, so a first pass hashes each lane from a zero state.
+/// The state at the start of each lane then follows from the states at the ends of the previous lanes,
+/// and a second pass hashes each lane starting from its true state.
+#[inline(always)]
+fn hash_prefixes_simd<'s, CH: CharHasher>(
+    h: &CH,
+    canonical: bool,
+    seq: impl Seq<'s>,
+) -> PaddedIt<impl ChunkIt<S>> {
+    let shl = S::splat(CH::R);
+    let shr = S::splat(32 - CH::R);
+
+    let mut fw = S::ZERO;
+    let mut rc = S::ZERO;
+    let PaddedIt { it, .. } = seq.par_iter_bp(1);
+    let len = it.len() as u32;
+    for a in it {
+        fw = ((fw << shl) | (fw >> shr)) ^ h.simd_f(a);
+        if canonical {
+            rc = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+        }
+    }
+
+    // Rotations are modulo 32, so wrapping is fine.
+    let rot = CH::R.wrapping_mul(len);
+    let (fw_end, rc_end) = (fw.to_array(), rc.to_array());
+    let mut fw_start = [0u32; 8];
+    let mut rc_start = [0u32; 8];
+    for j in 1..8 {
+        fw_start[j] = fw_start[j - 1].rotate_left(rot) ^ fw_end[j - 1];
+        rc_start[j] = rc_start[j - 1].rotate_right(rot) ^ rc_end[j - 1];
+    }
+
+    let mut fw = S::new(fw_start);
+    let mut rc = S::new(rc_start);
+    seq.par_iter_bp(1).map(move |a| {
+        fw = ((fw << shl) | (fw >> shr)) ^ h.simd_f(a);
+        if canonical {
+            rc = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+            // Wrapping SIMD add
+            fw + rc
+        } else {
+            fw
+        }
+    })
+}
+
+#[inline(always)]
+fn mapper<CH: CharHasher>(h: &CH, canonical: bool) -> impl FnMut(u8) -> u32 + Clone + '_ {
+    let mut fw = 0u32;
+    let mut rc = 0u32;
+    move |a| {
+        fw = fw.rotate_left(CH::R) ^ h.f(a);
+        if canonical {
+            rc = rc.rotate_right(CH::R) ^ h.c_rot(a);
+            fw.wrapping_add(rc)
+        } else {
+            fw
         }
     }
 }
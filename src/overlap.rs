@@ -0,0 +1,90 @@
+//! Candidate suffix–prefix overlaps between reads, as a prefilter for overlap-based assembly.
+
+use crate::SuffixHasher;
+use packed_seq::Seq;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// A candidate overlap, where the suffix of length `len` of read `a` has the same hash as the prefix of length `len` of read `b`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OverlapCandidate {
+    /// The index of the read whose suffix overlaps.
+    pub a: usize,
+    /// The index of the read whose prefix overlaps.
+    pub b: usize,
+    /// The length of the overlap.
+    pub len: usize,
+}
+
+/// Find candidate overlaps between the suffixes of one read and the prefixes of another, for all lengths in `lens`.
+///
+/// Prefixes and suffixes are hashed with [`crate::KmerHasher::hash_prefixes`] and [`SuffixHasher::hash_suffixes`],
+/// which ignore `k` and take linear time per read.
+/// Use a forward hasher such as `NtHasher<false>`; only same-strand overlaps are found.
+/// Candidates can be false positives due to hash collisions and should be verified,
+/// but no exact overlap with a length in `lens` is missed.
+///
+/// Returns candidates with `a != b`, sorted by `a`, then `b`, then `len`.
+/// Memory is linear in the number of reads times the number of lengths.
+pub fn overlap_candidates<'s, H: SuffixHasher>(
+    hasher: &H,
+    reads: &[impl Seq<'s>],
+    lens: RangeInclusive<usize>,
+) -> Vec<OverlapCandidate> {
+    assert!(*lens.start() > 0, "Overlap lengths must be positive.");
+    // Reads by `(len, hash)` of their prefixes.
+    let mut prefixes = HashMap::<(usize, u32), Vec<usize>>::new();
+    for (b, &read) in reads.iter().enumerate() {
+        for (len, h) in (1..).zip(hasher.hash_prefixes(read)) {
+            if lens.contains(&len) {
+                prefixes.entry((len, h)).or_default().push(b);
+            }
+        }
+    }
+
+    let mut candidates = vec![];
+    for (a, &read) in reads.iter().enumerate() {
+        for (len, h) in (1..).zip(hasher.hash_suffixes(read)) {
+            if !lens.contains(&len) {
+                continue;
+            }
+            if let Some(bs) = prefixes.get(&(len, h)) {
+                candidates.extend(bs.iter().filter(|&&b| b != a).map(|&b| OverlapCandidate {
+                    a,
+                    b,
+                    len,
+                }));
+            }
+        }
+    }
+    candidates.sort_unstable();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn tiled_reads() {
+        let genome = PackedSeqVec::random(2000);
+        // Reads of length 200, starting every 150 bases, so consecutive reads overlap by 50.
+        let reads: Vec<_> = (0..12)
+            .map(|i| genome.slice(150 * i..150 * i + 200))
+            .collect();
+        let hasher = NtHasher::<false>::new(1);
+        let candidates = overlap_candidates(&hasher, &reads, 20..=100);
+        let expected: Vec<_> = (0..11)
+            .map(|a| OverlapCandidate {
+                a,
+                b: a + 1,
+                len: 50,
+            })
+            .collect();
+        assert_eq!(candidates, expected);
+
+        assert!(overlap_candidates(&hasher, &reads, 60..=100).is_empty());
+    }
+}
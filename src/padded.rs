@@ -0,0 +1,156 @@
+//! Combinators on [`PaddedIt`], the SIMD-parallel iterator returned by e.g. [`crate::KmerHasher::hash_kmers_simd`].
+//!
+//! [`PaddedIt`] and [`ChunkIt`] are re-exported from [`packed_seq`], so that custom per-k-mer SIMD pipelines
+//! can be built on this crate alone. Besides [`PaddedItExt::map_simd`], the inherent
+//! [`PaddedIt::map`], [`PaddedIt::zip`], [`PaddedIt::advance`], and [`PaddedIt::collect`] are part of the API.
+//! [`PaddedLayout`] maps lanes back to positions in the input.
+//!
+//! ```
+//! use seq_hash::packed_seq::{PackedSeqVec, SeqVec, u32x8};
+//! use seq_hash::{KmerHasher, NtHasher, PaddedItExt};
+//! let seq = PackedSeqVec::random(1000);
+//! let fw = NtHasher::<false>::new(21);
+//! let rc = seq_hash::RevCompHasher::<NtHasher<false>>::new(21);
+//! // XOR of the forward and reverse-complement hashes, keeping the top 16 bits.
+//! let hashes = fw
+//!     .hash_kmers_simd(seq.as_slice(), 1)
+//!     .zip(rc.hash_kmers_simd(seq.as_slice(), 1))
+//!     .map(|(a, b): (u32x8, u32x8)| a ^ b)
+//!     .map_simd(|h| h >> 16)
+//!     .collect();
+//! assert_eq!(hashes.len(), 1000 - 20);
+//! ```
+
+use crate::S;
+use packed_seq::{ChunkIt, PaddedIt};
+use std::array::from_fn;
+
+/// Extension methods on [`PaddedIt`] over SIMD vectors.
+pub trait PaddedItExt {
+    /// Apply `f` to each SIMD vector.
+    ///
+    /// Like [`PaddedIt::map`], but with the input type fixed to `u32x8`, so closures need no type annotations.
+    fn map_simd<T>(self, f: impl FnMut(S) -> T) -> PaddedIt<impl ChunkIt<T>>;
+}
+
+impl<I: ChunkIt<S>> PaddedItExt for PaddedIt<I> {
+    #[inline(always)]
+    fn map_simd<T>(self, f: impl FnMut(S) -> T) -> PaddedIt<impl ChunkIt<T>> {
+        self.map(f)
+    }
+}
+
+/// The layout of a [`PaddedIt`], to map its chunked SIMD representation back to positions in the input.
+///
+/// An iterator over `n` values per lane returned by e.g. [`crate::KmerHasher::hash_kmers_simd`] with `context`
+/// covers 8 consecutive chunks of the input, where consecutive chunks overlap by `context-1` values.
+/// Value `i` of lane `j` is at position `j * (n + 1 - context) + i`,
+/// and the last [`padding`](PaddedLayout::padding) positions are past the end of the input.
+/// Methods taking `context` must be passed the one the iterator was created with,
+/// and are only meaningful when the input has at least `context` values (e.g. k-mers).
+///
+/// All methods describe the iterator as it was returned, and must be called before consuming it.
+pub trait PaddedLayout {
+    /// The number of padding positions past the end of the input, in the last lane(s).
+    fn padding(&self) -> usize;
+
+    /// The number of values `n` in each lane, including padding.
+    fn lane_len(&self) -> usize;
+
+    /// The number of distinct input positions covered, i.e., the number of values of [`PaddedIt::collect`] for `context = 1`.
+    #[inline(always)]
+    fn num_positions(&self, context: usize) -> usize {
+        let n = self.lane_len();
+        if n == 0 {
+            return 0;
+        }
+        8 * (n + 1 - context) + context - 1 - self.padding()
+    }
+
+    /// The number of valid (non-padding) values in each lane.
+    #[inline(always)]
+    fn lane_lens(&self, context: usize) -> [usize; 8] {
+        let n = self.lane_len();
+        if n == 0 {
+            return [0; 8];
+        }
+        let total = self.num_positions(context);
+        from_fn(|j| total.saturating_sub(j * (n + 1 - context)).min(n))
+    }
+
+    /// The input position of value `index` of `lane`, or `None` when it is padding.
+    #[inline(always)]
+    fn position(&self, context: usize, lane: usize, index: usize) -> Option<usize> {
+        assert!(lane < 8, "There are only 8 lanes.");
+        let n = self.lane_len();
+        if index >= n {
+            return None;
+        }
+        let pos = lane * (n + 1 - context) + index;
+        (pos < self.num_positions(context)).then_some(pos)
+    }
+
+    /// For each SIMD value, the input positions of its 8 lanes (as in [`Self::position`]),
+    /// and a mask that is all ones in the lanes that are not padding.
+    ///
+    /// Zip this with the iterator to filter out padding or to map values to positions.
+    /// Requires fewer than `2^32` positions.
+    #[inline(always)]
+    fn lane_positions(&self, context: usize) -> LanePositions {
+        let n = self.lane_len();
+        let total = self.num_positions(context);
+        assert!(
+            total <= u32::MAX as usize,
+            "Lane positions require fewer than 2^32 positions."
+        );
+        let stride = (n + 1).saturating_sub(context);
+        LanePositions {
+            pos: S::new(from_fn(|j| (j * stride) as u32)),
+            total: S::splat(total as u32),
+            remaining: n,
+        }
+    }
+}
+
+/// The positions and non-padding mask of each SIMD value of a [`PaddedIt`], see [`PaddedLayout::lane_positions`].
+#[derive(Clone, Debug)]
+pub struct LanePositions {
+    pos: S,
+    total: S,
+    remaining: usize,
+}
+
+impl Iterator for LanePositions {
+    /// `(positions, mask)`.
+    type Item = (S, S);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<(S, S)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let pos = self.pos;
+        self.pos += S::ONE;
+        Some((pos, pos.simd_lt(self.total)))
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for LanePositions {}
+
+impl<I: ExactSizeIterator> PaddedLayout for PaddedIt<I> {
+    #[inline(always)]
+    fn padding(&self) -> usize {
+        self.padding
+    }
+
+    #[inline(always)]
+    fn lane_len(&self) -> usize {
+        self.it.len()
+    }
+}
@@ -0,0 +1,234 @@
+//! External-memory partitioning of k-mer hashes, for counting and deduplicating more k-mers than fit in memory.
+//!
+//! Hashes are split over `P = 2^b` partition files by their top `b` bits,
+//! so each partition can be processed in memory on its own,
+//! and concatenating the sorted partitions gives all hashes in sorted order.
+//! Partition files contain little-endian `u32`s, without a header.
+
+use crate::sorted::bucket_sort;
+use crate::{KmerHasher, PaddedLayout, S};
+use packed_seq::Seq;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The number of hashes buffered per partition before they are written.
+const BLOCK: usize = 1 << 12;
+
+/// Streams hashes into partition files, split by their top bits.
+///
+/// Hashes are buffered per partition and written in blocks.
+pub struct PartitionWriter {
+    shift: u32,
+    paths: Vec<PathBuf>,
+    files: Vec<File>,
+    buffers: Vec<Vec<u32>>,
+    counts: Vec<u64>,
+}
+
+impl PartitionWriter {
+    /// Create `partitions` empty partition files `part-<i>.bin` in the existing directory `dir`.
+    ///
+    /// `partitions` must be a power of two, at most `2^16`.
+    pub fn create(dir: impl AsRef<Path>, partitions: usize) -> io::Result<Self> {
+        assert!(
+            partitions.is_power_of_two() && partitions <= 1 << 16,
+            "Number of partitions must be a power of two, at most 2^16."
+        );
+        let paths: Vec<PathBuf> = (0..partitions)
+            .map(|i| dir.as_ref().join(format!("part-{i:05}.bin")))
+            .collect();
+        let files = paths.iter().map(File::create).collect::<io::Result<_>>()?;
+        Ok(Self {
+            shift: 32 - partitions.ilog2(),
+            paths,
+            files,
+            buffers: vec![Vec::with_capacity(BLOCK); partitions],
+            counts: vec![0; partitions],
+        })
+    }
+
+    /// The number of partitions.
+    pub fn partitions(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// The partition containing hash `h`.
+    #[inline(always)]
+    pub fn partition_of(&self, h: u32) -> usize {
+        // `checked_shr` handles the single partition with a shift of 32.
+        h.checked_shr(self.shift).unwrap_or(0) as usize
+    }
+
+    fn flush_partition(&mut self, p: usize) -> io::Result<()> {
+        let buffer = &mut self.buffers[p];
+        let bytes: Vec<u8> = buffer.iter().flat_map(|h| h.to_le_bytes()).collect();
+        self.files[p].write_all(&bytes)?;
+        self.counts[p] += buffer.len() as u64;
+        buffer.clear();
+        Ok(())
+    }
+
+    /// Append hash `h` to its partition.
+    #[inline(always)]
+    pub fn push(&mut self, h: u32) -> io::Result<()> {
+        let p = self.partition_of(h);
+        self.buffers[p].push(h);
+        if self.buffers[p].len() == BLOCK {
+            self.flush_partition(p)?;
+        }
+        Ok(())
+    }
+
+    /// Append the SIMD block of hashes `h`, skipping lanes not set in `mask`.
+    #[inline(always)]
+    fn push_simd(&mut self, h: S, mask: S) -> io::Result<()> {
+        let mask = mask.to_bitmask();
+        for (j, h) in h.to_array().into_iter().enumerate() {
+            if mask >> j & 1 != 0 {
+                self.push(h)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Append the k-mer hashes of `seq`, computed with [`KmerHasher::hash_kmers_simd`].
+    pub fn hash_seq<'s>(&mut self, hasher: &impl KmerHasher, seq: impl Seq<'s>) -> io::Result<()> {
+        let hashes = hasher.hash_kmers_simd(seq, 1);
+        let positions = hashes.lane_positions(1);
+        for (h, (_, mask)) in hashes.it.zip(positions) {
+            self.push_simd(h, mask)?;
+        }
+        Ok(())
+    }
+
+    /// Flush all buffers and close the files.
+    pub fn finish(mut self) -> io::Result<Partitions> {
+        for p in 0..self.partitions() {
+            self.flush_partition(p)?;
+            self.files[p].flush()?;
+        }
+        Ok(Partitions {
+            paths: self.paths,
+            counts: self.counts,
+        })
+    }
+}
+
+/// Partition files written by a [`PartitionWriter`].
+#[derive(Clone, Debug)]
+pub struct Partitions {
+    paths: Vec<PathBuf>,
+    counts: Vec<u64>,
+}
+
+impl Partitions {
+    /// The number of partitions.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether there are no partitions.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// The path of partition `p`.
+    pub fn path(&self, p: usize) -> &Path {
+        &self.paths[p]
+    }
+
+    /// The number of hashes in partition `p`.
+    pub fn count(&self, p: usize) -> u64 {
+        self.counts[p]
+    }
+
+    /// The total number of hashes in all partitions.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Read the hashes of partition `p`, in the order they were written.
+    pub fn read(&self, p: usize) -> io::Result<Vec<u32>> {
+        let mut bytes = Vec::with_capacity(4 * self.counts[p] as usize);
+        File::open(&self.paths[p])?.read_to_end(&mut bytes)?;
+        if bytes.len() % 4 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated partition file",
+            ));
+        }
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .collect())
+    }
+
+    /// The distinct hashes of partition `p` with their number of occurrences, sorted by hash.
+    pub fn read_counts(&self, p: usize) -> io::Result<Vec<(u32, u32)>> {
+        let mut hashes = self.read(p)?;
+        bucket_sort(&mut hashes);
+        Ok(hashes
+            .chunk_by(|a, b| a == b)
+            .map(|run| (run[0], run.len() as u32))
+            .collect())
+    }
+
+    /// Delete the partition files.
+    pub fn remove(self) -> io::Result<()> {
+        self.paths.iter().try_for_each(std::fs::remove_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::HashMap;
+
+    #[test]
+    fn partition_and_count() {
+        let dir = std::env::temp_dir().join(format!("seq-hash-partition-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let hasher = <NtHasher>::new(31);
+        // Short reads have padding in more than one lane.
+        let seqs: Vec<PackedSeqVec> = (0..5)
+            .map(|i| PackedSeqVec::random(10_000 * i + 7))
+            .chain((32..150).step_by(3).map(PackedSeqVec::random))
+            .collect();
+
+        let mut expected = HashMap::<u32, u32>::new();
+        for seq in &seqs {
+            for h in hasher.hash_kmers_scalar(seq.as_slice()) {
+                *expected.entry(h).or_default() += 1;
+            }
+        }
+        let mut expected: Vec<(u32, u32)> = expected.into_iter().collect();
+        expected.sort_unstable();
+
+        for partitions in [1, 16] {
+            let mut writer = PartitionWriter::create(&dir, partitions).unwrap();
+            for seq in &seqs {
+                writer.hash_seq(&hasher, seq.as_slice()).unwrap();
+            }
+            writer.push(u32::MAX).unwrap();
+            let parts = writer.finish().unwrap();
+            assert_eq!(parts.len(), partitions);
+
+            // Partitions are ordered by hash prefix, so the concatenation is sorted.
+            let mut counts = vec![];
+            for p in 0..parts.len() {
+                counts.extend(parts.read_counts(p).unwrap());
+            }
+            assert_eq!(counts.pop(), Some((u32::MAX, 1)));
+            assert_eq!(counts, expected);
+            assert_eq!(
+                parts.total(),
+                1 + expected.iter().map(|&(_, c)| c as u64).sum::<u64>()
+            );
+            parts.remove().unwrap();
+        }
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
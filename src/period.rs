@@ -0,0 +1,99 @@
+//! Detecting periodic structure, such as tandem repeats, in the k-mer hash stream.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+use std::iter::zip;
+
+/// Candidate periods of each region of `region` consecutive k-mers of `seq`, as `(period, score)` pairs.
+///
+/// The score of a period `p` is the fraction of k-mers in the region whose hash equals that of the k-mer `p` positions earlier,
+/// so a tandem repeat with period `p` scores close to `1` at `p` and its multiples.
+/// Candidates with non-zero score for `p` in `1..=max_period` are sorted by decreasing score, and then by increasing period,
+/// so the first candidate is the smallest best-scoring period.
+///
+/// Comparisons of the first k-mers may reach back into the previous region. The last region may be shorter.
+/// Takes `O(n * max_period)` time. The hashes are computed in SIMD, but each lag is compared in a plain scalar pass.
+pub fn periodicity<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    region: usize,
+    max_period: usize,
+) -> Vec<Vec<(usize, f64)>> {
+    assert!(region > 0, "Region size must be positive.");
+    let hashes = hasher.hash_kmers_simd(seq, 1).collect();
+    (0..hashes.len())
+        .step_by(region)
+        .map(|start| {
+            let end = (start + region).min(hashes.len());
+            let mut candidates: Vec<(usize, f64)> = (1..=max_period)
+                .filter_map(|p| {
+                    // Only k-mers with a k-mer `p` positions earlier are compared.
+                    let from = start.max(p);
+                    if from >= end {
+                        return None;
+                    }
+                    let matches = zip(&hashes[from..end], &hashes[from - p..end - p])
+                        .filter(|(a, b)| a == b)
+                        .count();
+                    (matches > 0).then(|| (p, matches as f64 / (end - from) as f64))
+                })
+                .collect();
+            candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            candidates
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn tandem_repeat() {
+        // Random sequence, a tandem repeat with period 7, and random sequence again.
+        let mut ascii = PackedSeqVec::random(1000).as_slice().unpack();
+        ascii.extend(b"ACGTTGA".repeat(150));
+        ascii.extend(PackedSeqVec::random(1000).as_slice().unpack());
+        let seq = PackedSeqVec::from_ascii(&ascii);
+        let k = 15;
+        let hasher = <NtHasher>::new(k);
+        let regions = periodicity(&hasher, seq.as_slice(), 250, 20);
+        let n = seq.len() - (k - 1);
+        assert_eq!(regions.len(), n.div_ceil(250));
+
+        // Regions fully inside the repeat.
+        for region in &regions[5..8] {
+            assert_eq!(region[0], (7, 1.0));
+            assert_eq!(region[1], (14, 1.0));
+        }
+        // Random regions have no (or only spurious) periodicity.
+        for region in regions[..3].iter().chain(&regions[10..]) {
+            assert!(region.iter().all(|&(_, score)| score < 0.05), "{region:?}");
+        }
+    }
+
+    #[test]
+    fn naive_scores() {
+        let seq = PackedSeqVec::random(300);
+        let hasher = <NtHasher>::new(1);
+        let hashes: Vec<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+        for (r, region) in periodicity(&hasher, seq.as_slice(), 64, 10)
+            .iter()
+            .enumerate()
+        {
+            let start = r * 64;
+            let end = (start + 64).min(hashes.len());
+            for &(p, score) in region {
+                let range = start.max(p)..end;
+                let matches = range
+                    .clone()
+                    .filter(|&i| hashes[i] == hashes[i - p])
+                    .count();
+                assert_eq!(score, matches as f64 / range.len() as f64);
+            }
+            assert!(region.is_sorted_by(|a, b| a.1 >= b.1));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Hashing the k-mers at selected positions, without scanning the whole sequence.
+
+use crate::{KmerHasher, S};
+use packed_seq::{PackedSeq, Seq};
+use std::array::from_fn;
+use std::iter::zip;
+
+/// The hashes of the k-mers of `seq` starting at each of `positions`, in the same order.
+///
+/// Hashes equal those of [`KmerHasher::hash_kmers_scalar`] at the same positions,
+/// e.g. to re-hash minimizer positions or to spot-check an index.
+///
+/// Eight k-mers are hashed in parallel: their packed words are gathered into SIMD lanes,
+/// and fed one character per step through a fresh [`KmerHasher::in_out_mapper_simd`].
+/// This takes `k` steps per 8 k-mers, so it beats a full scan when positions are sparser than about one in `k`.
+pub fn hash_at_positions<'s>(
+    hasher: &impl KmerHasher,
+    seq: PackedSeq<'s>,
+    positions: &[u32],
+) -> Vec<u32> {
+    let k = hasher.k();
+    let delay = hasher.delay().0;
+    let words = k.div_ceil(32);
+    // The low and high halves of each 32-character word of the k-mers.
+    let mut lo = vec![S::ZERO; words];
+    let mut hi = vec![S::ZERO; words];
+    let mut out = Vec::with_capacity(positions.len());
+    for chunk in positions.chunks(8) {
+        for &p in chunk {
+            assert!(
+                p as usize + k <= seq.len(),
+                "Position {p} has no complete k-mer."
+            );
+        }
+        for (c, (lo, hi)) in zip(&mut lo, &mut hi).enumerate() {
+            let len = (k - 32 * c).min(32);
+            let w: [u64; 8] = from_fn(|j| {
+                chunk
+                    .get(j)
+                    .map_or(0, |&p| seq.read_kmer(len, p as usize + 32 * c))
+            });
+            *lo = S::new(w.map(|w| w as u32));
+            *hi = S::new(w.map(|w| (w >> 32) as u32));
+        }
+        let char_at = |t: usize| {
+            let (c, i) = (t / 32, t % 32);
+            let w = if i < 16 { lo[c] } else { hi[c] };
+            (w >> (2 * (i % 16)) as u32) & S::splat(3)
+        };
+
+        let mut mapper = hasher.in_out_mapper_simd(seq);
+        let mut h = S::ZERO;
+        for t in 0..k {
+            let r = if t >= delay {
+                char_at(t - delay)
+            } else {
+                S::ZERO
+            };
+            h = mapper((char_at(t), r));
+        }
+        out.extend_from_slice(&h.to_array()[..chunk.len()]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DynNtHasher, MulHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    fn check(hasher: &impl KmerHasher, seq: PackedSeq) {
+        let k = hasher.k();
+        let all: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+        let positions: Vec<u32> = (0..37)
+            .map(|_| rand::random_range(0..all.len() as u32))
+            .chain([0, all.len() as u32 - 1])
+            .collect();
+        let expected: Vec<u32> = positions.iter().map(|&p| all[p as usize]).collect();
+        assert_eq!(
+            hash_at_positions(hasher, seq, &positions),
+            expected,
+            "k={k}"
+        );
+    }
+
+    #[test]
+    fn matches_scan() {
+        let seq = PackedSeqVec::random(1000);
+        for offset in [0, 3] {
+            let seq = seq.slice(offset..1000);
+            for k in [1, 2, 5, 16, 17, 31, 32, 33, 63, 64, 65, 100] {
+                check(&<NtHasher>::new(k), seq);
+                check(&NtHasher::<false>::new(k), seq);
+                check(&<MulHasher>::new(k), seq);
+                check(&<DynNtHasher>::new(k, false), seq);
+            }
+        }
+        assert!(hash_at_positions(&<NtHasher>::new(5), seq.as_slice(), &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic = "no complete k-mer"]
+    fn out_of_bounds() {
+        let seq = PackedSeqVec::random(100);
+        hash_at_positions(&<NtHasher>::new(5), seq.as_slice(), &[96]);
+    }
+}
@@ -0,0 +1,408 @@
+//! A quotient filter over 32-bit k-mer hashes, which can be resized and merged.
+
+use crate::{KmerHasher, S};
+use packed_seq::Seq;
+
+/// The load at which [`QuotientFilter::insert`] grows the filter.
+const MAX_LOAD: f64 = 0.9;
+
+/// A quotient filter over `u32` hashes, e.g. canonical k-mer hashes from a [`crate::KmerHasher`].
+///
+/// The top `p = q + r` bits of each hash form its fingerprint, which is split into a `q`-bit quotient
+/// (its home slot among `2^q`) and an `r`-bit remainder that is stored.
+/// Remainders with the same quotient form a sorted run, and runs are kept in quotient order,
+/// using the `occupieds` and `runends` bits of the rank-and-select quotient filter (RSQF).
+/// Instead of per-block offsets, runs are located by scanning their cluster of used slots,
+/// which is short at the loads used here.
+///
+/// Memory is about `r + 3` bits per slot, and the false-positive rate is about `load * 2^-r`.
+/// Unlike a [`crate::BloomFilter`], the fingerprints can be recovered, so the filter can be
+/// grown when it fills up (moving one remainder bit into the quotient) and merged with other filters.
+/// Hashes are stored as a set: inserting a fingerprint that is already present has no effect.
+#[derive(Clone, Debug)]
+pub struct QuotientFilter {
+    q: u32,
+    r: u32,
+    len: usize,
+    /// Bit `i`: some stored fingerprint has quotient `i`.
+    occupieds: Vec<u64>,
+    /// Bit `i`: slot `i` holds the last remainder of a run.
+    runends: Vec<u64>,
+    /// Bit `i`: slot `i` holds a remainder.
+    used: Vec<u64>,
+    /// `r` bits per slot.
+    remainders: Vec<u64>,
+    num_slots: usize,
+}
+
+#[inline(always)]
+fn get_bit(bits: &[u64], i: usize) -> bool {
+    bits[i / 64] >> (i % 64) & 1 != 0
+}
+
+#[inline(always)]
+fn set_bit(bits: &mut [u64], i: usize, value: bool) {
+    let mask = 1 << (i % 64);
+    if value {
+        bits[i / 64] |= mask;
+    } else {
+        bits[i / 64] &= !mask;
+    }
+}
+
+impl QuotientFilter {
+    /// An empty filter with `2^quotient_bits` home slots and `remainder_bits` bits per remainder.
+    ///
+    /// Requires `0 < quotient_bits` and `quotient_bits + remainder_bits <= 32`.
+    pub fn new(quotient_bits: u32, remainder_bits: u32) -> Self {
+        assert!(
+            0 < quotient_bits && quotient_bits + remainder_bits <= 32,
+            "Requires 0 < quotient_bits and quotient_bits + remainder_bits <= 32."
+        );
+        let num_quotients = 1usize << quotient_bits;
+        // Room for runs shifted past the last home slot.
+        let num_slots = num_quotients + 64.max(10 * num_quotients.isqrt());
+        let words = num_slots.div_ceil(64);
+        Self {
+            q: quotient_bits,
+            r: remainder_bits,
+            len: 0,
+            occupieds: vec![0; num_quotients.div_ceil(64)],
+            runends: vec![0; words],
+            used: vec![0; words],
+            remainders: vec![0; (num_slots * remainder_bits as usize).div_ceil(64)],
+            num_slots,
+        }
+    }
+
+    /// A filter for about `n` hashes with fingerprints of `fingerprint_bits` bits, at a load of at most 90%.
+    ///
+    /// The false-positive rate is about `2^-(fingerprint_bits - q)` for the chosen `q`.
+    pub fn with_capacity(n: usize, fingerprint_bits: u32) -> Self {
+        let slots = (n as f64 / MAX_LOAD).ceil() as usize;
+        let q = slots.max(2).next_power_of_two().ilog2();
+        assert!(
+            q <= fingerprint_bits,
+            "Too few fingerprint bits for {n} hashes."
+        );
+        Self::new(q, fingerprint_bits - q)
+    }
+
+    /// The number of quotient bits `q`, so that there are `2^q` home slots.
+    pub fn quotient_bits(&self) -> u32 {
+        self.q
+    }
+
+    /// The number of stored remainder bits `r`.
+    pub fn remainder_bits(&self) -> u32 {
+        self.r
+    }
+
+    /// The number of fingerprint bits `q + r`, which is preserved when growing.
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.q + self.r
+    }
+
+    /// The number of stored fingerprints.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no fingerprints are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of stored fingerprints divided by the number of home slots.
+    pub fn load(&self) -> f64 {
+        self.len as f64 / (1u64 << self.q) as f64
+    }
+
+    /// The size of the filter in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        8 * (self.occupieds.len() + self.runends.len() + self.used.len() + self.remainders.len())
+    }
+
+    /// The `(quotient, remainder)` of hash `h`.
+    #[inline(always)]
+    fn split(&self, h: u32) -> (usize, u32) {
+        let fp = (h as u64 >> (32 - self.fingerprint_bits())) as u32;
+        ((fp >> self.r) as usize, fp & ((1u64 << self.r) - 1) as u32)
+    }
+
+    #[inline(always)]
+    fn remainder(&self, i: usize) -> u32 {
+        let r = self.r as usize;
+        if r == 0 {
+            return 0;
+        }
+        let bit = i * r;
+        let (w, o) = (bit / 64, bit % 64);
+        let mut x = self.remainders[w] >> o;
+        if o + r > 64 {
+            x |= self.remainders[w + 1] << (64 - o);
+        }
+        (x & ((1 << r) - 1)) as u32
+    }
+
+    #[inline(always)]
+    fn set_remainder(&mut self, i: usize, rem: u32) {
+        let r = self.r as usize;
+        if r == 0 {
+            return;
+        }
+        let bit = i * r;
+        let (w, o) = (bit / 64, bit % 64);
+        let mask = (1u64 << r) - 1;
+        self.remainders[w] = (self.remainders[w] & !(mask << o)) | ((rem as u64) << o);
+        if o + r > 64 {
+            let hi = 64 - o;
+            self.remainders[w + 1] = (self.remainders[w + 1] & !(mask >> hi)) | (rem as u64 >> hi);
+        }
+    }
+
+    /// The first slot of the run of quotient `q` (or where it would start),
+    /// and its last slot when `q` is occupied.
+    fn locate(&self, q: usize) -> (usize, Option<usize>) {
+        // The cluster containing `q` starts after the last unused slot before it.
+        let mut s = q;
+        while s > 0 && get_bit(&self.used, s - 1) {
+            s -= 1;
+        }
+        // Skip the runs of the occupied quotients in `s..q`, which are stored in order from `s`.
+        let mut start = s;
+        for x in s..q {
+            if get_bit(&self.occupieds, x) {
+                while !get_bit(&self.runends, start) {
+                    start += 1;
+                }
+                start += 1;
+            }
+        }
+        let start = start.max(q);
+        if !get_bit(&self.occupieds, q) {
+            return (start, None);
+        }
+        let mut end = start;
+        while !get_bit(&self.runends, end) {
+            end += 1;
+        }
+        (start, Some(end))
+    }
+
+    /// Whether hash `h` may have been inserted. There are no false negatives.
+    pub fn contains(&self, h: u32) -> bool {
+        let (q, rem) = self.split(h);
+        if !get_bit(&self.occupieds, q) {
+            return false;
+        }
+        let (start, end) = self.locate(q);
+        (start..=end.unwrap()).any(|i| self.remainder(i) == rem)
+    }
+
+    /// SIMD version of [`Self::contains`], returning all ones in the lanes that may have been inserted.
+    #[inline(always)]
+    pub fn contains_simd(&self, h: S) -> S {
+        S::new(
+            h.to_array()
+                .map(|h| if self.contains(h) { u32::MAX } else { 0 }),
+        )
+    }
+
+    /// Insert hash `h`, returning whether its fingerprint was new.
+    ///
+    /// When the load would exceed 90%, or a run would be shifted past the last slot, the filter is
+    /// [grown](Self::grow) first, which panics when no remainder bits are left.
+    pub fn insert(&mut self, h: u32) -> bool {
+        if (self.len + 1) as f64 > MAX_LOAD * (1u64 << self.q) as f64 {
+            self.grow();
+        }
+        let (q, rem) = self.split(h);
+        let (start, end) = self.locate(q);
+        let mut pos = start;
+        if let Some(end) = end {
+            while pos <= end && self.remainder(pos) < rem {
+                pos += 1;
+            }
+            if pos <= end && self.remainder(pos) == rem {
+                return false;
+            }
+        }
+        let Some(free) = (pos..self.num_slots).find(|&i| !get_bit(&self.used, i)) else {
+            self.grow();
+            return self.insert(h);
+        };
+        // Shift the slots `pos..free` one to the right.
+        for i in (pos..free).rev() {
+            self.set_remainder(i + 1, self.remainder(i));
+            let end = get_bit(&self.runends, i);
+            set_bit(&mut self.runends, i + 1, end);
+        }
+        set_bit(&mut self.used, free, true);
+        self.set_remainder(pos, rem);
+        match end {
+            // A new run.
+            None => {
+                set_bit(&mut self.runends, pos, true);
+                set_bit(&mut self.occupieds, q, true);
+            }
+            // Appended to the run.
+            Some(end) if pos == end + 1 => {
+                set_bit(&mut self.runends, end, false);
+                set_bit(&mut self.runends, pos, true);
+            }
+            // Inside the run, whose end was shifted.
+            Some(_) => set_bit(&mut self.runends, pos, false),
+        }
+        self.len += 1;
+        true
+    }
+
+    /// The stored fingerprints (the top [`Self::fingerprint_bits`] bits of the inserted hashes), in increasing order.
+    pub fn fingerprints(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut slot = 0;
+        (0..1usize << self.q)
+            .filter(|&q| get_bit(&self.occupieds, q))
+            .flat_map(move |q| {
+                slot = slot.max(q);
+                let start = slot;
+                while !get_bit(&self.runends, slot) {
+                    slot += 1;
+                }
+                slot += 1;
+                (start..slot).map(move |i| ((q as u32) << self.r) | self.remainder(i))
+            })
+    }
+
+    /// Double the number of home slots, by moving one bit of each remainder into its quotient.
+    ///
+    /// Fingerprints are preserved and `q + r` is unchanged, so the false-positive rate of about `n / 2^(q+r)`
+    /// stays the same for the same number of hashes `n`, while the load halves.
+    pub fn grow(&mut self) {
+        assert!(
+            self.r > 0,
+            "Quotient filter is full: no remainder bits are left to grow."
+        );
+        let mut grown = Self::new(self.q + 1, self.r - 1);
+        grown.extend_fingerprints(self.fingerprints());
+        *self = grown;
+    }
+
+    /// Insert fingerprints with the same number of bits as this filter.
+    fn extend_fingerprints(&mut self, fps: impl IntoIterator<Item = u32>) {
+        let shift = 32 - self.fingerprint_bits();
+        for fp in fps {
+            self.insert(((fp as u64) << shift) as u32);
+        }
+    }
+
+    /// The union of two filters with the same number of fingerprint bits.
+    ///
+    /// The result has the larger of the two quotient sizes, and is grown as needed.
+    pub fn merge(&self, other: &Self) -> Self {
+        assert_eq!(
+            self.fingerprint_bits(),
+            other.fingerprint_bits(),
+            "Only filters with equal fingerprint bits can be merged."
+        );
+        let (big, small) = if self.q >= other.q {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut merged = big.clone();
+        merged.extend_fingerprints(small.fingerprints());
+        merged
+    }
+
+    /// Insert the k-mer hashes of `seq`.
+    pub fn insert_kmers<'s>(&mut self, hasher: &impl KmerHasher, seq: impl Seq<'s>) {
+        for h in hasher.hash_kmers_scalar(seq) {
+            self.insert(h);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn insert_contains() {
+        for r in [0, 5, 8, 13, 20] {
+            let mut filter = QuotientFilter::new(10, r);
+            let items: Vec<u32> = (0..900).map(|_| rand::random()).collect();
+            for &h in &items {
+                filter.insert(h);
+            }
+            assert_eq!(filter.quotient_bits(), 10, "r={r}");
+            let shift = 32 - filter.fingerprint_bits();
+            let fps: BTreeSet<u32> = items.iter().map(|&h| (h as u64 >> shift) as u32).collect();
+            assert_eq!(filter.len(), fps.len());
+            assert!(filter.fingerprints().eq(fps.iter().copied()), "r={r}");
+            assert!(items.iter().all(|&h| filter.contains(h)));
+            let lanes = S::new(std::array::from_fn(|i| items[i]));
+            assert_eq!(filter.contains_simd(lanes), S::splat(u32::MAX));
+            // Inserting again is a no-op.
+            assert!(!filter.insert(items[0]));
+            assert_eq!(filter.len(), fps.len());
+
+            let fp = (0..10000)
+                .map(|_| rand::random::<u32>())
+                .filter(|&h| filter.contains(h) != fps.contains(&((h as u64 >> shift) as u32)))
+                .count();
+            assert_eq!(fp, 0);
+        }
+    }
+
+    #[test]
+    fn false_positives() {
+        let mut filter = QuotientFilter::with_capacity(10000, 24);
+        for _ in 0..10000 {
+            filter.insert(rand::random());
+        }
+        let fp = (0..100000)
+            .filter(|_| filter.contains(rand::random()))
+            .count();
+        // About `load * 2^-r = 0.6 * 100000 / 2^10`.
+        assert!(fp < 150, "fp={fp}");
+    }
+
+    #[test]
+    fn grow_and_merge() {
+        let mut a = QuotientFilter::new(4, 20);
+        let items: Vec<u32> = (0..3000).map(|_| rand::random()).collect();
+        for &h in &items[..2000] {
+            a.insert(h);
+        }
+        assert!(a.quotient_bits() >= 12);
+        assert_eq!(a.fingerprint_bits(), 24);
+        assert!(a.load() <= MAX_LOAD);
+        assert!(items[..2000].iter().all(|&h| a.contains(h)));
+
+        let mut b = QuotientFilter::new(8, 16);
+        for &h in &items[1000..] {
+            b.insert(h);
+        }
+        let merged = a.merge(&b);
+        assert!(items.iter().all(|&h| merged.contains(h)));
+        let expected: BTreeSet<u32> = a.fingerprints().chain(b.fingerprints()).collect();
+        assert!(merged.fingerprints().eq(expected.into_iter()));
+    }
+
+    #[test]
+    fn kmers() {
+        let hasher = <NtHasher>::new(21);
+        let seq = PackedSeqVec::random(10000);
+        let mut filter = QuotientFilter::with_capacity(10000, 28);
+        filter.insert_kmers(&hasher, seq.as_slice());
+        assert!(
+            hasher
+                .hash_kmers_scalar(seq.as_slice())
+                .all(|h| filter.contains(h))
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Rabin fingerprinting over byte streams.
+//!
+//! A byte string `b_0 .. b_{n-1}` is interpreted as a polynomial over GF(2)
+//! with `b_0` as the most significant coefficients, and its fingerprint is its
+//! remainder modulo an irreducible polynomial `P` of degree at most 56.
+//! Fingerprints are linear: `fp(a ^ b) = fp(a) ^ fp(b)` for equal-length inputs,
+//! and two distinct strings of length `n` collide with probability at most `8n/2^deg(P)`
+//! over the random choice of `P`.
+
+use crate::HashError;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+
+type SeedHasher = BuildHasherDefault<DefaultHasher>;
+
+/// Degree of the polynomial `p`, or `-1` for `p=0`.
+fn deg(p: u128) -> i32 {
+    127 - p.leading_zeros() as i32
+}
+
+/// `a mod p` over GF(2).
+fn pol_mod(mut a: u128, p: u128) -> u128 {
+    let dp = deg(p);
+    while deg(a) >= dp {
+        a ^= p << (deg(a) - dp);
+    }
+    a
+}
+
+/// `a * b mod p` over GF(2), for `a, b` of degree less than `deg(p) <= 64`.
+fn pol_mul_mod(a: u128, b: u128, p: u128) -> u128 {
+    let mut r = 0;
+    for i in 0..=deg(b).max(0) {
+        if b >> i & 1 == 1 {
+            r ^= a << i;
+        }
+    }
+    pol_mod(r, p)
+}
+
+fn pol_gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, pol_mod(a, b));
+    }
+    a
+}
+
+/// Ben-Or's irreducibility test: `p` is irreducible iff `gcd(p, x^(2^i) - x) = 1` for all `i <= deg(p)/2`.
+pub fn is_irreducible(p: u64) -> bool {
+    let p = p as u128;
+    let d = deg(p);
+    if d < 1 {
+        return false;
+    }
+    let x = 2;
+    let mut x_pow = x;
+    for _ in 0..d / 2 {
+        x_pow = pol_mul_mod(x_pow, x_pow, p);
+        if pol_gcd(p, x_pow ^ x) != 1 {
+            return false;
+        }
+    }
+    true
+}
+
+/// A rolling Rabin fingerprint over a window of `w` bytes.
+#[derive(Clone)]
+pub struct RabinHasher {
+    pol: u64,
+    window: usize,
+    /// `deg(pol) - 8`: the shift giving the top byte of a fingerprint.
+    shift: u32,
+    /// `out[b]`: fingerprint of `b` followed by `w-1` zero bytes.
+    out: [u64; 256],
+    /// `reduce[b]`: `(b * x^deg) mod P`, plus `b * x^deg` itself to cancel the overflowing byte.
+    reduce: [u64; 256],
+}
+
+impl RabinHasher {
+    /// An irreducible polynomial of degree 53.
+    pub const DEFAULT_POLYNOMIAL: u64 = 0x3DA3358B4DC173;
+
+    /// A rolling Rabin fingerprint over windows of `w` bytes, using [`Self::DEFAULT_POLYNOMIAL`].
+    pub fn new(w: usize) -> Self {
+        Self::with_polynomial(w, Self::DEFAULT_POLYNOMIAL).unwrap()
+    }
+
+    /// Use a random irreducible polynomial of degree 53 derived from `seed`.
+    pub fn new_with_seed(w: usize, seed: u32) -> Self {
+        Self::with_polynomial(w, Self::random_polynomial(seed)).unwrap()
+    }
+
+    /// Use the given polynomial, which must be irreducible and of degree `9..=56`.
+    pub fn with_polynomial(w: usize, pol: u64) -> Result<Self, HashError> {
+        let d = deg(pol as u128);
+        if !(9..=56).contains(&d) || !is_irreducible(pol) {
+            return Err(HashError::InvalidPolynomial { polynomial: pol });
+        }
+        assert!(w > 0, "Window size must be positive.");
+        let p = pol as u128;
+        let reduce =
+            std::array::from_fn(|b| (pol_mod((b as u128) << d, p) | ((b as u128) << d)) as u64);
+        let mut hasher = Self {
+            pol,
+            window: w,
+            shift: d as u32 - 8,
+            out: [0; 256],
+            reduce,
+        };
+        hasher.out = std::array::from_fn(|b| {
+            let mut h = hasher.append(0, b as u8);
+            for _ in 1..w {
+                h = hasher.append(h, 0);
+            }
+            h
+        });
+        Ok(hasher)
+    }
+
+    /// Find a random irreducible polynomial of degree 53, deterministically derived from `seed`.
+    pub fn random_polynomial(seed: u32) -> u64 {
+        let hasher = SeedHasher::new();
+        (0u64..)
+            .map(|i| (hasher.hash_one((seed, i)) & ((1 << 53) - 1)) | (1 << 53) | 1)
+            .find(|&p| is_irreducible(p))
+            .unwrap()
+    }
+
+    /// The polynomial `P`.
+    pub fn polynomial(&self) -> u64 {
+        self.pol
+    }
+
+    /// The window size `w`.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Append byte `b` to fingerprint `h`.
+    #[inline(always)]
+    pub fn append(&self, h: u64, b: u8) -> u64 {
+        let top = (h >> self.shift) as u8;
+        ((h << 8) | b as u64) ^ self.reduce[top as usize]
+    }
+
+    /// Append byte `b` to the fingerprint `h` of a full window, and remove byte `out` that leaves the window.
+    #[inline(always)]
+    pub fn roll(&self, h: u64, out: u8, b: u8) -> u64 {
+        self.append(h ^ self.out[out as usize], b)
+    }
+
+    /// The (non-rolling) fingerprint of all of `data`.
+    pub fn fingerprint(&self, data: &[u8]) -> u64 {
+        data.iter().fold(0, |h, &b| self.append(h, b))
+    }
+
+    /// The fingerprint of each window of `w` bytes of `data`.
+    pub fn fingerprints<'a>(&'a self, data: &'a [u8]) -> impl ExactSizeIterator<Item = u64> + 'a {
+        let w = self.window.min(data.len());
+        let mut h = self.fingerprint(&data[..w.saturating_sub(1)]);
+        let first = w.saturating_sub(1);
+        (0..(data.len() + 1).saturating_sub(self.window)).map(move |i| {
+            h = if i == 0 {
+                self.append(h, data[first])
+            } else {
+                self.roll(h, data[i - 1], data[i + self.window - 1])
+            };
+            h
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn irreducible() {
+        assert!(is_irreducible(RabinHasher::DEFAULT_POLYNOMIAL));
+        // x^2 + x + 1 is irreducible, x^2 + 1 = (x+1)^2 is not.
+        assert!(is_irreducible(0b111));
+        assert!(!is_irreducible(0b101));
+        for seed in 0..5 {
+            assert!(is_irreducible(RabinHasher::random_polynomial(seed)));
+        }
+        assert!(RabinHasher::with_polynomial(16, (1 << 53) | 0b101).is_err());
+    }
+
+    #[test]
+    fn rolling() {
+        let data: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+        for w in [1, 2, 7, 16, 64, 999, 1000, 1001] {
+            let hasher = RabinHasher::new_with_seed(w, 3);
+            let rolling: Vec<_> = hasher.fingerprints(&data).collect();
+            let naive: Vec<_> = data.windows(w).map(|x| hasher.fingerprint(x)).collect();
+            assert_eq!(rolling, naive, "w={w}");
+        }
+    }
+
+    #[test]
+    fn linear() {
+        let hasher = RabinHasher::new(16);
+        let a: Vec<u8> = (0..100).map(|_| rand::random()).collect();
+        let b: Vec<u8> = (0..100).map(|_| rand::random()).collect();
+        let ab: Vec<u8> = a.iter().zip(&b).map(|(x, y)| x ^ y).collect();
+        assert_eq!(
+            hasher.fingerprint(&ab),
+            hasher.fingerprint(&a) ^ hasher.fingerprint(&b)
+        );
+    }
+}
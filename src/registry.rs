@@ -0,0 +1,60 @@
+//! A thread-local cache of hashers, keyed by type and `k`.
+
+use crate::KmerHasher;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+thread_local! {
+    static HASHERS: RefCell<HashMap<(TypeId, usize), Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// A cached hasher of type `H` for `k`, constructed via [`KmerHasher::new`] on first use in each thread.
+///
+/// Avoids passing hasher objects through layers of code, or reconstructing them (and their tables) on each call.
+/// Only unseeded hashers are cached; construct seeded hashers explicitly.
+///
+/// ```
+/// use seq_hash::{KmerHasher, NtHasher, hasher_for};
+/// let a = hasher_for::<NtHasher>(21);
+/// let b = hasher_for::<NtHasher>(21);
+/// assert!(std::rc::Rc::ptr_eq(&a, &b));
+/// assert_eq!(a.k(), 21);
+/// ```
+pub fn hasher_for<H: KmerHasher + 'static>(k: usize) -> Rc<H> {
+    HASHERS.with_borrow_mut(|hashers| {
+        let hasher = hashers
+            .entry((TypeId::of::<H>(), k))
+            .or_insert_with(|| Rc::new(H::new(k)));
+        hasher.clone().downcast::<H>().unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MulHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn cached_per_type_and_k() {
+        let seq = PackedSeqVec::random(1000);
+        let a = hasher_for::<NtHasher>(21);
+        assert!(Rc::ptr_eq(&a, &hasher_for::<NtHasher>(21)));
+        assert!(!Rc::ptr_eq(&a, &hasher_for::<NtHasher>(31)));
+        assert_eq!(hasher_for::<NtHasher>(31).k(), 31);
+        assert_eq!(hasher_for::<MulHasher>(21).k(), 21);
+        assert_eq!(
+            a.hash_kmers_simd(seq.as_slice(), 1).collect(),
+            <NtHasher>::new(21)
+                .hash_kmers_simd(seq.as_slice(), 1)
+                .collect()
+        );
+        // Each thread has its own cache.
+        let b = std::thread::spawn(|| Rc::as_ptr(&hasher_for::<NtHasher>(21)) as usize)
+            .join()
+            .unwrap();
+        assert_ne!(Rc::as_ptr(&a) as usize, b);
+    }
+}
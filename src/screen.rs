@@ -0,0 +1,247 @@
+//! Screening query k-mers against a set of reference k-mer hashes.
+
+use crate::{BloomFilter, CuckooFilter, KmerHasher, PaddedLayout, QuotientFilter, S};
+use packed_seq::Seq;
+use std::collections::HashSet;
+
+/// A set of k-mer hashes of one or more reference sequences.
+///
+/// Build it with a canonical hasher to match k-mers on either strand,
+/// and use the same hasher to query it.
+#[derive(Clone, Debug)]
+pub enum KmerSet {
+    /// Exact membership of hashes. Distinct k-mers only match when their hashes collide.
+    Exact(HashSet<u32>),
+    /// Approximate membership, with false positives but no false negatives.
+    Bloom(BloomFilter),
+    /// Approximate membership, with fewer false positives than a Bloom filter of the same size.
+    Cuckoo(CuckooFilter),
+    /// Approximate membership that can be grown and merged after construction.
+    Quotient(QuotientFilter),
+}
+
+impl KmerSet {
+    /// The exact set of k-mer hashes of `refs`.
+    pub fn exact<'s, H: KmerHasher>(
+        hasher: &H,
+        refs: impl IntoIterator<Item = impl Seq<'s>>,
+    ) -> Self {
+        let mut set = HashSet::new();
+        for seq in refs {
+            set.extend(hasher.hash_kmers_simd(seq, 1).collect());
+        }
+        KmerSet::Exact(set)
+    }
+
+    /// A Bloom filter of the k-mer hashes of `refs`, using about `bits_per_kmer` bits per reference k-mer.
+    ///
+    /// Repeated k-mers are counted once per occurrence when sizing the filter.
+    pub fn bloom<'s, H: KmerHasher>(
+        hasher: &H,
+        refs: impl IntoIterator<Item = impl Seq<'s>> + Clone,
+        bits_per_kmer: usize,
+    ) -> Self {
+        let k = hasher.k();
+        let n = refs
+            .clone()
+            .into_iter()
+            .map(|seq| seq.len().saturating_sub(k - 1))
+            .sum();
+        let mut bloom = BloomFilter::with_capacity(n, bits_per_kmer);
+        for seq in refs {
+            for h in hasher.hash_kmers_scalar(seq) {
+                bloom.insert(h);
+            }
+        }
+        KmerSet::Bloom(bloom)
+    }
+
+    /// A cuckoo filter of the distinct k-mer hashes of `refs`, using 16 bits per slot.
+    ///
+    /// The filter is sized for the total number of reference k-mers,
+    /// and rebuilt with twice as many buckets in the rare case that it fills up.
+    /// See [`CuckooFilter::insert_kmers`] for why hashes should not be removed from it.
+    pub fn cuckoo<'s, H: KmerHasher>(
+        hasher: &H,
+        refs: impl IntoIterator<Item = impl Seq<'s>> + Clone,
+    ) -> Self {
+        let k = hasher.k();
+        let n: usize = refs
+            .clone()
+            .into_iter()
+            .map(|seq| seq.len().saturating_sub(k - 1))
+            .sum();
+        let mut num_buckets = CuckooFilter::with_capacity(n).num_buckets();
+        loop {
+            let mut filter = CuckooFilter::new(num_buckets);
+            if refs
+                .clone()
+                .into_iter()
+                .all(|seq| filter.insert_kmers(hasher, seq))
+            {
+                return KmerSet::Cuckoo(filter);
+            }
+            num_buckets *= 2;
+        }
+    }
+
+    /// A quotient filter of the distinct k-mer hashes of `refs`, with `fingerprint_bits` bits per fingerprint.
+    ///
+    /// The filter is sized for the total number of reference k-mers, and grows when more are inserted.
+    /// Use [`QuotientFilter::merge`] to combine sets of different references.
+    pub fn quotient<'s, H: KmerHasher>(
+        hasher: &H,
+        refs: impl IntoIterator<Item = impl Seq<'s>> + Clone,
+        fingerprint_bits: u32,
+    ) -> Self {
+        let k = hasher.k();
+        let n = refs
+            .clone()
+            .into_iter()
+            .map(|seq| seq.len().saturating_sub(k - 1))
+            .sum();
+        let mut filter = QuotientFilter::with_capacity(n, fingerprint_bits);
+        for seq in refs {
+            filter.insert_kmers(hasher, seq);
+        }
+        KmerSet::Quotient(filter)
+    }
+
+    /// Whether hash `h` is (possibly) in the set.
+    #[inline(always)]
+    pub fn contains(&self, h: u32) -> bool {
+        match self {
+            KmerSet::Exact(set) => set.contains(&h),
+            KmerSet::Bloom(bloom) => bloom.contains(h),
+            KmerSet::Cuckoo(filter) => filter.contains(h),
+            KmerSet::Quotient(filter) => filter.contains(h),
+        }
+    }
+
+    /// SIMD version of [`Self::contains`], returning all ones in the lanes that are (possibly) in the set.
+    #[inline(always)]
+    pub fn contains_simd(&self, h: S) -> S {
+        match self {
+            KmerSet::Exact(set) => S::new(
+                h.to_array()
+                    .map(|h| if set.contains(&h) { u32::MAX } else { 0 }),
+            ),
+            KmerSet::Bloom(bloom) => bloom.contains_simd(h),
+            KmerSet::Cuckoo(filter) => filter.contains_simd(h),
+            KmerSet::Quotient(filter) => filter.contains_simd(h),
+        }
+    }
+}
+
+/// Stream the k-mers of `query` and look them up in `set`, which must have been built with the same `hasher`.
+///
+/// Returns a bitvector with bit `i % 64` of word `i / 64` set when the `i`th k-mer is present.
+pub fn screen<'s, H: KmerHasher>(hasher: &H, set: &KmerSet, query: impl Seq<'s>) -> Vec<u64> {
+    let hashes = hasher.hash_kmers_simd(query, 1);
+    let mut bits = vec![0u64; hashes.num_positions(1).div_ceil(64)];
+    let positions = hashes.lane_positions(1);
+    for (h, (pos, mask)) in hashes.it.zip(positions) {
+        let found = (set.contains_simd(h) & mask).to_bitmask();
+        for (j, p) in pos.to_array().into_iter().enumerate() {
+            if found >> j & 1 != 0 {
+                bits[p as usize / 64] |= 1 << (p % 64);
+            }
+        }
+    }
+    bits
+}
+
+/// The containment of `query` in `set`: the fraction of k-mers of `query` that are present,
+/// or `0` when `query` has no k-mers.
+///
+/// Streams the k-mers of `query` in constant memory.
+/// For a Bloom filter, false positives bias the score upwards.
+pub fn containment<'s, H: KmerHasher>(hasher: &H, query: impl Seq<'s>, set: &KmerSet) -> f64 {
+    let hashes = hasher.hash_kmers_simd(query, 1);
+    let total = hashes.num_positions(1);
+    if total == 0 {
+        return 0.0;
+    }
+    let positions = hashes.lane_positions(1);
+    let mut found = 0;
+    for (h, (_, mask)) in hashes.it.zip(positions) {
+        found += (set.contains_simd(h) & mask).to_bitmask().count_ones() as usize;
+    }
+    found as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    fn bit(bits: &[u64], i: usize) -> bool {
+        bits[i / 64] >> (i % 64) & 1 != 0
+    }
+
+    #[test]
+    fn screen_reference() {
+        let k = 21;
+        let hasher = <NtHasher>::new(k);
+        let ref1 = PackedSeqVec::random(3000);
+        let ref2 = PackedSeqVec::random(2000);
+        let refs = [ref1.as_slice(), ref2.as_slice()];
+        let exact = KmerSet::exact(&hasher, refs);
+        let bloom = KmerSet::bloom(&hasher, refs, 16);
+        let cuckoo = KmerSet::cuckoo(&hasher, refs);
+        let quotient = KmerSet::quotient(&hasher, refs, 28);
+
+        // The query contains a part of ref1, a random part, and the reverse complement of a part of ref2.
+        let mut ascii = ref1.slice(500..1000).unpack();
+        ascii.extend(PackedSeqVec::random(500).as_slice().unpack());
+        ascii.extend(ref2.slice(100..600).to_revcomp().as_slice().unpack());
+        let query = PackedSeqVec::from_ascii(&ascii);
+
+        let n = 1500 - (k - 1);
+        let exact_bits = screen(&hasher, &exact, query.as_slice());
+        let bloom_bits = screen(&hasher, &bloom, query.as_slice());
+        let cuckoo_bits = screen(&hasher, &cuckoo, query.as_slice());
+        let quotient_bits = screen(&hasher, &quotient, query.as_slice());
+        assert_eq!(exact_bits.len(), n.div_ceil(64));
+        let ref_kmers: HashSet<u64> = refs
+            .iter()
+            .flat_map(|&r| crate::canonical_kmer_codes(r, k))
+            .collect();
+        let query_kmers: Vec<u64> = crate::canonical_kmer_codes(query.as_slice(), k).collect();
+        let present = |i: usize| ref_kmers.contains(&query_kmers[i]);
+        assert!((0..n).filter(|&i| present(i)).count() >= 2 * (500 - (k - 1)));
+        let mut false_positives = 0;
+        for i in 0..n {
+            assert_eq!(bit(&exact_bits, i), present(i), "i={i}");
+            if present(i) {
+                assert!(bit(&bloom_bits, i), "i={i}");
+                assert!(bit(&cuckoo_bits, i), "i={i}");
+                assert!(bit(&quotient_bits, i), "i={i}");
+            } else {
+                false_positives += bit(&bloom_bits, i) as usize;
+                false_positives += bit(&cuckoo_bits, i) as usize;
+                false_positives += bit(&quotient_bits, i) as usize;
+            }
+        }
+        assert!(false_positives < 10, "false_positives={false_positives}");
+        // Trailing bits are unset.
+        assert_eq!(exact_bits.last().unwrap() >> (n % 64), 0);
+
+        let ones: u32 = exact_bits.iter().map(|w| w.count_ones()).sum();
+        let c = containment(&hasher, query.as_slice(), &exact);
+        assert_eq!(c, ones as f64 / n as f64);
+        assert!(containment(&hasher, query.as_slice(), &bloom) >= c);
+    }
+
+    #[test]
+    fn containment_bounds() {
+        let hasher = <NtHasher>::new(21);
+        let reference = PackedSeqVec::random(5000);
+        let set = KmerSet::exact(&hasher, [reference.as_slice()]);
+        assert_eq!(containment(&hasher, reference.slice(1000..3000), &set), 1.0);
+        assert_eq!(containment(&hasher, reference.slice(0..20), &set), 0.0);
+        let other = PackedSeqVec::random(2000);
+        assert_eq!(containment(&hasher, other.as_slice(), &set), 0.0);
+    }
+}
@@ -0,0 +1,156 @@
+//! A compact `hash -> positions` index of the k-mers of a reference, for seed lookups.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+
+/// A multimap from k-mer hashes to the positions where they occur in a reference, stored as CSR-style arrays.
+///
+/// Distinct hashes are stored sorted, and the positions of the `i`th hash are
+/// `positions[offsets[i]..offsets[i+1]]`, in increasing order.
+/// Hashes occurring more than `max_frequency` times are dropped when building,
+/// as is usual for repetitive seeds in aligners.
+///
+/// Query with the same hasher that was used to build the index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeedIndex {
+    k: usize,
+    hashes: Vec<u32>,
+    offsets: Vec<u32>,
+    positions: Vec<u32>,
+    num_filtered: usize,
+}
+
+impl SeedIndex {
+    /// Index all k-mers of `reference`, dropping hashes that occur more than `max_frequency` times.
+    ///
+    /// The reference must be shorter than `2^32`.
+    pub fn build<'s>(
+        hasher: &impl KmerHasher,
+        reference: impl Seq<'s>,
+        max_frequency: usize,
+    ) -> Self {
+        assert!(
+            reference.len() <= u32::MAX as usize,
+            "The reference must be shorter than 2^32."
+        );
+        let hashes: Vec<u32> = hasher.hash_kmers_simd(reference, 1).collect();
+        // Sort by hash, and then by position.
+        let mut pairs: Vec<u64> = (0..)
+            .zip(hashes)
+            .map(|(pos, h): (u32, u32)| (h as u64) << 32 | pos as u64)
+            .collect();
+        pairs.sort_unstable();
+
+        let mut index = SeedIndex {
+            k: hasher.k(),
+            hashes: vec![],
+            offsets: vec![0],
+            positions: vec![],
+            num_filtered: 0,
+        };
+        for run in pairs.chunk_by(|a, b| a >> 32 == b >> 32) {
+            if run.len() > max_frequency {
+                index.num_filtered += 1;
+                continue;
+            }
+            index.hashes.push((run[0] >> 32) as u32);
+            index.positions.extend(run.iter().map(|&x| x as u32));
+            index.offsets.push(index.positions.len() as u32);
+        }
+        index
+    }
+
+    /// The k-mer length of the hasher used to build the index.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The number of distinct indexed hashes.
+    pub fn num_hashes(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// The total number of indexed positions.
+    pub fn num_positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// The number of distinct hashes dropped for occurring more than `max_frequency` times.
+    pub fn num_filtered(&self) -> usize {
+        self.num_filtered
+    }
+
+    /// The sorted positions of the k-mers with hash `h`, or an empty slice when it is not indexed.
+    #[inline(always)]
+    pub fn get(&self, h: u32) -> &[u32] {
+        match self.hashes.binary_search(&h) {
+            Ok(i) => &self.positions[self.offsets[i] as usize..self.offsets[i + 1] as usize],
+            Err(_) => &[],
+        }
+    }
+
+    /// All seed hits of `query`, as `(query_pos, reference_pos)` pairs ordered by query position.
+    pub fn hits<'s>(&self, hasher: &impl KmerHasher, query: impl Seq<'s>) -> Vec<(u32, u32)> {
+        assert_eq!(hasher.k(), self.k, "The query hasher must use the same k.");
+        let hashes: Vec<u32> = hasher.hash_kmers_simd(query, 1).collect();
+        let mut hits = vec![];
+        for (q, h) in (0..).zip(hashes) {
+            hits.extend(self.get(h).iter().map(|&r| (q, r)));
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::HashMap;
+
+    #[test]
+    fn index_and_query() {
+        let hasher = NtHasher::<false>::new(15);
+        // A reference with a repeat occurring 5 times.
+        let repeat = PackedSeqVec::random(100).as_slice().unpack();
+        let mut ascii = vec![];
+        for _ in 0..5 {
+            ascii.extend(PackedSeqVec::random(1000).as_slice().unpack());
+            ascii.extend(&repeat);
+        }
+        let reference = PackedSeqVec::from_ascii(&ascii);
+
+        let mut expected = HashMap::<u32, Vec<u32>>::new();
+        for (pos, h) in (0..).zip(hasher.hash_kmers_scalar(reference.as_slice())) {
+            expected.entry(h).or_default().push(pos);
+        }
+
+        let all = SeedIndex::build(&hasher, reference.as_slice(), usize::MAX);
+        assert_eq!(all.num_hashes(), expected.len());
+        assert_eq!(all.num_positions(), reference.len() - 14);
+        assert_eq!(all.num_filtered(), 0);
+        for (&h, positions) in &expected {
+            assert_eq!(all.get(h), positions);
+        }
+
+        let filtered = SeedIndex::build(&hasher, reference.as_slice(), 4);
+        assert!(filtered.num_filtered() >= 86);
+        for (&h, positions) in &expected {
+            if positions.len() > 4 {
+                assert!(filtered.get(h).is_empty());
+            } else {
+                assert_eq!(filtered.get(h), positions);
+            }
+        }
+
+        // The query contains the first 200 bases of the reference, and the repeat.
+        let query = PackedSeqVec::from_ascii(&[&ascii[..200], &repeat].concat());
+        let hits = filtered.hits(&hasher, query.as_slice());
+        let unique: Vec<(u32, u32)> = (0..186).map(|i| (i, i)).collect();
+        assert_eq!(hits[..186], unique);
+        assert_eq!(
+            all.hits(&hasher, query.as_slice()).len(),
+            hits.len() + 5 * 86
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Whole-sequence signatures derived from the k-mer hash stream.
+
+use crate::KmerHasher;
+use packed_seq::{Seq, complement_base};
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+
+type SeedHasher = BuildHasherDefault<DefaultHasher>;
+
+/// A bijective 64-bit mixer (the `splitmix64` finalizer),
+/// used to expand 32-bit k-mer hashes to 64 well-mixed bits.
+#[inline(always)]
+pub(crate) const fn mix64(x: u64) -> u64 {
+    let mut x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// An order-invariant 64-bit fingerprint of the multiset of k-mers in `seq`.
+///
+/// This is the wrapping sum of all k-mer hashes, each expanded to 64 bits.
+/// With a canonical hasher, a sequence and its reverse complement have the same fingerprint.
+/// Since the fingerprint is additive, fingerprints of pieces overlapping by `k-1`
+/// characters can be summed to get the fingerprint of the full sequence,
+/// and rotations of a circular sequence only differ in the `k-1` k-mers spanning the start.
+pub fn fingerprint_seq<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>) -> u64 {
+    hasher
+        .hash_kmers_simd(seq, 1)
+        .collect()
+        .into_iter()
+        .fold(0u64, |s, h| s.wrapping_add(mix64(h as u64)))
+}
+
+/// A 64-bit SimHash (Charikar 2002) signature of `seq`.
+///
+/// Each k-mer hash is expanded to 64 bits, and bit `i` of the signature is set when
+/// the majority of k-mers have bit `i` set.
+/// Sequences sharing most of their k-mers have signatures at a small Hamming distance,
+/// see [`simhash_distance`].
+pub fn simhash<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>) -> u64 {
+    let mut counts = [0i32; 64];
+    for h in hasher.hash_kmers_simd(seq, 1).collect() {
+        let x = mix64(h as u64);
+        for (i, c) in counts.iter_mut().enumerate() {
+            *c += ((x >> i) & 1) as i32 * 2 - 1;
+        }
+    }
+    counts
+        .iter()
+        .enumerate()
+        .fold(0, |s, (i, &c)| s | ((c > 0) as u64) << i)
+}
+
+/// The Hamming distance between two [`simhash`] signatures.
+#[inline(always)]
+pub fn simhash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// The Mersenne prime `2^61 - 1`.
+const P61: u64 = (1 << 61) - 1;
+
+#[inline(always)]
+fn mul_mod_p61(a: u64, b: u64) -> u64 {
+    let x = a as u128 * b as u128;
+    let r = (x as u64 & P61) + (x >> 61) as u64;
+    if r >= P61 { r - P61 } else { r }
+}
+
+#[inline(always)]
+fn add_mod_p61(a: u64, b: u64) -> u64 {
+    let r = a + b;
+    if r >= P61 { r - P61 } else { r }
+}
+
+/// A strand-neutral hash of whole reads, for PCR/optical duplicate detection.
+///
+/// A read and its reverse complement get the same hash.
+/// Both orientations are hashed with a polynomial hash modulo `2^61-1` at a random point,
+/// in a single forward pass, and the minimum is taken.
+///
+/// **Collisions:** for two reads of length at most `n` that are not equal or each other's reverse complement,
+/// the probability over the seed that [`Self::hash64`] collides is at most `4n / (2^61-1)`.
+/// [`Self::hash32`] adds another `2^-32`, so prefer the 64-bit version beyond a few thousand reads.
+///
+/// Only supports 2-bit DNA sequences ([`packed_seq::AsciiSeq`] and [`packed_seq::PackedSeq`]).
+/// Unlike the rolling k-mer hashers, this does not have a positional period of 32 bases.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadHasher {
+    base: u64,
+}
+
+impl Default for ReadHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadHasher {
+    pub fn new() -> Self {
+        Self::new_with_seed(0)
+    }
+
+    pub fn new_with_seed(seed: u32) -> Self {
+        let base = 2 + SeedHasher::new().hash_one(seed) % (P61 - 3);
+        Self { base }
+    }
+
+    /// 64-bit strand-neutral hash of `seq`.
+    pub fn hash64<'s>(&self, seq: impl Seq<'s>) -> u64 {
+        assert!(seq.bits_per_char() <= 2, "ReadHasher only supports DNA.");
+        // Horner's rule for the forward strand, and increasing powers for the reverse complement.
+        let mut fw = 0;
+        let mut rc = 0;
+        let mut pow = 1;
+        for b in seq.iter_bp() {
+            fw = add_mod_p61(mul_mod_p61(fw, self.base), b as u64 + 1);
+            rc = add_mod_p61(rc, mul_mod_p61(pow, complement_base(b) as u64 + 1));
+            pow = mul_mod_p61(pow, self.base);
+        }
+        // The mixing is a bijection, so it does not introduce collisions.
+        mix64(fw.min(rc))
+    }
+
+    /// 32-bit strand-neutral hash of `seq`.
+    pub fn hash32<'s>(&self, seq: impl Seq<'s>) -> u32 {
+        (self.hash64(seq) >> 32) as u32
+    }
+}
@@ -0,0 +1,81 @@
+//! Push-based consumption of the SIMD hash stream.
+
+use crate::{KmerHasher, PaddedLayout, S};
+use packed_seq::Seq;
+
+/// A consumer of blocks of 8 k-mer hashes, e.g. a Bloom filter, counter, or writer.
+///
+/// Blocks are passed in stream order of the SIMD iterator, so consecutive lanes of a block
+/// correspond to distant positions in the sequence. Use this when the order of hashes does not matter.
+pub trait HashSink {
+    /// Consume 8 hashes. Only lanes where `mask` is set (all ones) are valid;
+    /// the others are padding past the end of the sequence.
+    fn accept_block(&mut self, hashes: S, mask: S);
+}
+
+/// Feed all k-mer hashes of `seq` to `sink`, in SIMD blocks of 8.
+pub fn hash_into_sink<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>, sink: &mut impl HashSink) {
+    let hashes = hasher.hash_kmers_simd(seq, 1);
+    let positions = hashes.lane_positions(1);
+    for (hashes, (_, mask)) in hashes.it.zip(positions) {
+        sink.accept_block(hashes, mask);
+    }
+}
+
+/// Like [`hash_into_sink`], but hashes `seq` in consecutive chunks of `chunk_len` k-mers.
+///
+/// See [`KmerHasher::hash_kmers_chunked_into`] for when this helps; [`crate::DEFAULT_CHUNK_LEN`] is a reasonable default.
+pub fn hash_into_sink_chunked<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    chunk_len: usize,
+    sink: &mut impl HashSink,
+) {
+    assert!(chunk_len > 0, "Chunk length must be positive.");
+    let num_kmers = hasher.num_kmers(seq);
+    for start in (0..num_kmers).step_by(chunk_len) {
+        let end = (start + chunk_len).min(num_kmers);
+        hash_into_sink(
+            hasher,
+            crate::kmer_range_slice(hasher, seq, start..end),
+            sink,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    struct Collect(Vec<u32>);
+
+    impl HashSink for Collect {
+        fn accept_block(&mut self, hashes: S, mask: S) {
+            crate::append_filtered(hashes, mask, &mut self.0);
+        }
+    }
+
+    #[test]
+    fn sink_sees_all_hashes() {
+        let seq = PackedSeqVec::random(2000);
+        for len in [0, 3, 50, 2000] {
+            let seq = seq.slice(0..len);
+            for k in [1, 21] {
+                let hasher = NtHasher::<true>::new(k);
+                let mut expected: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+                let mut sink = Collect(vec![]);
+                hash_into_sink(&hasher, seq, &mut sink);
+                sink.0.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(sink.0, expected, "len={len} k={k}");
+
+                let mut sink = Collect(vec![]);
+                hash_into_sink_chunked(&hasher, seq, 37, &mut sink);
+                sink.0.sort_unstable();
+                assert_eq!(sink.0, expected, "len={len} k={k}");
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Keyed SipHash-1-3 hashing of packed k-mer codes.
+
+use crate::kmers::code_mapper;
+use crate::{KmerHasher, S};
+use packed_seq::Seq;
+use std::array::from_fn;
+
+/// Hashes the 2-bit packed code of each k-mer (for `k <= 32`) with SipHash-1-3 under a secret 128-bit key.
+///
+/// [`crate::NtHasher`] and friends are linear in the input, so an adversary who knows (or can probe) the seed
+/// can craft sequences with many colliding k-mers.
+/// SipHash is a keyed pseudo-random function, so with a secret random key this is infeasible.
+/// Use this when hashing untrusted input into e.g. hash tables.
+///
+/// The code is hashed as a single 8-byte message, so `hash_code(x)` equals the low 32 bits of
+/// SipHash-1-3 of `x.to_le_bytes()`. The canonical version hashes the minimum of the forward and reverse-complement codes.
+///
+/// Only supports 2-bit DNA sequences ([`packed_seq::AsciiSeq`] and [`packed_seq::PackedSeq`]).
+/// The SIMD version hashes two batches of 4 lanes, which the compiler can vectorize.
+#[derive(Clone, Copy)]
+pub struct SipKmerHasher<const CANONICAL: bool = true> {
+    k: usize,
+    key: (u64, u64),
+}
+
+impl<const CANONICAL: bool> SipKmerHasher<CANONICAL> {
+    /// Create a new [`SipKmerHasher`] for kmers of length `k <= 32`, with a fixed all-zero key.
+    ///
+    /// This offers no protection against adversarial inputs; prefer [`SipKmerHasher::new_with_key`].
+    pub fn new(k: usize) -> Self {
+        Self::new_with_key(k, [0; 16])
+    }
+
+    /// Create a new [`SipKmerHasher`] for kmers of length `k <= 32` with the given secret key.
+    pub fn new_with_key(k: usize, key: [u8; 16]) -> Self {
+        assert!(0 < k && k <= 32, "SipKmerHasher requires k <= 32.");
+        let (k0, k1) = key.split_at(8);
+        Self {
+            k,
+            key: (
+                u64::from_le_bytes(k0.try_into().unwrap()),
+                u64::from_le_bytes(k1.try_into().unwrap()),
+            ),
+        }
+    }
+
+    /// Hash a packed k-mer code.
+    #[inline(always)]
+    pub fn hash_code(&self, x: u64) -> u32 {
+        self.hash_codes([x])[0]
+    }
+
+    /// Hash `N` packed k-mer codes in parallel.
+    #[inline(always)]
+    fn hash_codes<const N: usize>(&self, x: [u64; N]) -> [u32; N] {
+        let (k0, k1) = self.key;
+        let mut v0 = [k0 ^ 0x736f_6d65_7073_6575; N];
+        let mut v1 = [k1 ^ 0x646f_7261_6e64_6f6d; N];
+        let mut v2 = [k0 ^ 0x6c79_6765_6e65_7261; N];
+        let mut v3 = [k1 ^ 0x7465_6462_7974_6573; N];
+        let round = |v0: &mut [u64; N], v1: &mut [u64; N], v2: &mut [u64; N], v3: &mut [u64; N]| {
+            for l in 0..N {
+                v0[l] = v0[l].wrapping_add(v1[l]);
+                v1[l] = v1[l].rotate_left(13) ^ v0[l];
+                v0[l] = v0[l].rotate_left(32);
+                v2[l] = v2[l].wrapping_add(v3[l]);
+                v3[l] = v3[l].rotate_left(16) ^ v2[l];
+                v0[l] = v0[l].wrapping_add(v3[l]);
+                v3[l] = v3[l].rotate_left(21) ^ v0[l];
+                v2[l] = v2[l].wrapping_add(v1[l]);
+                v1[l] = v1[l].rotate_left(17) ^ v2[l];
+                v2[l] = v2[l].rotate_left(32);
+            }
+        };
+        // The message block, followed by the final block holding the length (8 bytes).
+        for m in [x, [8 << 56; N]] {
+            for l in 0..N {
+                v3[l] ^= m[l];
+            }
+            round(&mut v0, &mut v1, &mut v2, &mut v3);
+            for l in 0..N {
+                v0[l] ^= m[l];
+            }
+        }
+        for v in &mut v2 {
+            *v ^= 0xff;
+        }
+        for _ in 0..3 {
+            round(&mut v0, &mut v1, &mut v2, &mut v3);
+        }
+        from_fn(|l| (v0[l] ^ v1[l] ^ v2[l] ^ v3[l]) as u32)
+    }
+}
+
+impl<const CANONICAL: bool> KmerHasher for SipKmerHasher<CANONICAL> {
+    const CANONICAL: bool = CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        2
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        let mut mapper = code_mapper::<CANONICAL>(self.k);
+        move |(a, _r)| self.hash_code(mapper(a))
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        let mut mappers: [_; 8] = from_fn(|_| code_mapper::<CANONICAL>(self.k));
+        move |(a, _r)| {
+            let a = a.to_array();
+            let codes: [u64; 8] = from_fn(|l| mappers[l](a[l] as u8));
+            let lo = self.hash_codes::<4>(codes[..4].try_into().unwrap());
+            let hi = self.hash_codes::<4>(codes[4..].try_into().unwrap());
+            S::new(from_fn(|l| if l < 4 { lo[l] } else { hi[l - 4] }))
+        }
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        assert!(seq.len() <= 32, "SipKmerHasher requires k <= 32.");
+        let mut mapper = code_mapper::<CANONICAL>(seq.len().max(1));
+        move |a| self.hash_code(mapper(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical_kmer_codes;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::hash::{DefaultHasher, Hasher};
+
+    #[test]
+    fn matches_std_siphash13() {
+        // `DefaultHasher::new()` is SipHash-1-3 with an all-zero key.
+        let hasher = SipKmerHasher::<false>::new(21);
+        for x in [0, 1, 0xdead_beef, u64::MAX, rand::random()] {
+            let mut std = DefaultHasher::new();
+            std.write_u64(x);
+            assert_eq!(hasher.hash_code(x), std.finish() as u32);
+        }
+    }
+
+    #[test]
+    fn hashes_codes() {
+        let seq = PackedSeqVec::random(1000);
+        let seq = seq.as_slice();
+        let key = rand::random();
+        for k in [1, 2, 16, 31, 32] {
+            let fw = SipKmerHasher::<false>::new_with_key(k, key);
+            let expected: Vec<u32> = (0..=seq.len() - k)
+                .map(|i| fw.hash_code(seq.slice(i..i + k).as_u64()))
+                .collect();
+            assert_eq!(fw.hash_kmers_scalar(seq).collect::<Vec<_>>(), expected);
+            assert_eq!(fw.hash_kmers_simd(seq, 1).collect(), expected);
+
+            let canonical = SipKmerHasher::<true>::new_with_key(k, key);
+            let expected: Vec<u32> = canonical_kmer_codes(seq, k)
+                .map(|code| canonical.hash_code(code))
+                .collect();
+            assert_eq!(canonical.hash_kmers_simd(seq, 1).collect(), expected);
+            assert_eq!(canonical.hash_seq(seq.slice(3..3 + k)), expected[3]);
+        }
+    }
+}
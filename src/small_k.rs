@@ -0,0 +1,160 @@
+//! Direct hashing of the packed code of short k-mers.
+
+use crate::kmers::code_mapper;
+use crate::{KmerHasher, S};
+use packed_seq::{ChunkIt, Delay, PaddedIt, Seq};
+
+/// A separate hash function for short k-mers (`k <= 16`) that mixes their 2-bit packed code.
+///
+/// This is *not* a faster path for [`crate::NtHasher`]: its hashes are unrelated to NtHash values,
+/// so it cannot replace an `NtHasher` wherever hashes are stored, compared, or shared between tools.
+/// Use it only when choosing the hash function for new data.
+///
+/// The `2k`-bit code is maintained with a single shift/or over a single character stream,
+/// and hashed with a multiply and xor-shift, instead of the two rotate/xor streams of NtHash.
+/// This is about 2x faster than `NtHasher` on short reads,
+/// and 1.2-1.3x faster on long sequences (see `small_k_bench` in `src/test.rs`).
+/// Since the mix is a bijection on `u32`, distinct k-mers never collide.
+///
+/// Codes are as in [`crate::canonical_kmer_codes`].
+/// The canonical version hashes the minimum of the forward and reverse-complement codes.
+///
+/// Only supports 2-bit DNA sequences ([`packed_seq::AsciiSeq`] and [`packed_seq::PackedSeq`]).
+#[derive(Clone, Copy)]
+pub struct SmallKHasher<const CANONICAL: bool = true> {
+    k: usize,
+    seed: u32,
+}
+
+impl<const CANONICAL: bool> SmallKHasher<CANONICAL> {
+    /// Create a new [`SmallKHasher`] for kmers of length `k <= 16`.
+    pub fn new(k: usize) -> Self {
+        Self::new_with_seed(k, 0)
+    }
+
+    /// Create a new [`SmallKHasher`] whose codes are xor-ed with `seed` before mixing.
+    pub fn new_with_seed(k: usize, seed: u32) -> Self {
+        assert!(0 < k && k <= 16, "SmallKHasher requires k <= 16.");
+        Self { k, seed }
+    }
+
+    /// Hash a packed k-mer code.
+    #[inline(always)]
+    pub fn hash_code(&self, code: u32) -> u32 {
+        let h = (code ^ self.seed).wrapping_mul(0x9e37_79b1);
+        h ^ (h >> 16)
+    }
+
+    /// SIMD version of [`Self::hash_code`].
+    #[inline(always)]
+    fn hash_code_simd(&self, code: S) -> S {
+        let h = (code ^ S::splat(self.seed)) * S::splat(0x9e37_79b1);
+        h ^ (h >> 16)
+    }
+}
+
+impl<const CANONICAL: bool> KmerHasher for SmallKHasher<CANONICAL> {
+    const CANONICAL: bool = CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::new(k)
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        2
+    }
+
+    /// The outgoing character is not used, so it is read at the same position as the incoming one.
+    #[inline(always)]
+    fn delay(&self) -> Delay {
+        Delay(0)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        let mut mapper = code_mapper::<CANONICAL>(self.k);
+        move |(a, _r)| self.hash_code(mapper(a) as u32)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        let shift = 2 * (self.k as u32 - 1);
+        let mask = S::splat(u32::MAX >> (32 - 2 * self.k));
+        let mut fw = S::ZERO;
+        let mut rc = S::ZERO;
+        move |(a, _r)| {
+            fw = (fw >> 2) | (a << shift);
+            let code = if CANONICAL {
+                // ^2 for complement.
+                rc = ((rc << 2) | (a ^ S::splat(2))) & mask;
+                fw.min(rc)
+            } else {
+                fw
+            };
+            self.hash_code_simd(code)
+        }
+    }
+
+    /// Reads a single character stream, since the outgoing character is not needed.
+    #[inline(always)]
+    fn hash_kmers_simd<'s>(&self, seq: impl Seq<'s>, context: usize) -> PaddedIt<impl ChunkIt<S>> {
+        let k = self.k();
+        let mut mapper = self.in_out_mapper_simd(seq);
+        seq.par_iter_bp(context + k - 1)
+            .map(move |a| mapper((a, S::ZERO)))
+            .advance(k - 1)
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        assert!(seq.len() <= 16, "SmallKHasher requires k <= 16.");
+        let mut mapper = code_mapper::<CANONICAL>(seq.len().max(1));
+        move |a| self.hash_code(mapper(a) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical_kmer_codes;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn hashes_codes() {
+        let seq = PackedSeqVec::random(1000);
+        let seq = seq.as_slice();
+        for k in [1, 2, 4, 8, 15, 16] {
+            let fw = SmallKHasher::<false>::new_with_seed(k, 31415);
+            let expected: Vec<u32> = (0..=seq.len() - k)
+                .map(|i| fw.hash_code(seq.slice(i..i + k).as_u64() as u32))
+                .collect();
+            assert_eq!(fw.hash_kmers_scalar(seq).collect::<Vec<_>>(), expected);
+            assert_eq!(fw.hash_kmers_simd(seq, 1).collect(), expected);
+            assert_eq!(fw.hash_seq(seq.slice(7..7 + k)), expected[7]);
+
+            let canonical = SmallKHasher::<true>::new(k);
+            let expected: Vec<u32> = canonical_kmer_codes(seq, k)
+                .map(|code| canonical.hash_code(code as u32))
+                .collect();
+            assert_eq!(
+                canonical.hash_kmers_scalar(seq).collect::<Vec<_>>(),
+                expected
+            );
+            assert_eq!(canonical.hash_kmers_simd(seq, 1).collect(), expected);
+            let mut rc = canonical
+                .hash_kmers_scalar(seq.to_revcomp().as_slice())
+                .collect::<Vec<_>>();
+            rc.reverse();
+            assert_eq!(rc, expected);
+        }
+    }
+}
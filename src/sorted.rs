@@ -0,0 +1,195 @@
+//! Merging and set algebra on sorted hash vectors,
+//! e.g. as read with [`crate::read_hashes`] from a [`crate::DumpEncoding::SortedDeltaVarint`] dump.
+//!
+//! All inputs must be sorted. Set operations return sorted and deduplicated hashes.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Below this length, [`slice::sort_unstable`] beats [`bucket_sort`].
+const BUCKET_THRESHOLD: usize = 1 << 12;
+
+fn debug_assert_sorted(hashes: &[u32]) {
+    debug_assert!(hashes.is_sorted(), "Input hashes must be sorted.");
+}
+
+/// K-way merge of sorted `streams` into a single sorted vector, keeping duplicates.
+pub fn merge_sorted(streams: &[&[u32]]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(streams.iter().map(|s| s.len()).sum());
+    // Heap of `(next value, stream, position)`.
+    let mut heap: BinaryHeap<Reverse<(u32, usize, usize)>> = streams
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| {
+            debug_assert_sorted(s);
+            s.first().map(|&h| Reverse((h, i, 0)))
+        })
+        .collect();
+    while let Some(Reverse((h, i, pos))) = heap.pop() {
+        out.push(h);
+        if let Some(&next) = streams[i].get(pos + 1) {
+            heap.push(Reverse((next, i, pos + 1)));
+        }
+    }
+    out
+}
+
+/// The union of sorted `streams`, sorted and deduplicated.
+pub fn union_sorted(streams: &[&[u32]]) -> Vec<u32> {
+    let mut out = merge_sorted(streams);
+    out.dedup();
+    out
+}
+
+/// The hashes in both `a` and `b`, sorted and deduplicated.
+pub fn intersect_sorted(a: &[u32], b: &[u32]) -> Vec<u32> {
+    debug_assert_sorted(a);
+    debug_assert_sorted(b);
+    let mut out = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                if out.last() != Some(&a[i]) {
+                    out.push(a[i]);
+                }
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+/// The hashes in `a` but not in `b`, sorted and deduplicated.
+pub fn difference_sorted(a: &[u32], b: &[u32]) -> Vec<u32> {
+    debug_assert_sorted(a);
+    debug_assert_sorted(b);
+    let mut out = vec![];
+    let mut j = 0;
+    for &h in a {
+        while j < b.len() && b[j] < h {
+            j += 1;
+        }
+        if (j == b.len() || b[j] != h) && out.last() != Some(&h) {
+            out.push(h);
+        }
+    }
+    out
+}
+
+/// The distinct k-mer hashes of `seq`, sorted.
+///
+/// The hashes are collected from [`KmerHasher::hash_kmers_simd`], scattered into buckets on their high bits,
+/// and each bucket is sorted while it fits in cache.
+/// For uniformly distributed hashes this is faster than sorting the whole vector at once.
+pub fn unique_sorted_hashes<'s>(hasher: &impl KmerHasher, seq: impl Seq<'s>) -> Vec<u32> {
+    let mut hashes = hasher.hash_kmers_simd(seq, 1).collect();
+    bucket_sort(&mut hashes);
+    hashes.dedup();
+    hashes
+}
+
+/// Sort `v` by scattering it into buckets on the high bits, and then sorting each cache-sized bucket.
+pub(crate) fn bucket_sort(v: &mut Vec<u32>) {
+    if v.len() < BUCKET_THRESHOLD {
+        v.sort_unstable();
+        return;
+    }
+    // Aim for around 1024 values per bucket.
+    let bits = (v.len() / 1024).ilog2().clamp(1, 16);
+    let shift = 32 - bits;
+    let mut starts = vec![0; (1 << bits) + 1];
+    for &x in v.iter() {
+        starts[(x >> shift) as usize + 1] += 1;
+    }
+    for i in 1..starts.len() {
+        starts[i] += starts[i - 1];
+    }
+    let mut offsets = starts.clone();
+    let mut buf = vec![0; v.len()];
+    for &x in v.iter() {
+        let b = (x >> shift) as usize;
+        buf[offsets[b]] = x;
+        offsets[b] += 1;
+    }
+    for w in starts.windows(2) {
+        buf[w[0]..w[1]].sort_unstable();
+    }
+    *v = buf;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::collections::BTreeSet;
+
+    fn random_sorted(n: usize, max: u32) -> Vec<u32> {
+        let mut v: Vec<u32> = (0..n).map(|_| rand::random_range(0..max)).collect();
+        v.sort_unstable();
+        v
+    }
+
+    #[test]
+    fn set_algebra_naive() {
+        for max in [10, 1000, u32::MAX] {
+            let streams: Vec<Vec<u32>> = (0..5).map(|i| random_sorted(100 * i, max)).collect();
+            let slices: Vec<&[u32]> = streams.iter().map(|s| s.as_slice()).collect();
+
+            let mut all: Vec<u32> = streams.concat();
+            all.sort_unstable();
+            assert_eq!(merge_sorted(&slices), all);
+            all.dedup();
+            assert_eq!(union_sorted(&slices), all);
+            assert_eq!(merge_sorted(&[]), vec![]);
+
+            let a: BTreeSet<u32> = streams[3].iter().copied().collect();
+            let b: BTreeSet<u32> = streams[4].iter().copied().collect();
+            let (sa, sb) = (&streams[3], &streams[4]);
+            assert_eq!(
+                intersect_sorted(sa, sb),
+                a.intersection(&b).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                difference_sorted(sa, sb),
+                a.difference(&b).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                difference_sorted(sa, &[]),
+                a.iter().copied().collect::<Vec<_>>()
+            );
+            assert_eq!(intersect_sorted(sa, &[]), vec![]);
+        }
+    }
+
+    #[test]
+    fn bucket() {
+        for n in [0, 1, 100, BUCKET_THRESHOLD, 100_000] {
+            for max in [10, 1 << 16, u32::MAX] {
+                let mut v: Vec<u32> = (0..n).map(|_| rand::random_range(0..max)).collect();
+                let mut expected = v.clone();
+                expected.sort_unstable();
+                bucket_sort(&mut v);
+                assert_eq!(v, expected, "n={n} max={max}");
+            }
+        }
+    }
+
+    #[test]
+    fn unique_sorted() {
+        let hasher = <NtHasher>::new(5);
+        let seq = PackedSeqVec::random(100_000);
+        let mut expected: Vec<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+        expected.sort_unstable();
+        expected.dedup();
+        // At most 4^5/2 canonical 5-mers.
+        assert!(expected.len() <= 512);
+        assert_eq!(unique_sorted_hashes(&hasher, seq.as_slice()), expected);
+    }
+}
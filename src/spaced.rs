@@ -0,0 +1,224 @@
+//! Spaced seeds: k-mer hashes that ignore some positions inside each k-mer.
+
+use crate::nthash::CharHasher;
+use crate::{KmerHasher, S};
+use packed_seq::Seq;
+
+/// Wraps a [`CharHasher`] (e.g. [`crate::NtHasher`]) to ignore the characters at some positions of each k-mer,
+/// as given by a runtime mask.
+///
+/// The hash of a k-mer equals the hash of the same k-mer with all ignored characters replaced by `A`,
+/// minus the contribution of those `A`s.
+/// Each ignored position costs one additional table lookup per k-mer.
+///
+/// For canonical hashers, the mask should be symmetric for the hash to be invariant under reverse complement.
+#[derive(Clone)]
+pub struct SpacedHasher<CH> {
+    hasher: CH,
+    /// `(j, fw_rot, rc_rot)` for each ignored position `j` of the k-mer.
+    ignored: Vec<(usize, u32, u32)>,
+    mask: Vec<bool>,
+}
+
+impl<CH: CharHasher> SpacedHasher<CH> {
+    /// `mask[j]` is `false` for positions `j` to ignore. `k` is `mask.len()`.
+    pub fn new(mask: &[bool]) -> Self {
+        Self::with_hasher(CH::new(mask.len()), mask)
+    }
+
+    /// Parse a pattern like `"1101011"`, where `0`s (or `-`, `_`, `x`) indicate positions to ignore.
+    pub fn from_pattern(pattern: &str) -> Self {
+        let mask: Vec<bool> = pattern
+            .chars()
+            .map(|c| match c {
+                '1' | '#' => true,
+                '0' | '-' | '_' | 'x' => false,
+                _ => panic!("Unexpected character {c:?} in spaced seed pattern."),
+            })
+            .collect();
+        Self::new(&mask)
+    }
+
+    /// Repeat the periodic `unit` pattern to cover `k` positions.
+    ///
+    /// E.g. `unit = [true, true, false]` uses 2 bases and skips 1, repeatedly.
+    pub fn periodic(unit: &[bool], k: usize) -> Self {
+        assert!(
+            unit.contains(&true),
+            "The pattern must use at least one position."
+        );
+        let mask: Vec<bool> = unit.iter().copied().cycle().take(k).collect();
+        Self::new(&mask)
+    }
+
+    /// An `(m, n)` skip-mer with `weight` used bases: out of each `n` consecutive positions,
+    /// the first `m` are used.
+    ///
+    /// The k-mer starts and ends with a block of `m` used positions, so that the mask is symmetric,
+    /// and hence `k = n * (weight / m - 1) + m`. Requires `weight` to be a multiple of `m`.
+    pub fn skip_mer(m: usize, n: usize, weight: usize) -> Self {
+        assert!(0 < m && m <= n, "Skip-mers require 0 < m <= n.");
+        assert!(
+            weight > 0 && weight.is_multiple_of(m),
+            "The weight must be a positive multiple of m."
+        );
+        let k = n * (weight / m - 1) + m;
+        let unit: Vec<bool> = (0..n).map(|j| j < m).collect();
+        Self::periodic(&unit, k)
+    }
+
+    /// Wrap an existing hasher. Requires `mask.len() == hasher.k()`.
+    pub fn with_hasher(hasher: CH, mask: &[bool]) -> Self {
+        let k = hasher.k();
+        assert_eq!(mask.len(), k, "The mask must have length k.");
+        let ignored = (0..k)
+            .filter(|&j| !mask[j])
+            .map(|j| {
+                (
+                    j,
+                    ((k - 1 - j) as u32).wrapping_mul(CH::R) % 32,
+                    (j as u32).wrapping_mul(CH::R) % 32,
+                )
+            })
+            .collect();
+        Self {
+            hasher,
+            ignored,
+            mask: mask.to_vec(),
+        }
+    }
+
+    /// The mask of positions that are used.
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    /// The number of positions that are used.
+    pub fn weight(&self) -> usize {
+        self.mask.iter().filter(|&&b| b).count()
+    }
+}
+
+#[inline(always)]
+fn simd_rotl(x: S, r: u32) -> S {
+    (x << r) | (x >> ((32 - r) % 32))
+}
+
+impl<CH: CharHasher> KmerHasher for SpacedHasher<CH> {
+    const CANONICAL: bool = CH::CANONICAL;
+
+    /// A spaced hasher that does not ignore any positions.
+    fn new(k: usize) -> Self {
+        Self::new(&vec![true; k])
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        CH::BITS_PER_CHAR
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        let k = self.k();
+
+        let mut fw = h.fw_init();
+        let mut rc = h.rc_init();
+        // The last `k` added characters; the k-mer starts at `buf[pos]`.
+        let mut buf = vec![0u8; k];
+        let mut pos = 0;
+
+        move |(a, r)| {
+            buf[pos] = a;
+            pos = if pos + 1 == k { 0 } else { pos + 1 };
+
+            let mut fw_out = fw.rotate_left(CH::R) ^ h.f(a);
+            fw = fw_out ^ h.f_rot(r);
+            let mut rc_out = 0;
+            if CH::CANONICAL {
+                rc_out = rc.rotate_right(CH::R) ^ h.c_rot(a);
+                rc = rc_out ^ h.c(r);
+            }
+            for &(j, fw_rot, rc_rot) in &self.ignored {
+                let b = buf[(pos + j) % k];
+                fw_out ^= h.f(b).rotate_left(fw_rot);
+                if CH::CANONICAL {
+                    rc_out ^= h.c(b).rotate_left(rc_rot);
+                }
+            }
+            if CH::CANONICAL {
+                fw_out.wrapping_add(rc_out)
+            } else {
+                fw_out
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        let k = self.k();
+
+        let mut fw = S::splat(h.fw_init());
+        let mut rc = S::splat(h.rc_init());
+        let shl = S::splat(CH::R);
+        let shr = S::splat(32 - CH::R);
+        let mut buf = vec![S::splat(0); k];
+        let mut pos = 0;
+
+        move |(a, r)| {
+            buf[pos] = a;
+            pos = if pos + 1 == k { 0 } else { pos + 1 };
+
+            let mut fw_out = ((fw << shl) | (fw >> shr)) ^ h.simd_f(a);
+            fw = fw_out ^ h.simd_f_rot(r);
+            let mut rc_out = S::splat(0);
+            if CH::CANONICAL {
+                rc_out = ((rc >> shl) | (rc << shr)) ^ h.simd_c_rot(a);
+                rc = rc_out ^ h.simd_c(r);
+            }
+            for &(j, fw_rot, rc_rot) in &self.ignored {
+                let idx = if pos + j >= k { pos + j - k } else { pos + j };
+                let b = buf[idx];
+                fw_out ^= simd_rotl(h.simd_f(b), fw_rot);
+                if CH::CANONICAL {
+                    rc_out ^= simd_rotl(h.simd_c(b), rc_rot);
+                }
+            }
+            if CH::CANONICAL {
+                // Wrapping SIMD add
+                fw_out + rc_out
+            } else {
+                fw_out
+            }
+        }
+    }
+
+    /// Characters at positions `j` of `seq` with `mask[j] == false` are ignored.
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.check_alphabet(seq).unwrap();
+        let h = &self.hasher;
+        let mut fw = 0u32;
+        let mut rc = 0u32;
+        let mut i = 0;
+        move |a| {
+            let used = self.mask.get(i).copied().unwrap_or(true);
+            i += 1;
+            fw = fw.rotate_left(CH::R) ^ if used { h.f(a) } else { 0 };
+            if CH::CANONICAL {
+                rc = rc.rotate_right(CH::R) ^ if used { h.c_rot(a) } else { 0 };
+                fw.wrapping_add(rc)
+            } else {
+                fw
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Instrumentation counters for long-running hashing services.
+
+use crate::{HashError, KmerHasher, PaddedLayout, S};
+use packed_seq::{ChunkIt, Delay, PackedNSeq, PaddedIt, Seq};
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// A snapshot of the counters of a [`StatsHasher`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashStats {
+    /// The number of bases in all hashed sequences.
+    pub bases: u64,
+    /// The number of k-mer hashes emitted, by both scalar and SIMD passes.
+    pub kmers: u64,
+    /// The number of emitted k-mers that were ambiguous (see [`KmerHasher::hash_valid_kmers_scalar`]).
+    pub ambiguous_kmers: u64,
+    /// The number of SIMD chunks of 8 lanes processed.
+    pub chunks: u64,
+    /// The number of k-mer hashes emitted by scalar passes.
+    pub scalar_kmers: u64,
+}
+
+impl HashStats {
+    /// The fraction of k-mers that were hashed by scalar rather than SIMD passes, or `0` when nothing was hashed.
+    pub fn scalar_tail_fraction(&self) -> f64 {
+        if self.kmers == 0 {
+            0.0
+        } else {
+            self.scalar_kmers as f64 / self.kmers as f64
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    bases: AtomicU64,
+    kmers: AtomicU64,
+    ambiguous_kmers: AtomicU64,
+    chunks: AtomicU64,
+    scalar_kmers: AtomicU64,
+}
+
+/// A [`KmerHasher`] wrapper that counts the work done by hashing passes.
+///
+/// Counters are updated with relaxed atomics, so a shared `&StatsHasher` can be used from multiple threads.
+/// Bases, k-mers and chunks are counted when a pass starts; ambiguous k-mers as they are emitted.
+/// Only the `hash_kmers_*` and `hash_valid_kmers_*` entry points are counted, not the raw mappers.
+///
+/// Requires the `stats` feature.
+pub struct StatsHasher<H: KmerHasher> {
+    hasher: H,
+    counters: Counters,
+}
+
+impl<H: KmerHasher> StatsHasher<H> {
+    /// Wrap `hasher`, with all counters at `0`.
+    pub fn wrap(hasher: H) -> Self {
+        Self {
+            hasher,
+            counters: Counters::default(),
+        }
+    }
+
+    /// The wrapped hasher.
+    pub fn inner(&self) -> &H {
+        &self.hasher
+    }
+
+    /// A snapshot of the current counters.
+    pub fn stats(&self) -> HashStats {
+        let c = &self.counters;
+        HashStats {
+            bases: c.bases.load(Relaxed),
+            kmers: c.kmers.load(Relaxed),
+            ambiguous_kmers: c.ambiguous_kmers.load(Relaxed),
+            chunks: c.chunks.load(Relaxed),
+            scalar_kmers: c.scalar_kmers.load(Relaxed),
+        }
+    }
+
+    /// Reset all counters to `0`, returning their previous values.
+    pub fn take_stats(&self) -> HashStats {
+        let c = &self.counters;
+        HashStats {
+            bases: c.bases.swap(0, Relaxed),
+            kmers: c.kmers.swap(0, Relaxed),
+            ambiguous_kmers: c.ambiguous_kmers.swap(0, Relaxed),
+            chunks: c.chunks.swap(0, Relaxed),
+            scalar_kmers: c.scalar_kmers.swap(0, Relaxed),
+        }
+    }
+
+    fn count_scalar(&self, len: usize) {
+        let kmers = len.saturating_sub(self.k() - 1) as u64;
+        self.counters.bases.fetch_add(len as u64, Relaxed);
+        self.counters.kmers.fetch_add(kmers, Relaxed);
+        self.counters.scalar_kmers.fetch_add(kmers, Relaxed);
+    }
+
+    fn count_simd(&self, len: usize, it: &PaddedIt<impl ChunkIt<S>>) {
+        let chunks = it.it.len();
+        self.counters.bases.fetch_add(len as u64, Relaxed);
+        self.counters
+            .kmers
+            .fetch_add((8 * chunks - it.padding) as u64, Relaxed);
+        self.counters.chunks.fetch_add(chunks as u64, Relaxed);
+    }
+}
+
+impl<H: KmerHasher> KmerHasher for StatsHasher<H> {
+    const CANONICAL: bool = H::CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::wrap(H::new(k))
+    }
+
+    #[inline(always)]
+    fn is_canonical(&self) -> bool {
+        self.hasher.is_canonical()
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        self.hasher.max_bits_per_char()
+    }
+
+    #[inline(always)]
+    fn check_alphabet<'s>(&self, seq: impl Seq<'s>) -> Result<(), HashError> {
+        self.hasher.check_alphabet(seq)
+    }
+
+    #[inline(always)]
+    fn delay(&self) -> Delay {
+        self.hasher.delay()
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.hasher.in_out_mapper_scalar(seq)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.hasher.in_out_mapper_simd(seq)
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.hasher.mapper(seq)
+    }
+
+    #[inline(always)]
+    fn hash_kmers_scalar<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        self.count_scalar(seq.len());
+        self.hasher.hash_kmers_scalar(seq)
+    }
+
+    #[inline(always)]
+    fn hash_kmers_simd<'s>(&self, seq: impl Seq<'s>, context: usize) -> PaddedIt<impl ChunkIt<S>> {
+        let it = self.hasher.hash_kmers_simd(seq, context);
+        self.count_simd(seq.len(), &it);
+        it
+    }
+
+    #[inline(always)]
+    fn hash_valid_kmers_scalar<'s>(
+        &self,
+        nseq: PackedNSeq<'s>,
+    ) -> impl ExactSizeIterator<Item = u32> {
+        self.count_scalar(nseq.seq.len());
+        self.hasher.hash_valid_kmers_scalar(nseq).inspect(|&h| {
+            if h == u32::MAX {
+                self.counters.ambiguous_kmers.fetch_add(1, Relaxed);
+            }
+        })
+    }
+
+    #[inline(always)]
+    fn hash_valid_kmers_simd<'s, 't>(
+        &'t self,
+        nseq: PackedNSeq<'s>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<S> + use<'s, 't, H>> {
+        let it = self.hasher.hash_valid_kmers_simd(nseq, context);
+        self.count_simd(nseq.seq.len(), &it);
+        let mut positions = it.lane_positions(context);
+        it.map(move |h: S| {
+            let (_, mask) = positions.next().unwrap();
+            let ambiguous = (h.simd_eq(S::MAX) & mask).to_bitmask();
+            if ambiguous != 0 {
+                self.counters
+                    .ambiguous_kmers
+                    .fetch_add(ambiguous.count_ones() as u64, Relaxed);
+            }
+            h
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedNSeqVec, PackedSeqVec, SeqVec};
+
+    #[test]
+    fn counts_passes() {
+        let hasher = StatsHasher::<NtHasher>::new(21);
+        let seq = PackedSeqVec::random(1000);
+        assert_eq!(hasher.hash_kmers_scalar(seq.as_slice()).count(), 980);
+        let simd = hasher.hash_kmers_simd(seq.as_slice(), 1).collect();
+        assert_eq!(simd.len(), 980);
+
+        let stats = hasher.take_stats();
+        assert_eq!(stats.bases, 2000);
+        assert_eq!(stats.kmers, 2 * 980);
+        assert_eq!(stats.scalar_kmers, 980);
+        assert_eq!(stats.scalar_tail_fraction(), 0.5);
+        assert!(stats.chunks >= 980 / 8);
+        assert_eq!(stats.ambiguous_kmers, 0);
+        assert_eq!(hasher.stats(), HashStats::default());
+    }
+
+    #[test]
+    fn counts_ambiguous() {
+        let hasher = StatsHasher::<NtHasher>::new(5);
+        let mut ascii = vec![b'A'; 200];
+        ascii[50] = b'N';
+        ascii[150] = b'N';
+        let nseq = PackedNSeqVec::from_ascii(&ascii);
+        let scalar: Vec<u32> = hasher.hash_valid_kmers_scalar(nseq.as_slice()).collect();
+        assert_eq!(hasher.take_stats().ambiguous_kmers, 10);
+        let simd = hasher.hash_valid_kmers_simd(nseq.as_slice(), 1).collect();
+        assert_eq!(scalar, simd);
+        assert_eq!(hasher.take_stats().ambiguous_kmers, 10);
+    }
+}
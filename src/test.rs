@@ -57,12 +57,29 @@ fn test_hash<H: KmerHasher>(hasher: impl Fn(usize) -> H, test_plaintext: bool) {
         let scalar_packed = hasher.hash_kmers_scalar(packed_seq).collect::<Vec<_>>();
         let simd_ascii = hasher.hash_kmers_simd(ascii_seq, 1).collect();
         let simd_packed = hasher.hash_kmers_simd(packed_seq, 1).collect();
+        let iter = hasher
+            .hash_kmers_iter(packed_seq.iter_bp())
+            .collect::<Vec<_>>();
 
         let len = ascii_seq.len();
         assert_eq!(scalar_ascii, naive, "k={k}, len={len}");
         assert_eq!(scalar_packed, naive, "k={k}, len={len}");
         assert_eq!(simd_ascii, naive, "k={k}, len={len}");
         assert_eq!(simd_packed, naive, "k={k}, len={len}");
+        assert_eq!(iter, naive, "k={k}, len={len}");
+
+        if naive.len() >= 8 {
+            let pos: [usize; 8] = from_fn(|j| j * (naive.len() - 1) / 7);
+            let expected = pos.map(|p| naive[p]);
+            let packed = hasher.hash_kmers_batch8(pos.map(|p| packed_seq.slice(p..p + k)));
+            let ascii = hasher.hash_kmers_batch8(pos.map(|p| ascii_seq.slice(p..p + k)));
+            assert_eq!(packed.to_array(), expected, "k={k}, len={len}");
+            assert_eq!(ascii.to_array(), expected, "k={k}, len={len}");
+        }
+
+        let prefixes = hasher.hash_prefixes(packed_seq).collect::<Vec<_>>();
+        assert_eq!(hasher.hash_prefixes_simd(packed_seq).collect(), prefixes);
+        assert_eq!(hasher.hash_prefixes_simd(ascii_seq).collect(), prefixes);
 
         // Hashes of plaintext chars will differ from hashing corresponding packed data.
         if test_plaintext {
@@ -73,28 +90,263 @@ fn test_hash<H: KmerHasher>(hasher: impl Fn(usize) -> H, test_plaintext: bool) {
     });
 }
 
+fn test_suffixes<H: SuffixHasher>(hasher: impl Fn(usize) -> H) {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let len = packed_seq.len();
+        if len > 100 {
+            return;
+        }
+        let hasher = hasher(k);
+        let naive = (0..len)
+            .rev()
+            .map(|i| hasher.hash_seq(packed_seq.slice(i..len)))
+            .collect_vec();
+        let suffixes = hasher.hash_suffixes(packed_seq).collect_vec();
+        assert_eq!(suffixes, naive, "k={k}, len={len}");
+    });
+}
+
 #[test]
 fn nthash_forward() {
     test_hash(NtHasher::<false>::new, false);
     test_hash(|k| NtHasher::<false>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| NtHasher::<false>::new_with_seed(k, 31415));
 }
 
 #[test]
 fn nthash_canonical() {
     test_hash(NtHasher::<true>::new, false);
     test_hash(|k| NtHasher::<true>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| NtHasher::<true>::new_with_seed(k, 31415));
+}
+
+#[test]
+fn nthash_static() {
+    static FORWARD: NtHasher<false> = NtHasher::new(21);
+    static CANONICAL: NtHasher = NtHasher::new(31);
+    let seq = PackedSeqVec::random(1000);
+    assert_eq!(
+        FORWARD.hash_kmers_simd(seq.as_slice(), 1).collect(),
+        <NtHasher<false> as nthash::CharHasher>::new_with_seed(21, None)
+            .hash_kmers_simd(seq.as_slice(), 1)
+            .collect()
+    );
+    assert_eq!(
+        CANONICAL.hash_kmers_simd(seq.as_slice(), 1).collect(),
+        <NtHasher as nthash::CharHasher>::new_with_seed(31, None)
+            .hash_kmers_simd(seq.as_slice(), 1)
+            .collect()
+    );
+}
+
+#[test]
+fn forward_canonical_views() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let canonical = NtHasher::<true>::new_with_seed(k, 31415);
+        let forward = NtHasher::<false>::new_with_seed(k, 31415);
+        assert_eq!(
+            canonical
+                .as_forward()
+                .hash_kmers_scalar(packed_seq)
+                .collect_vec(),
+            forward.hash_kmers_scalar(packed_seq).collect_vec()
+        );
+        assert_eq!(
+            forward
+                .as_canonical()
+                .hash_kmers_simd(packed_seq, 1)
+                .collect(),
+            canonical.hash_kmers_simd(packed_seq, 1).collect()
+        );
+        let table = TableHasher::<true>::new_with_seed(k, 31415);
+        assert_eq!(
+            table.as_forward().hash_kmers_simd(packed_seq, 1).collect(),
+            TableHasher::<false>::new_with_seed(k, 31415)
+                .hash_kmers_simd(packed_seq, 1)
+                .collect()
+        );
+        let mul = MulHasher::<false>::new_with_seed(k, 31415);
+        assert_eq!(
+            mul.as_canonical().hash_kmers_simd(packed_seq, 1).collect(),
+            MulHasher::<true>::new_with_seed(k, 31415)
+                .hash_kmers_simd(packed_seq, 1)
+                .collect()
+        );
+    });
 }
 
 #[test]
 fn mulhash_forward() {
     test_hash(MulHasher::<false>::new, false);
     test_hash(|k| MulHasher::<false>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| MulHasher::<false>::new_with_seed(k, 31415));
 }
 
 #[test]
 fn mulhash_canonical() {
     test_hash(MulHasher::<true>::new, false);
     test_hash(|k| MulHasher::<true>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| MulHasher::<true>::new_with_seed(k, 31415));
+}
+
+#[test]
+fn feistel() {
+    test_hash(
+        |k| FeistelHasher::with_key(NtHasher::<true>::new(k), 31415),
+        false,
+    );
+    test_hash(
+        |k| FeistelHasher::with_key(AntiLexHasher::<false>::new(k), 31415),
+        false,
+    );
+    test_suffixes(|k| FeistelHasher::with_key(NtHasher::<true>::new(k), 31415));
+    test_suffixes(|k| DynNtHasher::<7>::new_with_seed(k, 31415, true));
+}
+
+#[test]
+fn tablehash_forward() {
+    test_hash(TableHasher::<false>::new, false);
+    test_hash(|k| TableHasher::<false>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| TableHasher::<false>::new_with_seed(k, 31415));
+}
+
+#[test]
+fn tablehash_canonical() {
+    test_hash(TableHasher::<true>::new, false);
+    test_hash(|k| TableHasher::<true>::new_with_seed(k, 31415), false);
+    test_suffixes(|k| TableHasher::<true>::new_with_seed(k, 31415));
+}
+
+#[test]
+fn tablehash_bytes() {
+    test_on_inputs(|k, slice, _ascii_seq, _packed_seq| {
+        let hasher = TableHasher::<false>::new(k);
+
+        let naive = slice
+            .windows(k)
+            .map(|seq| hasher.hash_seq(seq))
+            .collect::<Vec<_>>();
+        let scalar = hasher.hash_kmers_scalar(slice).collect::<Vec<_>>();
+        let simd = hasher.hash_kmers_simd(slice, 1).collect();
+
+        let len = slice.len();
+        assert_eq!(scalar, naive, "k={k}, len={len}");
+        assert_eq!(simd, naive, "k={k}, len={len}");
+        if naive.len() >= 8 {
+            let kmers: [&[u8]; 8] = from_fn(|j| &slice[j..j + k]);
+            assert_eq!(hasher.hash_kmers_batch8(kmers).to_array(), naive[..8]);
+        }
+    });
+}
+
+#[test]
+fn runtime_canonical_wrappers() {
+    for canonical in [false, true] {
+        let hasher = <DynNtHasher>::new(21, canonical);
+        assert_eq!(hasher.is_canonical(), canonical);
+        assert_eq!(
+            FeistelHasher::with_key(hasher.clone(), 1).is_canonical(),
+            canonical
+        );
+        assert_eq!(ValidatingHasher::wrap(hasher).is_canonical(), canonical);
+    }
+}
+
+#[test]
+fn runtime_canonical() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        for canonical in [false, true] {
+            let hasher = DynNtHasher::<7>::new_with_seed(k, 31415, canonical);
+            assert_eq!(hasher.is_canonical(), canonical);
+            let expected = if canonical {
+                NtHasher::<true>::new_with_seed(k, 31415)
+                    .hash_kmers_simd(packed_seq, 1)
+                    .collect()
+            } else {
+                NtHasher::<false>::new_with_seed(k, 31415)
+                    .hash_kmers_simd(packed_seq, 1)
+                    .collect()
+            };
+            let scalar = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+            let simd = hasher.hash_kmers_simd(packed_seq, 1).collect();
+            assert_eq!(scalar, expected, "k={k} canonical={canonical}");
+            assert_eq!(simd, expected, "k={k} canonical={canonical}");
+
+            let prefixes = hasher.hash_prefixes(packed_seq).collect_vec();
+            assert_eq!(hasher.hash_prefixes_simd(packed_seq).collect(), prefixes);
+            let len = packed_seq.len();
+            let suffixes = hasher.hash_suffixes(packed_seq).collect_vec();
+            if len > 0 {
+                assert_eq!(suffixes[len - 1], hasher.hash_seq(packed_seq));
+            }
+        }
+    });
+}
+
+/// A random mask of length `k`, optionally symmetric.
+fn random_mask(k: usize, symmetric: bool) -> Vec<bool> {
+    let mut mask: Vec<bool> = (0..k).map(|_| rand::random_bool(0.7)).collect();
+    if symmetric {
+        for j in 0..k / 2 {
+            mask[k - 1 - j] = mask[j];
+        }
+    }
+    mask
+}
+
+#[test]
+fn spaced_forward() {
+    test_hash(
+        |k| SpacedHasher::<NtHasher<false>>::new(&random_mask(k, false)),
+        false,
+    );
+    test_hash(
+        |k| SpacedHasher::<MulHasher<false>>::new(&random_mask(k, false)),
+        false,
+    );
+}
+
+#[test]
+fn spaced_canonical() {
+    test_hash(
+        |k| SpacedHasher::<NtHasher<true>>::new(&random_mask(k, false)),
+        false,
+    );
+}
+
+#[test]
+fn spaced_ignores_positions() {
+    let hasher = SpacedHasher::<NtHasher<true>>::from_pattern("11011");
+    let a = AsciiSeqVec::from_ascii(b"ACGTAACATA");
+    let b = AsciiSeqVec::from_ascii(b"ACTTAACCTA");
+    let ha = hasher.hash_kmers_simd(a.as_slice(), 1).collect();
+    let hb = hasher.hash_kmers_simd(b.as_slice(), 1).collect();
+    assert_eq!(ha[0], hb[0]);
+    assert_eq!(ha[5], hb[5]);
+    assert_ne!(ha[1], hb[1]);
+    assert_eq!(hasher.weight(), 4);
+}
+
+#[test]
+fn skip_mers() {
+    let hasher = SpacedHasher::<NtHasher<true>>::skip_mer(2, 3, 6);
+    assert_eq!(hasher.k(), 8);
+    assert_eq!(hasher.weight(), 6);
+    assert_eq!(
+        hasher.mask(),
+        SpacedHasher::<NtHasher<true>>::from_pattern("11011011").mask()
+    );
+    let seq = PackedSeqVec::random(1000);
+    let seq = seq.as_slice();
+    let fw = hasher.hash_kmers_simd(seq, 1).collect();
+    let mut rc = hasher
+        .hash_kmers_simd(seq.to_revcomp().as_slice(), 1)
+        .collect();
+    rc.reverse();
+    assert_eq!(fw, rc);
+
+    let periodic = SpacedHasher::<NtHasher<false>>::periodic(&[true, false], 5);
+    assert_eq!(periodic.mask(), [true, false, true, false, true]);
 }
 
 #[test]
@@ -102,11 +354,85 @@ fn anti_lex_forward() {
     test_hash(AntiLexHasher::<false>::new, true);
 }
 
+#[test]
+fn anti_lex_bytes() {
+    test_hash(AntiLexHasher::<false>::new_bytes, true);
+    test_on_inputs(|k, slice, _ascii_seq, _packed_seq| {
+        let hasher = AntiLexHasher::<false>::new_bytes(k);
+        let naive = slice
+            .windows(k)
+            .map(|w| {
+                // Read the last (at most) 4 characters right to left, and invert the last one.
+                let m = k.min(4);
+                let shift = 8 * (m - 1);
+                let fw = (0..m).fold(0u32, |fw, t| fw | (w[k - 1 - t] as u32) << (shift - 8 * t));
+                fw ^ (0xff << shift)
+            })
+            .collect_vec();
+        assert_eq!(hasher.hash_kmers_scalar(slice).collect_vec(), naive);
+        assert_eq!(hasher.hash_kmers_simd(slice, 1).collect(), naive);
+    });
+}
+
 #[test]
 fn anti_lex_canonical() {
     test_hash(AntiLexHasher::<true>::new, true);
 }
 
+#[test]
+fn anti_lex_extremum() {
+    for extremum in [Extremum::Min, Extremum::Max, Extremum::Seeded(31415)] {
+        test_hash(|k| AntiLexHasher::new_with_extremum(k, extremum), true);
+    }
+    let seq = &*PACKED_SEQ;
+    let seq_rc = seq.as_slice().to_revcomp();
+    for k in [1, 5, 16] {
+        let fw = AntiLexHasher::<false>::new(k);
+        let fw_hashes = fw.hash_kmers_scalar(seq.as_slice()).collect_vec();
+        let mut rc_hashes = fw.hash_kmers_scalar(seq_rc.as_slice()).collect_vec();
+        rc_hashes.reverse();
+        let pairs = zip(fw_hashes, rc_hashes).collect_vec();
+
+        let min = AntiLexHasher::new_with_extremum(k, Extremum::Min);
+        let max = AntiLexHasher::new_with_extremum(k, Extremum::Max);
+        let seeded = AntiLexHasher::new_with_extremum(k, Extremum::Seeded(1));
+        assert_eq!(min.extremum(), Extremum::Min);
+        assert_eq!(
+            min.hash_kmers_scalar(seq.as_slice()).collect_vec(),
+            pairs.iter().map(|&(a, b)| a.min(b)).collect_vec()
+        );
+        assert_eq!(
+            max.hash_kmers_simd(seq.as_slice(), 1).collect(),
+            pairs.iter().map(|&(a, b)| a.max(b)).collect_vec()
+        );
+        assert!(
+            zip(&pairs, seeded.hash_kmers_scalar(seq.as_slice()))
+                .all(|(&(a, b), h)| h == a || h == b)
+        );
+    }
+}
+
+#[test]
+#[ignore = "This is a benchmark, not a test"]
+fn anti_lex_density() {
+    eprintln!("\nDensity of random minimizers with canonical AntiLexHasher, w = 10");
+    let w = 10;
+    let seq = PackedSeqVec::random(10_000_000);
+    for k in [8, 12, 16] {
+        let num_kmers = (seq.len() - k + 1) as f64;
+        let density = |hasher: &AntiLexHasher<true>| {
+            winnow(hasher, seq.as_slice(), w).len() as f64 / num_kmers
+        };
+        eprintln!(
+            "k = {k:>2}: min {:.4}  max {:.4}  seeded {:.4}  (random {:.4})",
+            density(&AntiLexHasher::new_with_extremum(k, Extremum::Min)),
+            density(&AntiLexHasher::new_with_extremum(k, Extremum::Max)),
+            density(&AntiLexHasher::new_with_extremum(k, Extremum::Seeded(0))),
+            winnow(&NtHasher::<true>::new(k), seq.as_slice(), w).len() as f64 / num_kmers,
+        );
+    }
+}
+
 #[test]
 fn canonical_is_revcomp() {
     fn f<H: KmerHasher>(hasher: impl Fn(usize) -> H) {
@@ -137,7 +463,158 @@ fn canonical_is_revcomp() {
     }
     f(NtHasher::<true>::new);
     f(MulHasher::<true>::new);
+    f(TableHasher::<true>::new);
+    f(|k| SpacedHasher::<NtHasher<true>>::new(&random_mask(k, true)));
     f(AntiLexHasher::<true>::new);
+    f(|k| AntiLexHasher::new_with_extremum(k, Extremum::Max));
+    f(|k| AntiLexHasher::new_with_extremum(k, Extremum::Seeded(31415)));
+}
+
+#[test]
+fn delayed_out2() {
+    test_on_inputs(|k, _slice, ascii_seq, packed_seq| {
+        let hasher = NtHasher::<true>::new(k);
+        let hashes = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+        for w in [1, 5, 32] {
+            let delay2 = Delay(w + k - 1);
+            let expected_out2 = (0..hashes.len())
+                .map(|i| {
+                    let end = i + k - 1;
+                    (end >= delay2.0).then(|| packed_seq.get(end - delay2.0))
+                })
+                .collect_vec();
+            let aligned = PackedSeqVec::from_ascii(ascii_seq.0);
+            for (h, o) in [
+                hasher
+                    .hash_kmers_delayed_scalar(packed_seq, delay2)
+                    .unzip::<_, _, Vec<_>, Vec<_>>(),
+                hasher.hash_kmers_delayed_scalar(ascii_seq, delay2).unzip(),
+            ] {
+                assert_eq!(h, hashes);
+                assert_eq!(o, expected_out2);
+            }
+
+            // With `context = w`, lanes overlap by `w-1` k-mers that are skipped after windowing.
+            if hashes.len() <= w {
+                continue;
+            }
+            let simd_h = hasher
+                .hash_kmers_delayed_simd(packed_seq, w, delay2)
+                .advance(w - 1)
+                .map(|(h, _)| h)
+                .collect();
+            assert_eq!(simd_h, hashes[w - 1..], "k={k} w={w}");
+            // The first `w` k-mers of each lane have no second out character.
+            for seq in [packed_seq, aligned.as_slice()] {
+                let PaddedIt { it, padding } = hasher.hash_kmers_delayed_simd(seq, w + 1, delay2);
+                let len = it.len();
+                let mut lanes = vec![vec![]; 8];
+                for (i, (_, o)) in it.enumerate() {
+                    assert_eq!(o.is_some(), i >= w, "k={k} w={w}");
+                    if let Some(o) = o {
+                        for (lane, o) in lanes.iter_mut().zip(o.to_array()) {
+                            lane.push(o as u8);
+                        }
+                    }
+                }
+                let simd_o = lanes.concat();
+                assert_eq!(simd_o.len(), 8 * (len - w));
+                assert_eq!(
+                    simd_o[..simd_o.len() - padding],
+                    expected_out2[w..].iter().map(|o| o.unwrap()).collect_vec(),
+                    "k={k} w={w}"
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn alphabet_errors() {
+    let ascii = b"ACGTACGT".as_slice();
+    let packed = PackedSeqVec::from_ascii(ascii);
+
+    let hasher = NtHasher::<true>::new(3);
+    let err = HashError::UnsupportedAlphabet {
+        bits_per_char: 8,
+        max_bits_per_char: 2,
+    };
+    assert_eq!(hasher.try_mapper(ascii).err(), Some(err));
+    assert_eq!(hasher.try_in_out_mapper_scalar(ascii).err(), Some(err));
+    assert_eq!(hasher.try_in_out_mapper_simd(ascii).err(), Some(err));
+    assert!(hasher.try_mapper(packed.as_slice()).is_ok());
+    assert_eq!(
+        AntiLexHasher::<false>::new(3).check_alphabet(ascii),
+        Err(err)
+    );
+    assert!(MulHasher::<true>::new(3).try_mapper(ascii).is_ok());
+}
+
+#[test]
+fn hamming_neighbors_naive() {
+    fn f<H: SubstitutionHasher>(hasher: impl Fn(usize) -> H) {
+        for k in [1, 2, 5, 16, 31, 32, 33, 70] {
+            let hasher = hasher(k);
+            let kmer = PackedSeqVec::random(k);
+            let ascii = kmer.as_slice().unpack();
+            let neighbors = hamming_neighbors(&hasher, kmer.as_slice()).collect_vec();
+            assert_eq!(neighbors.len(), 3 * k);
+            for (pos, base, hash) in neighbors {
+                let mut variant = ascii.clone();
+                variant[pos] = packed_seq::unpack_base(base);
+                let expected = hasher.hash_seq(AsciiSeqVec::from_ascii(&variant).as_slice());
+                assert_eq!(hash, expected, "k={k} pos={pos}");
+            }
+        }
+    }
+    f(NtHasher::<false>::new);
+    f(NtHasher::<true>::new);
+    f(|k| MulHasher::<true>::new_with_seed(k, 31415));
+    f(TableHasher::<true>::new);
+    f(|k| DynNtHasher::<7>::new(k, true));
+}
+
+#[test]
+fn snv_hashes_naive() {
+    fn f<H: SubstitutionHasher>(hasher: impl Fn(usize) -> H) {
+        for k in [1, 2, 5, 31, 33] {
+            let hasher = hasher(k);
+            for len in [0, k - 1, k, 3 * k + 10] {
+                let seq = PackedSeqVec::random(len);
+                let ascii = seq.as_slice().unpack();
+                let num_kmers = (len + 1).saturating_sub(k);
+                let snvs = snv_hashes(&hasher, seq.as_slice()).collect_vec();
+                assert_eq!(snvs.len(), 3 * k * num_kmers, "k={k} len={len}");
+                for (pos, base, start, hash) in snvs {
+                    assert!(start <= pos && pos < start + k);
+                    assert_ne!(base, seq.as_slice().get(pos));
+                    let mut variant = ascii.clone();
+                    variant[pos] = packed_seq::unpack_base(base);
+                    let kmer = AsciiSeqVec::from_ascii(&variant[start..start + k]);
+                    assert_eq!(hash, hasher.hash_seq(kmer.as_slice()), "k={k} pos={pos}");
+                }
+            }
+        }
+    }
+    f(NtHasher::<false>::new);
+    f(NtHasher::<true>::new);
+    f(|k| MulHasher::<true>::new_with_seed(k, 31415));
+    f(|k| DynNtHasher::<7>::new(k, true));
+}
+
+#[test]
+fn auto_rotation() {
+    const R: u32 = best_rotation(21);
+    assert!((1..32).contains(&R));
+    let seq = &*PACKED_SEQ;
+    let hasher = NtHasher::<true, R>::new_auto(21);
+    let scalar = hasher.hash_kmers_scalar(seq.as_slice()).collect_vec();
+    let simd = hasher.hash_kmers_simd(seq.as_slice(), 1).collect();
+    assert_eq!(scalar, simd);
+    let hasher = NtHasher::<false, { best_rotation(5) }>::new_auto(5);
+    let scalar = hasher.hash_kmers_scalar(seq.as_slice()).collect_vec();
+    let simd = hasher.hash_kmers_simd(seq.as_slice(), 1).collect();
+    assert_eq!(scalar, simd);
 }
 
 #[test]
@@ -160,6 +637,91 @@ fn seeded() {
     });
 }
 
+#[test]
+fn seed_accessor() {
+    assert_eq!(<NtHasher>::new(21).seed(), None);
+    assert_eq!(<NtHasher>::new_with_seed(21, 31415).seed(), Some(31415));
+    assert_eq!(<MulHasher>::new_with_seed(21, 31415).seed(), Some(31415));
+    assert_eq!(<TableHasher>::new(21).seed(), None);
+    assert_eq!(<DynNtHasher>::new_with_seed(21, 7, false).seed(), Some(7));
+}
+
+#[test]
+#[cfg(feature = "getrandom")]
+fn random_seed() {
+    let seq = PackedSeqVec::random(1000);
+    let hasher = <NtHasher>::new_random(21);
+    let seed = hasher.seed().unwrap();
+    let reproduced = <NtHasher>::new_with_seed(21, seed);
+    assert_eq!(
+        hasher.hash_kmers_simd(seq.as_slice(), 1).collect(),
+        reproduced.hash_kmers_simd(seq.as_slice(), 1).collect()
+    );
+    assert!(<TableHasher>::new_random(21).seed().is_some());
+    assert!(<DynMulHasher>::new_random(21, true).seed().is_some());
+}
+
+#[test]
+fn simhash_near_duplicates() {
+    let hasher = <NtHasher>::new(21);
+    let seq = PackedSeqVec::random(10000);
+    let mut ascii = seq.as_slice().unpack();
+    for i in (0..ascii.len()).step_by(500) {
+        ascii[i] = if ascii[i] == b'A' { b'C' } else { b'A' };
+    }
+    let mutated = PackedSeqVec::from_ascii(&ascii);
+    let other = PackedSeqVec::random(10000);
+
+    let s = simhash(&hasher, seq.as_slice());
+    assert_eq!(s, simhash(&hasher, seq.as_slice()));
+    assert_eq!(s, simhash(&hasher, seq.as_slice().to_revcomp().as_slice()));
+    let near = simhash_distance(s, simhash(&hasher, mutated.as_slice()));
+    let far = simhash_distance(s, simhash(&hasher, other.as_slice()));
+    assert!(near < 12 && far > 16, "near={near} far={far}");
+}
+
+#[test]
+fn fingerprint_order_invariant() {
+    let k = 21;
+    let hasher = <NtHasher>::new(k);
+    let seq = PackedSeqVec::random(5000);
+    let seq = seq.as_slice();
+    let fp = fingerprint_seq(&hasher, seq);
+    assert_eq!(fp, fingerprint_seq(&hasher, seq.to_revcomp().as_slice()));
+    for i in [0, 1, 100, 4000] {
+        let a = fingerprint_seq(&hasher, seq.slice(0..i + k - 1));
+        let b = fingerprint_seq(&hasher, seq.slice(i..seq.len()));
+        assert_eq!(a.wrapping_add(b), fp);
+    }
+    assert_ne!(fp, fingerprint_seq(&hasher, seq.slice(1..seq.len())));
+}
+
+#[test]
+fn read_hash_strand_neutral() {
+    let hasher = ReadHasher::new_with_seed(5);
+    let mut seen = std::collections::HashSet::new();
+    for len in [0, 1, 2, 10, 31, 32, 33, 100, 1000] {
+        for _ in 0..10 {
+            let read = PackedSeqVec::random(len);
+            let ascii = AsciiSeqVec::from_ascii(&read.as_slice().unpack());
+            let h = hasher.hash64(read.as_slice());
+            assert_eq!(h, hasher.hash64(ascii.as_slice()));
+            assert_eq!(h, hasher.hash64(read.as_slice().to_revcomp().as_slice()));
+            assert_eq!(
+                hasher.hash32(read.as_slice()),
+                hasher.hash32(read.as_slice().to_revcomp().as_slice())
+            );
+            if len > 10 {
+                assert!(seen.insert(h));
+            }
+        }
+    }
+    // Swapping bases 32 positions apart changes the hash.
+    let a = AsciiSeqVec::from_ascii(&[b"A".as_slice(), &[b'T'; 31], b"C"].concat());
+    let b = AsciiSeqVec::from_ascii(&[b"C".as_slice(), &[b'T'; 31], b"A"].concat());
+    assert_ne!(hasher.hash64(a.as_slice()), hasher.hash64(b.as_slice()));
+}
+
 #[test]
 #[ignore = "This is a benchmark, not a test"]
 fn hash_kmers_bench() {
@@ -192,3 +754,668 @@ fn hash_kmers_bench() {
         }
     }
 }
+
+/// Compares the rolling hash against the two fast paths for small `k`:
+/// [`SmallKHasher`], which mixes the packed `2k`-bit code directly,
+/// and looking up the code in a `4^k` table of NtHash values, which would keep the hashes unchanged.
+/// Run with `cargo test -r small_k_bench -- --ignored --nocapture`.
+#[test]
+#[ignore = "This is a benchmark, not a test"]
+fn small_k_bench() {
+    eprintln!("\nBench small k: NtHasher vs SmallKHasher vs table");
+
+    fn bench(name: &str, len: usize, mut f: impl FnMut()) {
+        // 1Gbp input.
+        let rep = 1_000_000_000 / len;
+        let start = std::time::Instant::now();
+        for _ in 0..rep {
+            f();
+        }
+        eprintln!(
+            "{name:>7} len {len:>7} => {:.03} Gbp/s",
+            start.elapsed().as_secs_f64().recip()
+        );
+    }
+    fn consume(it: impl Iterator<Item = S>) {
+        it.for_each(
+            #[inline(always)]
+            |y| {
+                core::hint::black_box(&y);
+            },
+        );
+    }
+
+    for k in [1, 2, 4, 8, 16] {
+        eprintln!("\nk = {k}");
+        let nt = NtHasher::<false>::new(k);
+        let small = SmallKHasher::<false>::new(k);
+        let mask = S::splat(u32::MAX >> (32 - 2 * k));
+        // The table is too large to build for `k > 8`.
+        let table_k = k.min(8);
+        let table: Vec<u32> = (0..1u32 << (2 * table_k))
+            .map(|code| {
+                let kmer = (0..table_k)
+                    .map(|i| b"ACTG"[(code >> (2 * i) & 3) as usize])
+                    .collect_vec();
+                nt.hash_seq(PackedSeqVec::from_ascii(&kmer).as_slice())
+            })
+            .collect();
+        let table = table.as_slice();
+
+        for len in [150, 1_000_000] {
+            let seq = PackedSeqVec::random(len);
+            let seq = seq.as_slice();
+            bench("rolling", len, || consume(nt.hash_kmers_simd(seq, 1).it));
+            bench("small", len, || consume(small.hash_kmers_simd(seq, 1).it));
+            if k > 8 {
+                continue;
+            }
+            bench("table", len, || {
+                let mut code = S::ZERO;
+                let shift = 2 * (k as u32 - 1);
+                let it = seq.par_iter_bp(k).it.map(
+                    #[inline(always)]
+                    move |c: S| {
+                        code = (code >> 2) | (c << shift);
+                        // SAFETY: masked codes are below `table.len() = 4^k`.
+                        unsafe { crate::intrinsics::gather_slice(table, code & mask) }
+                    },
+                );
+                consume(it.skip(k - 1));
+            });
+        }
+    }
+}
+
+#[test]
+#[ignore = "This is a benchmark, not a test"]
+fn hash_kmers_chunked_bench() {
+    eprintln!("\nBench SeqHash::hash_kmers_chunked_into");
+
+    let hasher = NtHasher::<false>::new(31);
+    let len = 1_000_000;
+    let rep = 1_000_000_000 / len;
+    let seq = PackedSeqVec::random(len);
+    let mut out = vec![];
+    for chunk_len in [1 << 12, 1 << 14, 1 << 16, 1 << 18, len] {
+        let start = std::time::Instant::now();
+        for _ in 0..rep {
+            hasher.hash_kmers_chunked_into(seq.as_slice(), chunk_len, &mut out);
+            core::hint::black_box(&out);
+        }
+        eprintln!(
+            "Chunk {chunk_len:>7} => {:.03} Gbp/s",
+            start.elapsed().as_secs_f64().recip()
+        );
+    }
+}
+
+#[test]
+fn hash_kmers_chunked() {
+    let seq = PackedSeqVec::random(3000);
+    let mut out = vec![1, 2, 3];
+    for len in [0, 10, 100, 3000] {
+        let seq = seq.slice(0..len);
+        for k in [1, 5, 31] {
+            let hasher = <NtHasher>::new(k);
+            let expected = hasher.hash_kmers_scalar(seq).collect_vec();
+            for chunk_len in [1, 7, 64, 1000, DEFAULT_CHUNK_LEN] {
+                hasher.hash_kmers_chunked_into(seq, chunk_len, &mut out);
+                assert_eq!(out, expected, "len={len} k={k} chunk_len={chunk_len}");
+            }
+        }
+    }
+}
+
+#[test]
+fn for_each_kmer_hash() {
+    use std::ops::ControlFlow;
+    let seq = PackedSeqVec::random(5000);
+    for k in [1, 5, 31] {
+        let hasher = <NtHasher>::new(k);
+        let expected = hasher.hash_kmers_scalar(seq.as_slice()).collect_vec();
+        let mut visited = vec![];
+        let flow = hasher.for_each_kmer_hash(seq.as_slice(), |pos, h| {
+            visited.push((pos, h));
+            ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visited, expected.iter().copied().enumerate().collect_vec());
+
+        for stop in [0, 1023, 1024, 3000] {
+            let mut calls = 0;
+            let flow = hasher.for_each_kmer_hash(seq.as_slice(), |pos, h| {
+                calls += 1;
+                if pos == stop {
+                    ControlFlow::Break(h)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+            assert_eq!(flow, ControlFlow::Break(expected[stop]));
+            assert_eq!(calls, stop + 1);
+        }
+    }
+}
+
+#[test]
+fn hash_kmer_range() {
+    fn check<H: KmerHasher>(hasher: impl Fn(usize) -> H) {
+        test_on_inputs(|k, _slice, ascii_seq, packed_seq| {
+            let hasher = hasher(k);
+            let expected = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+            let n = hasher.num_kmers(packed_seq);
+            assert_eq!(n, expected.len());
+            let mut pages = vec![];
+            for start in (0..n).step_by(97) {
+                let range = start..(start + 97).min(n);
+                let scalar = hasher
+                    .hash_kmer_range_scalar(packed_seq, range.clone())
+                    .collect_vec();
+                assert_eq!(scalar, expected[range.clone()], "k={k} range={range:?}");
+                assert_eq!(
+                    hasher
+                        .hash_kmer_range_simd(ascii_seq, range.clone(), 1)
+                        .collect(),
+                    scalar
+                );
+                pages.extend(scalar);
+            }
+            assert_eq!(pages, expected);
+            assert_eq!(hasher.hash_kmer_range_scalar(packed_seq, n..n).len(), 0);
+        });
+    }
+    check(NtHasher::<true>::new);
+    check(MulHasher::<false>::new);
+    check(AntiLexHasher::<false>::new);
+}
+
+#[test]
+#[should_panic]
+fn hash_kmer_range_out_of_bounds() {
+    let seq = PackedSeqVec::random(100);
+    let _ = <NtHasher>::new(21).hash_kmer_range_scalar(seq.as_slice(), 0..81);
+}
+
+#[test]
+fn seq_ext() {
+    use packed_seq::PackedNSeqVec;
+    let mut ascii = (0..2000).map(|_| b"ACGT"[random_range(0..4)]).collect_vec();
+    for _ in 0..20 {
+        ascii[random_range(0..2000)] = b'N';
+    }
+    let nseq = PackedNSeqVec::from_ascii(&ascii);
+    for k in [1, 5, 31, 32] {
+        let hasher = <NtHasher>::new(k);
+        let nseq = nseq.as_slice();
+        assert_eq!(
+            nseq.kmer_hashes(&hasher),
+            hasher.hash_kmers_scalar(nseq.seq).collect_vec()
+        );
+        assert_eq!(
+            nseq.valid_kmer_hashes(&hasher),
+            hasher.hash_valid_kmers_scalar(nseq).collect_vec()
+        );
+        assert_eq!(
+            nseq.seq.valid_kmer_hashes(&hasher),
+            nseq.seq.kmer_hashes(&hasher)
+        );
+    }
+}
+
+#[test]
+fn ascii_kmers() {
+    let ascii = (0..1000)
+        .map(|_| b"ACGTN"[random_range(0..5)])
+        .collect_vec();
+    for canonical in [false, true] {
+        for k in [1, 4, 21] {
+            let hasher = <DynNtHasher>::new(k, canonical);
+            let expected = ascii
+                .windows(k)
+                .map(|w| {
+                    if w.contains(&b'N') {
+                        u32::MAX
+                    } else {
+                        hasher.hash_seq(PackedSeqVec::from_ascii(w).as_slice())
+                    }
+                })
+                .collect_vec();
+            assert_eq!(hash_ascii_kmers(&ascii, k, canonical), expected);
+        }
+    }
+}
+
+/// A third-party container of 2-bit codes.
+#[derive(Clone, Copy)]
+struct Codes<'a>(&'a [u8]);
+
+impl<'s> HashableSeq<'s> for Codes<'s> {
+    fn char_len(&self) -> usize {
+        self.0.len()
+    }
+    fn char_bits(&self) -> usize {
+        2
+    }
+    fn iter_chars(self) -> impl Iterator<Item = u8> {
+        self.0.iter().copied()
+    }
+}
+
+#[test]
+fn hashable_seq() {
+    test_on_inputs(|k, slice, _ascii_seq, packed_seq| {
+        let codes = packed_seq.iter_bp().collect_vec();
+        let hasher = <NtHasher>::new(k);
+        assert_eq!(
+            hasher.hash_kmers_hashable(Codes(&codes)).collect_vec(),
+            hasher.hash_kmers_scalar(packed_seq).collect_vec()
+        );
+        let hasher = <TableHasher>::new(k);
+        assert_eq!(
+            hasher.hash_kmers_hashable(Codes(&codes)).collect_vec(),
+            hasher.hash_kmers_scalar(packed_seq).collect_vec()
+        );
+        let hasher = <MulHasher>::new(k);
+        assert_eq!(
+            hasher.hash_kmers_hashable(slice).collect_vec(),
+            hasher.hash_kmers_scalar(slice).collect_vec()
+        );
+    });
+}
+
+#[test]
+fn nthash_family() {
+    let seq = PackedSeqVec::random(1000);
+    let family = NtHasher::<false>::family(21, 64, 31415);
+    assert_eq!(family.len(), 64);
+    let hashes = family
+        .iter()
+        .map(|h| h.hash_kmers_scalar(seq.as_slice()).collect_vec())
+        .collect_vec();
+    assert!(hashes.iter().all_unique());
+    // Deterministic in the master seed.
+    let again = NtHasher::<false>::family(21, 64, 31415);
+    assert_eq!(
+        again[17].hash_seq(seq.as_slice()),
+        family[17].hash_seq(seq.as_slice())
+    );
+    let other = NtHasher::<false>::family(21, 1, 27182);
+    assert_ne!(
+        other[0].hash_seq(seq.as_slice()),
+        family[0].hash_seq(seq.as_slice())
+    );
+}
+
+#[test]
+fn into_ptr() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let hasher = <NtHasher>::new(k);
+        let expected = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+        let mut out = vec![0; expected.len() + 3];
+        let n = unsafe { hash_kmers_into_ptr(&hasher, packed_seq, out.as_mut_ptr(), out.len()) };
+        assert_eq!(out[..n], expected);
+        assert!(out[n..].iter().all(|&x| x == 0));
+        if n > 0 {
+            let mut small = vec![0; n - 1];
+            let m = unsafe {
+                hash_kmers_into_ptr(&hasher, packed_seq, small.as_mut_ptr(), small.len())
+            };
+            assert_eq!(m, n);
+            assert!(small.iter().all(|&x| x == 0));
+        }
+    });
+}
+
+#[test]
+fn valid_hash_avoids_sentinel() {
+    use packed_seq::PackedNSeqVec;
+    // Anti-lex hashes this kmer to all ones.
+    let kmer = b"GGGGGGGGGGGGGGGA";
+    let hasher = AntiLexHasher::<false>::new(16);
+    let nseq = PackedNSeqVec::from_ascii(kmer);
+    let nseq = nseq.as_slice();
+    assert_eq!(hasher.hash_kmers_scalar(nseq.seq).collect_vec(), [u32::MAX]);
+    assert_eq!(
+        hasher.hash_valid_kmers_scalar(nseq).collect_vec(),
+        [u32::MAX - 1]
+    );
+    assert_eq!(
+        hasher.hash_valid_kmers_simd(nseq, 1).collect(),
+        [u32::MAX - 1]
+    );
+}
+
+#[test]
+fn nonzero_hashes() {
+    use std::num::NonZeroU32;
+    assert_eq!(size_of::<Option<NonZeroU32>>(), 4);
+    // `hash_kmers_scalar` of `A` is 0 for `ColexHasher`.
+    let seq = PackedSeqVec::from_ascii(b"AACAAGTA");
+    let hasher = ColexHasher::new(2);
+    let hashes = hasher.hash_kmers_scalar(seq.as_slice()).collect_vec();
+    assert!(hashes.contains(&0));
+    let expected = hashes.iter().map(|&h| h.max(1)).collect_vec();
+    assert_eq!(
+        hasher
+            .hash_kmers_nonzero_scalar(seq.as_slice())
+            .map(NonZeroU32::get)
+            .collect_vec(),
+        expected
+    );
+    assert_eq!(
+        hasher.hash_kmers_nonzero_simd(seq.as_slice(), 1).collect(),
+        expected
+    );
+}
+
+#[test]
+fn shorter_than_k() {
+    for k in [1, 2, 5, 21, 31, 64, 96] {
+        let hasher = <NtHasher>::new(k);
+        let ascii = AsciiSeqVec::random(k);
+        let packed = PackedSeqVec::from_ascii(&ascii.seq);
+        let nseq = packed_seq::PackedNSeqVec::from_ascii(&ascii.seq);
+        for len in 0..k {
+            let expected = 0;
+            let seq = packed.slice(0..len);
+            let nseq = nseq.slice(0..len);
+            assert_eq!(
+                hasher.hash_kmers_scalar(seq).len(),
+                expected,
+                "k={k} len={len}"
+            );
+            assert_eq!(
+                hasher.hash_kmers_scalar(ascii.slice(0..len)).count(),
+                expected
+            );
+            assert_eq!(hasher.hash_kmers_simd(seq, 1).collect().len(), expected);
+            assert_eq!(hasher.hash_kmers_simd(seq, 10).collect().len(), expected);
+            assert_eq!(hasher.hash_kmers_nonzero_scalar(seq).len(), expected);
+            assert_eq!(hasher.hash_kmers_iter(seq.iter_bp()).count(), expected);
+            assert_eq!(
+                hasher.hash_kmers_delayed_scalar(seq, Delay(k)).len(),
+                expected
+            );
+            let PaddedIt { it, padding } = hasher.hash_kmers_delayed_simd(seq, 1, Delay(k));
+            assert_eq!(8 * it.len() - padding, expected);
+            assert_eq!(
+                hasher.hash_valid_kmers_scalar(nseq).len(),
+                expected,
+                "k={k} len={len}"
+            );
+            assert_eq!(
+                hasher.hash_valid_kmers_simd(nseq, 1).collect().len(),
+                expected,
+                "k={k} len={len}"
+            );
+        }
+    }
+}
+
+#[test]
+fn revcomp_track() {
+    test_on_inputs(|k, _slice, ascii_seq, packed_seq| {
+        let hasher = RevCompHasher::<NtHasher<false>>::new_with_seed(k, 31415);
+        let fwd = NtHasher::<false>::new_with_seed(k, 31415);
+        let naive = ascii_seq
+            .0
+            .windows(k)
+            .map(|kmer| {
+                let rc = PackedSeqVec::from_ascii(kmer).as_slice().to_revcomp();
+                fwd.hash_seq(rc.as_slice())
+            })
+            .collect_vec();
+        let len = packed_seq.len();
+        assert_eq!(
+            hasher.hash_kmers_scalar(packed_seq).collect_vec(),
+            naive,
+            "k={k} len={len}"
+        );
+        assert_eq!(
+            hasher.hash_kmers_simd(packed_seq, 1).collect(),
+            naive,
+            "k={k} len={len}"
+        );
+        // `hash_seq` ignores `k`.
+        for l in [1, k, len / 2, len]
+            .into_iter()
+            .filter(|&l| 0 < l && l <= len)
+        {
+            let seq = packed_seq.slice(0..l);
+            assert_eq!(
+                hasher.hash_seq(seq),
+                fwd.hash_seq(seq.to_revcomp().as_slice()),
+                "k={k} l={l}"
+            );
+        }
+    });
+}
+
+#[test]
+fn mapper_with_base() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let hasher = NtHasher::<true>::new(k);
+        let delay = hasher.delay().0;
+        let out = std::iter::repeat_n(0, delay).chain(packed_seq.iter_bp());
+        let mut mapper = hasher.in_out_mapper_scalar(packed_seq);
+        let mut with_base = hasher.in_out_mapper_with_base_scalar(packed_seq);
+        for (a, r) in zip(packed_seq.iter_bp(), out) {
+            assert_eq!(with_base((a, r)), (a, mapper((a, r))));
+        }
+
+        let mut mapper = hasher.mapper(packed_seq);
+        let mut with_base = hasher.mapper_with_base(packed_seq);
+        for a in packed_seq.iter_bp() {
+            assert_eq!(with_base(a), (a, mapper(a)));
+        }
+
+        let mut mapper = hasher.in_out_mapper_simd(packed_seq);
+        let mut with_base = hasher.in_out_mapper_with_base_simd(packed_seq);
+        for ar in packed_seq.par_iter_bp_delayed(k, hasher.delay()).it {
+            assert_eq!(with_base(ar), (ar.0, mapper(ar)));
+        }
+    });
+}
+
+#[test]
+fn rolling_states() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let fwd = NtHasher::<false>::new_with_seed(k, 31415);
+        let canonical = NtHasher::<true>::new_with_seed(k, 31415);
+        let revcomp = RevCompHasher::<NtHasher<false>>::new_with_seed(k, 31415);
+        let len = packed_seq.len();
+
+        let states = rolling_states_scalar(&fwd, packed_seq).collect_vec();
+        let fw = states.iter().map(|s| s.0).collect_vec();
+        let rc = states.iter().map(|s| s.1).collect_vec();
+        let sum = states.iter().map(|s| s.0.wrapping_add(s.1)).collect_vec();
+        assert_eq!(
+            fw,
+            fwd.hash_kmers_scalar(packed_seq).collect_vec(),
+            "k={k} len={len}"
+        );
+        assert_eq!(
+            rc,
+            revcomp.hash_kmers_scalar(packed_seq).collect_vec(),
+            "k={k} len={len}"
+        );
+        assert_eq!(
+            sum,
+            canonical.hash_kmers_scalar(packed_seq).collect_vec(),
+            "k={k} len={len}"
+        );
+        // The `CANONICAL` parameter is ignored.
+        assert_eq!(
+            rolling_states_scalar(&canonical, packed_seq).collect_vec(),
+            states
+        );
+
+        let simd_fw = rolling_states_simd(&fwd, packed_seq)
+            .map(|(fw, _)| fw)
+            .collect();
+        let simd_rc = rolling_states_simd(&fwd, packed_seq)
+            .map(|(_, rc)| rc)
+            .collect();
+        assert_eq!(simd_fw, fw, "k={k} len={len}");
+        assert_eq!(simd_rc, rc, "k={k} len={len}");
+    });
+}
+
+#[test]
+fn combined_canonical() {
+    test_on_inputs(|k, _slice, ascii_seq, packed_seq| {
+        let fwd = MulHasher::<false>::new_with_seed(k, 31415);
+        let canonical = MulHasher::<true>::new_with_seed(k, 31415);
+        let len = packed_seq.len();
+
+        let sum = hash_kmers_combined_scalar(&fwd, packed_seq, u32::wrapping_add).collect_vec();
+        assert_eq!(
+            sum,
+            canonical.hash_kmers_scalar(packed_seq).collect_vec(),
+            "k={k} len={len}"
+        );
+
+        let min = hash_kmers_combined_scalar(&fwd, packed_seq, u32::min).collect_vec();
+        let simd_min = hash_kmers_combined_simd(&fwd, packed_seq, S::min).collect();
+        assert_eq!(simd_min, min, "k={k} len={len}");
+
+        // Mappers with delay `k-1`.
+        let mut mapper = in_out_mapper_combined_scalar(&fwd, u32::min);
+        let out = std::iter::repeat_n(0, k - 1).chain(packed_seq.iter_bp());
+        let mapped = zip(packed_seq.iter_bp(), out)
+            .map(&mut mapper)
+            .skip(k - 1)
+            .collect_vec();
+        assert_eq!(mapped, min, "k={k} len={len}");
+        let simd_mapped = packed_seq
+            .par_iter_bp_delayed(k, Delay(k - 1))
+            .map(in_out_mapper_combined_simd(&fwd, S::min))
+            .advance(k - 1)
+            .collect();
+        assert_eq!(simd_mapped, min, "k={k} len={len}");
+
+        // A symmetric combiner is invariant under reverse complement.
+        let rc = PackedSeqVec::from_ascii(ascii_seq.0)
+            .as_slice()
+            .to_revcomp();
+        let mut rc_min = hash_kmers_combined_scalar(&fwd, rc.as_slice(), u32::min).collect_vec();
+        rc_min.reverse();
+        assert_eq!(rc_min, min, "k={k} len={len}");
+    });
+}
+
+#[test]
+fn hash_all() {
+    let seqs: Vec<PackedSeqVec> = [0, 5, 21, 100, 1000, 3]
+        .map(PackedSeqVec::random)
+        .into_iter()
+        .collect();
+    for k in [1, 21, 40] {
+        let hasher = <NtHasher>::new(k);
+        let expected: Vec<Vec<u32>> = seqs
+            .iter()
+            .map(|seq| hasher.hash_kmers_scalar(seq.as_slice()).collect())
+            .collect();
+        assert_eq!(hasher.hash_all(&seqs), expected);
+
+        struct Collect(Vec<u32>);
+        impl HashSink for Collect {
+            fn accept_block(&mut self, hashes: S, mask: S) {
+                append_filtered(hashes, mask, &mut self.0);
+            }
+        }
+        let mut sink = Collect(vec![]);
+        hasher.hash_all_into_sink(&seqs, &mut sink);
+        sink.0.sort_unstable();
+        let mut expected = expected.concat();
+        expected.sort_unstable();
+        assert_eq!(sink.0, expected);
+    }
+}
+
+#[test]
+fn hash_with_gc() {
+    test_on_inputs(|k, _slice, ascii_seq, packed_seq| {
+        let hasher = NtHasher::<true>::new(k);
+        let hashes = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+        let gc = ascii_seq
+            .0
+            .windows(k)
+            .map(|w| w.iter().filter(|&&c| c == b'C' || c == b'G').count() as u32)
+            .collect_vec();
+        let expected = zip(hashes.iter().copied(), gc.iter().copied()).collect_vec();
+        assert_eq!(
+            hasher.hash_kmers_with_gc_scalar(packed_seq).collect_vec(),
+            expected
+        );
+        assert_eq!(
+            hasher.hash_kmers_with_gc_scalar(ascii_seq).collect_vec(),
+            expected
+        );
+
+        for context in [1, 5] {
+            let simd_h = hasher
+                .hash_kmers_with_gc_simd(packed_seq, context)
+                .advance(context - 1)
+                .map(|(h, _)| h)
+                .collect();
+            assert_eq!(
+                simd_h,
+                hashes[(context - 1).min(hashes.len())..],
+                "k={k} context={context}"
+            );
+            let simd_gc = hasher
+                .hash_kmers_with_gc_simd(packed_seq, context)
+                .advance(context - 1)
+                .map(|(_, g)| g)
+                .collect();
+            assert_eq!(
+                simd_gc,
+                gc[(context - 1).min(gc.len())..],
+                "k={k} context={context}"
+            );
+        }
+    });
+}
+
+#[test]
+fn padded_layout() {
+    test_on_inputs(|k, _slice, _ascii_seq, packed_seq| {
+        let hasher = NtHasher::<true>::new(k);
+        let hashes = hasher.hash_kmers_scalar(packed_seq).collect_vec();
+        // Sequences with fewer than `context` k-mers have no complete windows.
+        for context in [1, 2, 7].into_iter().filter(|&c| c <= hashes.len()) {
+            let padded = hasher.hash_kmers_simd(packed_seq, context);
+            let n = padded.lane_len();
+            let lane_lens = padded.lane_lens(context);
+            assert_eq!(padded.num_positions(context), hashes.len());
+            let lane_positions = padded.lane_positions(context).collect_vec();
+            assert_eq!(lane_positions.len(), n);
+            for (i, (pos, mask)) in lane_positions.iter().enumerate() {
+                for j in 0..8 {
+                    let expected = padded.position(context, j, i);
+                    assert_eq!(mask.as_array()[j] != 0, expected.is_some());
+                    if let Some(p) = expected {
+                        assert_eq!(pos.as_array()[j] as usize, p);
+                    }
+                }
+            }
+            let positions: Vec<Vec<Option<usize>>> = (0..8)
+                .map(|j| (0..n).map(|i| padded.position(context, j, i)).collect())
+                .collect();
+            let mut seen = vec![false; hashes.len()];
+            for (i, h) in padded.it.enumerate() {
+                for j in 0..8 {
+                    if let Some(pos) = positions[j][i] {
+                        assert_eq!(h.as_array()[j], hashes[pos], "k={k} context={context}");
+                        seen[pos] = true;
+                    } else {
+                        assert!(i >= lane_lens[j]);
+                    }
+                }
+            }
+            assert!(seen.into_iter().all(|s| s), "k={k} context={context}");
+        }
+    });
+}
@@ -0,0 +1,196 @@
+//! Cross-validating SIMD hashes against the scalar reference in debug builds.
+
+use crate::{HashError, KmerHasher, PaddedLayout, S};
+use packed_seq::{ChunkIt, Delay, PackedNSeq, PaddedIt, Seq};
+use std::iter::zip;
+
+/// A [`KmerHasher`] wrapper that, in debug builds, checks each SIMD block of hashes against the scalar path.
+///
+/// [`KmerHasher::hash_kmers_simd`] and [`KmerHasher::hash_valid_kmers_simd`] first compute the scalar hashes,
+/// and then assert that each block of 8 lanes emitted by the SIMD pass matches them,
+/// reporting the first differing k-mer position. This helps to localize miscompiles or undefined behaviour
+/// when SIMD and scalar results diverge. In release builds, all methods simply delegate.
+pub struct ValidatingHasher<H: KmerHasher> {
+    hasher: H,
+}
+
+impl<H: KmerHasher> ValidatingHasher<H> {
+    /// Wrap `hasher`.
+    pub fn wrap(hasher: H) -> Self {
+        Self { hasher }
+    }
+
+    /// The wrapped hasher.
+    pub fn inner(&self) -> &H {
+        &self.hasher
+    }
+}
+
+/// Check the SIMD blocks of `it` against `expected`, when given.
+fn validate(
+    it: PaddedIt<impl ChunkIt<S>>,
+    expected: Option<Vec<u32>>,
+    context: usize,
+) -> PaddedIt<impl ChunkIt<S>> {
+    let mut expected = expected.map(|e| (e, it.lane_positions(context).enumerate()));
+    it.map(move |h: S| {
+        if let Some((expected, positions)) = &mut expected {
+            let (t, (pos, mask)) = positions.next().unwrap();
+            let mask = mask.to_bitmask();
+            for (j, (h, pos)) in zip(h.to_array(), pos.to_array()).enumerate() {
+                if mask >> j & 1 != 0
+                    && let Some(&e) = expected.get(pos as usize)
+                {
+                    assert_eq!(
+                        h, e,
+                        "SIMD hash differs from scalar hash for k-mer {pos} (lane {j}, block {t})."
+                    );
+                }
+            }
+        }
+        h
+    })
+}
+
+impl<H: KmerHasher> KmerHasher for ValidatingHasher<H> {
+    const CANONICAL: bool = H::CANONICAL;
+
+    fn new(k: usize) -> Self {
+        Self::wrap(H::new(k))
+    }
+
+    #[inline(always)]
+    fn is_canonical(&self) -> bool {
+        self.hasher.is_canonical()
+    }
+
+    #[inline(always)]
+    fn k(&self) -> usize {
+        self.hasher.k()
+    }
+
+    #[inline(always)]
+    fn max_bits_per_char(&self) -> usize {
+        self.hasher.max_bits_per_char()
+    }
+
+    #[inline(always)]
+    fn check_alphabet<'s>(&self, seq: impl Seq<'s>) -> Result<(), HashError> {
+        self.hasher.check_alphabet(seq)
+    }
+
+    #[inline(always)]
+    fn delay(&self) -> Delay {
+        self.hasher.delay()
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+        self.hasher.in_out_mapper_scalar(seq)
+    }
+
+    #[inline(always)]
+    fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+        self.hasher.in_out_mapper_simd(seq)
+    }
+
+    #[inline(always)]
+    fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+        self.hasher.mapper(seq)
+    }
+
+    #[inline(always)]
+    fn hash_kmers_scalar<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        self.hasher.hash_kmers_scalar(seq)
+    }
+
+    #[inline(always)]
+    fn hash_kmers_simd<'s>(&self, seq: impl Seq<'s>, context: usize) -> PaddedIt<impl ChunkIt<S>> {
+        let expected = cfg!(debug_assertions).then(|| self.hasher.hash_kmers_scalar(seq).collect());
+        validate(self.hasher.hash_kmers_simd(seq, context), expected, context)
+    }
+
+    #[inline(always)]
+    fn hash_valid_kmers_scalar<'s>(
+        &self,
+        nseq: PackedNSeq<'s>,
+    ) -> impl ExactSizeIterator<Item = u32> {
+        self.hasher.hash_valid_kmers_scalar(nseq)
+    }
+
+    #[inline(always)]
+    fn hash_valid_kmers_simd<'s, 't>(
+        &'t self,
+        nseq: PackedNSeq<'s>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<S> + use<'s, 't, H>> {
+        let expected =
+            cfg!(debug_assertions).then(|| self.hasher.hash_valid_kmers_scalar(nseq).collect());
+        validate(
+            self.hasher.hash_valid_kmers_simd(nseq, context),
+            expected,
+            context,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedNSeqVec, PackedSeqVec, SeqVec};
+
+    #[test]
+    fn passes_through() {
+        let hasher = ValidatingHasher::<NtHasher>::new(21);
+        let seq = PackedSeqVec::random(1000);
+        for context in [1, 10] {
+            let PaddedIt { it, .. } = hasher.hash_kmers_simd(seq.as_slice(), context);
+            it.for_each(drop);
+        }
+        assert_eq!(
+            hasher.hash_kmers_simd(seq.as_slice(), 1).collect(),
+            hasher
+                .inner()
+                .hash_kmers_scalar(seq.as_slice())
+                .collect::<Vec<_>>()
+        );
+        let nseq = PackedNSeqVec::random(1000, 0.01);
+        let valid = hasher.hash_valid_kmers_simd(nseq.as_slice(), 1).collect();
+        assert_eq!(valid.len(), 980);
+    }
+
+    /// Forwards to [`NtHasher`], but corrupts the SIMD hashes in one lane.
+    #[cfg(debug_assertions)]
+    struct Broken(NtHasher);
+
+    #[cfg(debug_assertions)]
+    impl KmerHasher for Broken {
+        const CANONICAL: bool = true;
+        fn new(k: usize) -> Self {
+            Broken(NtHasher::new(k))
+        }
+        fn k(&self) -> usize {
+            self.0.k()
+        }
+        fn in_out_mapper_scalar<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((u8, u8)) -> u32 {
+            self.0.in_out_mapper_scalar(seq)
+        }
+        fn in_out_mapper_simd<'s>(&self, seq: impl Seq<'s>) -> impl FnMut((S, S)) -> S {
+            let mut mapper = self.0.in_out_mapper_simd(seq);
+            move |ar| mapper(ar) ^ S::new([0, 0, 0, 1, 0, 0, 0, 0])
+        }
+        fn mapper<'s>(&self, seq: impl Seq<'s>) -> impl FnMut(u8) -> u32 + Clone {
+            self.0.mapper(seq)
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic = "lane 3"]
+    fn detects_divergence() {
+        let hasher = ValidatingHasher::<Broken>::new(21);
+        let seq = PackedSeqVec::random(1000);
+        hasher.hash_kmers_simd(seq.as_slice(), 1).collect();
+    }
+}
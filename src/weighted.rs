@@ -0,0 +1,104 @@
+//! Biasing k-mer hashes by per-base penalties, e.g. derived from quality scores.
+//!
+//! The penalty of a k-mer is the (rolling) sum of the penalties of its bases.
+//! It is clamped to `bits` bits and stored in the top bits of the output,
+//! with the top `32-bits` bits of the hash below it.
+//! Thus, min-based samplers (e.g. minimizers) prefer k-mers with lower penalty,
+//! and choose uniformly at random between k-mers with equal penalty.
+
+use crate::KmerHasher;
+use packed_seq::Seq;
+use std::iter::zip;
+
+/// Convert ASCII (Phred+33) quality scores to penalties `max(0, 40 - q)`.
+pub fn quality_penalties(quality: &[u8]) -> Vec<u8> {
+    quality
+        .iter()
+        .map(|&q| 40u8.saturating_sub(q.saturating_sub(b'!')))
+        .collect()
+}
+
+/// Combine a k-mer `hash` with its total `penalty`, as described in the module docs.
+#[inline(always)]
+pub fn weight_hash(hash: u32, penalty: u32, bits: u32) -> u32 {
+    debug_assert!(0 < bits && bits < 32);
+    let max = (1 << bits) - 1;
+    (penalty.min(max) << (32 - bits)) | (hash >> bits)
+}
+
+/// Rolling sums of the penalties of each window of `k` bases.
+fn kmer_penalties(penalties: &[u8], k: usize) -> impl ExactSizeIterator<Item = u32> {
+    let (init, rest) = penalties.split_at((k - 1).min(penalties.len()));
+    let mut sum: u32 = init.iter().map(|&p| p as u32).sum();
+    zip(rest, penalties).map(move |(&add, &remove)| {
+        sum += add as u32;
+        let out = sum;
+        sum -= remove as u32;
+        out
+    })
+}
+
+/// A scalar iterator over the penalty-weighted hashes of all k-mers in `seq`.
+/// `penalties` must have the same length as `seq`.
+pub fn hash_kmers_weighted_scalar<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    penalties: &[u8],
+    bits: u32,
+) -> impl ExactSizeIterator<Item = u32> {
+    assert_eq!(seq.len(), penalties.len());
+    zip(
+        hasher.hash_kmers_scalar(seq),
+        kmer_penalties(penalties, hasher.k()),
+    )
+    .map(move |(h, p)| weight_hash(h, p, bits))
+}
+
+/// The penalty-weighted hashes of all k-mers in `seq`, using the SIMD hash stream.
+/// `penalties` must have the same length as `seq`.
+pub fn hash_kmers_weighted<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    penalties: &[u8],
+    bits: u32,
+) -> Vec<u32> {
+    assert_eq!(seq.len(), penalties.len());
+    let mut hashes = hasher.hash_kmers_simd(seq, 1).collect();
+    for (h, p) in zip(&mut hashes, kmer_penalties(penalties, hasher.k())) {
+        *h = weight_hash(*h, p, bits);
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn weighted() {
+        let k = 11;
+        let bits = 4;
+        let hasher = NtHasher::<true>::new(k);
+        let seq = PackedSeqVec::random(1000);
+        let quality: Vec<u8> = (0..1000).map(|_| rand::random_range(b'!'..b'J')).collect();
+        let penalties = quality_penalties(&quality);
+
+        let simd = hash_kmers_weighted(&hasher, seq.as_slice(), &penalties, bits);
+        let scalar: Vec<u32> =
+            hash_kmers_weighted_scalar(&hasher, seq.as_slice(), &penalties, bits).collect();
+        assert_eq!(simd, scalar);
+
+        let hashes: Vec<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+        for (i, (&w, &h)) in zip(&simd, &hashes).enumerate() {
+            let p: u32 = penalties[i..i + k].iter().map(|&p| p as u32).sum();
+            assert_eq!(w >> (32 - bits), p.min(15));
+            assert_eq!(w << bits, h >> bits << bits);
+        }
+
+        let zero = vec![0; 1000];
+        let unweighted = hash_kmers_weighted(&hasher, seq.as_slice(), &zero, bits);
+        assert!(zip(&unweighted, &hashes).all(|(&w, &h)| w == h >> bits));
+    }
+}
@@ -0,0 +1,328 @@
+//! Window-based selection schemes on top of the k-mer hash stream.
+
+use crate::{KmerHasher, PaddedLayout, S};
+use packed_seq::{ChunkIt, PaddedIt, Seq};
+use std::collections::{HashMap, VecDeque};
+use std::iter::zip;
+
+/// A SIMD mapper returning the minimum of the last `w` values in each lane.
+///
+/// Uses the two-stacks approach: the current block of `w` values is stored as-is,
+/// and the suffix minima of the previous block are recomputed every `w` steps,
+/// for amortized `O(1)` work per value.
+/// The first `w-1` outputs only cover a partial window.
+pub fn sliding_min_mapper(w: usize) -> impl FnMut(S) -> S {
+    assert!(w > 0, "Window size must be positive.");
+    let mut cur = vec![S::MAX; w];
+    let mut suffix = vec![S::MAX; w + 1];
+    let mut prefix = S::MAX;
+    let mut idx = 0;
+    move |x| {
+        if idx == w {
+            idx = 0;
+            for i in (0..w).rev() {
+                suffix[i] = suffix[i + 1].min(cur[i]);
+            }
+            prefix = S::MAX;
+        }
+        cur[idx] = x;
+        prefix = prefix.min(x);
+        idx += 1;
+        prefix.min(suffix[idx])
+    }
+}
+
+/// The minimum k-mer hash in each window of `w` consecutive k-mers of `seq`, in SIMD.
+///
+/// Yields one value per window; use e.g. [`PaddedIt::collect`] to get a flat vector.
+/// Only values are returned; for positions, use `simd_minimizers`.
+/// See [`sliding_min_scalar`] for a lazy scalar version.
+pub fn sliding_min<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    w: usize,
+) -> PaddedIt<impl ChunkIt<S>> {
+    hasher
+        .hash_kmers_simd(seq, w)
+        .map(sliding_min_mapper(w))
+        .advance(w - 1)
+}
+
+/// Scalar version of [`sliding_min`], lazily yielding the minimum k-mer hash in each window of `w` consecutive k-mers.
+///
+/// Keeps a monotone queue of the hashes that can still become a window minimum, for amortized `O(1)` work per k-mer.
+/// Yields nothing when `seq` has fewer than `w` k-mers.
+///
+/// There is no lane-parallel monotone queue: its length and pops depend on the data in each lane,
+/// so the 8 lanes would diverge. [`sliding_min`] is the SIMD version instead, with the same amortized `O(1)` work
+/// per k-mer, using the branch-free two-stacks approach of [`sliding_min_mapper`] in all lanes at once.
+pub fn sliding_min_scalar<'s, H: KmerHasher>(
+    hasher: &H,
+    seq: impl Seq<'s>,
+    w: usize,
+) -> impl ExactSizeIterator<Item = u32> {
+    assert!(w > 0, "Window size must be positive.");
+    // Monotone queue of `(pos, hash)` with strictly increasing hashes.
+    let mut q = VecDeque::<(usize, u32)>::with_capacity(w);
+    hasher
+        .hash_kmers_scalar(seq)
+        .enumerate()
+        .map(move |(i, h)| {
+            while q.back().is_some_and(|&(_, h2)| h2 >= h) {
+                q.pop_back();
+            }
+            q.push_back((i, h));
+            if q[0].0 + w <= i {
+                q.pop_front();
+            }
+            q[0].1
+        })
+        .skip(w - 1)
+}
+
+/// Winnowing (Schleimer, Wilkerson, Aiken 2003) of `seq` with windows of `w` k-mers.
+///
+/// In each window of `w` consecutive k-mers, the k-mer with the smallest hash is selected,
+/// breaking ties towards the rightmost one.
+/// Returns the distinct selected `(pos, hash)` fingerprints, ordered by position.
+/// Any substring of length at least `w+k-1` shared by two inputs yields a shared fingerprint.
+///
+/// Works for any alphabet the hasher supports, e.g. `&[u8]` with [`crate::MulHasher`] or [`crate::TableHasher`].
+pub fn winnow<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>, w: usize) -> Vec<(usize, u32)> {
+    assert!(w > 0, "Window size must be positive.");
+    let hashes = hasher.hash_kmers_simd(seq, 1).collect();
+
+    let mut fingerprints = vec![];
+    // Monotone queue of `(pos, hash)` with strictly increasing hashes.
+    let mut q = VecDeque::<(usize, u32)>::new();
+    for (i, &h) in hashes.iter().enumerate() {
+        while q.back().is_some_and(|&(_, h2)| h2 >= h) {
+            q.pop_back();
+        }
+        q.push_back((i, h));
+        if q[0].0 + w <= i {
+            q.pop_front();
+        }
+        if i + 1 >= w && fingerprints.last() != Some(&q[0]) {
+            fingerprints.push(q[0]);
+        }
+    }
+    fingerprints
+}
+
+/// For each window of `w` consecutive k-mers of `seq`, the rank (in `0..w`) of its last k-mer by hash within the window.
+///
+/// The rank is the number of other k-mers in the window with a smaller hash, where ties are broken towards the leftmost k-mer,
+/// so rank `0` is the leftmost minimum.
+/// Yields one value per window, and nothing when `seq` has fewer than `w` k-mers.
+/// Each window is scanned, for `O(w)` work per k-mer, which vectorizes well for typical `w`.
+pub fn window_ranks<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>, w: usize) -> Vec<u32> {
+    assert!(w > 0, "Window size must be positive.");
+    let hashes = hasher.hash_kmers_simd(seq, 1).collect();
+    (w - 1..hashes.len())
+        .map(|i| {
+            let h = hashes[i];
+            hashes[i + 1 - w..i]
+                .iter()
+                .map(|&h2| (h2 <= h) as u32)
+                .sum::<u32>()
+        })
+        .collect()
+}
+
+/// The number of distinct k-mer hashes in each window of `w` consecutive k-mers of `seq`,
+/// as a sequence-complexity profile, e.g. for masking low-complexity regions.
+///
+/// Use a canonical hasher to count a k-mer and its reverse complement once.
+/// Yields one value per window, and nothing when `seq` has fewer than `w` k-mers.
+/// Maintains the multiset of hashes in the current window, for `O(1)` expected work per k-mer.
+pub fn window_complexity<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>, w: usize) -> Vec<u32> {
+    assert!(w > 0, "Window size must be positive.");
+    let hashes = hasher.hash_kmers_simd(seq, 1).collect();
+
+    let mut counts = HashMap::<u32, u32>::with_capacity(w);
+    let mut out = Vec::with_capacity((hashes.len() + 1).saturating_sub(w));
+    for (i, &h) in hashes.iter().enumerate() {
+        *counts.entry(h).or_default() += 1;
+        if i >= w {
+            let old = hashes[i - w];
+            let c = counts.get_mut(&old).unwrap();
+            *c -= 1;
+            if *c == 0 {
+                counts.remove(&old);
+            }
+        }
+        if i + 1 >= w {
+            out.push(counts.len() as u32);
+        }
+    }
+    out
+}
+
+/// The minimum k-mer hash of `seq`, or `None` when it has no k-mers.
+///
+/// Reduces the SIMD hash stream directly, without materializing it.
+pub fn min_hash<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>) -> Option<u32> {
+    argmin_hash(hasher, seq).map(|(_, h)| h)
+}
+
+/// The position and hash of the leftmost k-mer of `seq` with minimal hash, or `None` when it has no k-mers.
+///
+/// Reduces the SIMD hash stream directly, without materializing it.
+pub fn argmin_hash<'s, H: KmerHasher>(hasher: &H, seq: impl Seq<'s>) -> Option<(usize, u32)> {
+    let hashes = hasher.hash_kmers_simd(seq, 1);
+    let positions = hashes.lane_positions(1);
+    // Positions are below `u32::MAX`, so this marks lanes without a valid position yet.
+    let none = S::MAX;
+    let mut best = S::MAX;
+    let mut best_pos = none;
+    for (h, (pos, valid)) in hashes.it.zip(positions) {
+        let better = valid & (h.simd_lt(best) | best_pos.simd_eq(none));
+        best = better.blend(h, best);
+        best_pos = better.blend(pos, best_pos);
+    }
+    zip(best_pos.to_array(), best.to_array())
+        .filter(|&(p, _)| p != u32::MAX)
+        .min_by_key(|&(p, h)| (h, p))
+        .map(|(p, h)| (p as usize, h))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MulHasher, NtHasher};
+    use packed_seq::{PackedSeqVec, SeqVec};
+
+    #[test]
+    fn winnow_naive() {
+        let data: Vec<u8> = (0..2000).map(|_| rand::random_range(b'a'..=b'd')).collect();
+        for (k, w) in [(1, 1), (3, 4), (5, 10), (8, 1), (31, 17)] {
+            let hasher = MulHasher::<false>::new(k);
+            let hashes: Vec<u32> = hasher.hash_kmers_scalar(data.as_slice()).collect();
+            let mut naive = vec![];
+            for i in 0..hashes.len().saturating_sub(w - 1) {
+                let window = &hashes[i..i + w];
+                let min = *window.iter().min().unwrap();
+                let pos = i + window.iter().rposition(|&h| h == min).unwrap();
+                if naive.last() != Some(&(pos, min)) {
+                    naive.push((pos, min));
+                }
+            }
+            assert_eq!(winnow(&hasher, data.as_slice(), w), naive, "k={k} w={w}");
+        }
+    }
+
+    #[test]
+    fn sliding_min_naive() {
+        let seq = PackedSeqVec::random(5000);
+        for len in [0, 1, 10, 100, 5000] {
+            let seq = seq.slice(0..len);
+            for (k, w) in [(1, 1), (5, 2), (21, 11), (31, 100)] {
+                let hasher = NtHasher::<true>::new(k);
+                let hashes: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+                let naive: Vec<u32> = hashes
+                    .windows(w)
+                    .map(|w| *w.iter().min().unwrap())
+                    .collect();
+                assert_eq!(
+                    sliding_min(&hasher, seq, w).collect(),
+                    naive,
+                    "len={len} k={k} w={w}"
+                );
+                let scalar = sliding_min_scalar(&hasher, seq, w);
+                assert_eq!(scalar.len(), naive.len());
+                assert_eq!(scalar.collect::<Vec<_>>(), naive, "len={len} k={k} w={w}");
+            }
+        }
+    }
+
+    #[test]
+    fn window_ranks_naive() {
+        let data: Vec<u8> = (0..1000).map(|_| rand::random_range(b'a'..=b'c')).collect();
+        for (k, w) in [(1, 1), (2, 4), (5, 10), (31, 17)] {
+            let hasher = MulHasher::<false>::new(k);
+            let hashes: Vec<u32> = hasher.hash_kmers_scalar(data.as_slice()).collect();
+            let naive: Vec<u32> = hashes
+                .windows(w)
+                .map(|window| {
+                    let mut order: Vec<usize> = (0..w).collect();
+                    order.sort_by_key(|&j| (window[j], j));
+                    order.iter().position(|&j| j == w - 1).unwrap() as u32
+                })
+                .collect();
+            let ranks = window_ranks(&hasher, data.as_slice(), w);
+            assert_eq!(ranks, naive, "k={k} w={w}");
+            assert!(ranks.iter().all(|&r| r < w as u32));
+        }
+        assert!(window_ranks(&MulHasher::<false>::new(5), &b"abc"[..], 2).is_empty());
+    }
+
+    #[test]
+    fn winnow_shared_substring() {
+        let (k, w) = (5, 8);
+        let shared: Vec<u8> = (0..w + k - 1).map(|_| rand::random()).collect();
+        let mut a: Vec<u8> = (0..100).map(|_| rand::random()).collect();
+        let mut b: Vec<u8> = (0..37).map(|_| rand::random()).collect();
+        a.extend_from_slice(&shared);
+        b.extend_from_slice(&shared);
+        b.extend((0..50).map(|_| rand::random::<u8>()));
+
+        let hasher = MulHasher::<false>::new(k);
+        let fa: Vec<u32> = winnow(&hasher, a.as_slice(), w)
+            .into_iter()
+            .map(|x| x.1)
+            .collect();
+        let fb = winnow(&hasher, b.as_slice(), w);
+        assert!(fb.iter().any(|(_, h)| fa.contains(h)));
+    }
+
+    #[test]
+    fn window_complexity_naive() {
+        let seq = PackedSeqVec::random(2000);
+        // A tandem repeat has low complexity.
+        let mut ascii = seq.slice(0..500).unpack();
+        ascii.extend(b"ACG".repeat(200));
+        let seq = PackedSeqVec::from_ascii(&ascii);
+        let k = 11;
+        let hasher = <NtHasher>::new(k);
+        let hashes: Vec<u32> = hasher.hash_kmers_scalar(seq.as_slice()).collect();
+        for w in [1, 5, 64, hashes.len(), hashes.len() + 1] {
+            let naive: Vec<u32> = hashes
+                .windows(w)
+                .map(|win| win.iter().collect::<std::collections::HashSet<_>>().len() as u32)
+                .collect();
+            assert_eq!(
+                window_complexity(&hasher, seq.as_slice(), w),
+                naive,
+                "w={w}"
+            );
+        }
+        let profile = window_complexity(&hasher, seq.as_slice(), 64);
+        assert!(profile[0] > 60);
+        // `ACG` repeats have 3 distinct k-mers, which are also distinct from their reverse complements.
+        assert_eq!(*profile.last().unwrap(), 3);
+    }
+
+    #[test]
+    fn argmin_naive() {
+        let seq = PackedSeqVec::random(2000);
+        for len in [0, 1, 4, 5, 17, 100, 2000] {
+            let seq = seq.slice(0..len);
+            for k in [1, 5, 31] {
+                let hasher = NtHasher::<true>::new(k);
+                let hashes: Vec<u32> = hasher.hash_kmers_scalar(seq).collect();
+                let naive = hashes
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .min_by_key(|&(i, h)| (h, i));
+                assert_eq!(argmin_hash(&hasher, seq), naive, "len={len} k={k}");
+                assert_eq!(min_hash(&hasher, seq), naive.map(|x| x.1));
+            }
+        }
+        // Ties resolve to the leftmost position.
+        let seq = PackedSeqVec::from_ascii(&[b'A'; 100]);
+        let hasher = NtHasher::<true>::new(5);
+        assert_eq!(argmin_hash(&hasher, seq.as_slice()).unwrap().0, 0);
+    }
+}
@@ -0,0 +1,145 @@
+//! Salting k-mer hashes by position window, to decorrelate hashes inside repeats.
+
+use crate::signature::mix64;
+use crate::{KmerHasher, PaddedLayout, S};
+use packed_seq::{ChunkIt, PaddedIt, Seq};
+
+/// Wraps a [`KmerHasher`] and xors the hash of the k-mer at position `pos` with a salt derived from `pos / window`.
+///
+/// Inside a long tandem repeat, the same k-mers recur with the same hashes, so e.g. minimizer schemes
+/// pick long runs of identical minimizing hashes. Salting per window decorrelates these runs,
+/// while k-mers in the same window remain comparable.
+///
+/// Hashes depend on the position, so this is not a [`KmerHasher`] itself,
+/// and hashes of the same k-mer in different sequences or windows differ.
+#[derive(Clone, Debug)]
+pub struct XorFold<H> {
+    hasher: H,
+    window: usize,
+    seed: u32,
+}
+
+impl<H: KmerHasher> XorFold<H> {
+    /// Salt the hashes of `hasher` per `window` consecutive k-mers.
+    pub fn new(hasher: H, window: usize) -> Self {
+        Self::new_with_seed(hasher, window, 0)
+    }
+
+    /// Seeded version, with different salts for each seed.
+    pub fn new_with_seed(hasher: H, window: usize, seed: u32) -> Self {
+        assert!(
+            0 < window && window <= u32::MAX as usize,
+            "Window must be in 1..2^32."
+        );
+        Self {
+            hasher,
+            window,
+            seed,
+        }
+    }
+
+    /// The wrapped hasher.
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// The number of consecutive k-mers sharing a salt.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// The salt of the k-mer at position `pos`.
+    #[inline(always)]
+    pub fn salt(&self, pos: usize) -> u32 {
+        mix64(((self.seed as u64) << 32) ^ (pos / self.window) as u64) as u32
+    }
+
+    /// A scalar iterator over the salted hashes of all k-mers in `seq`.
+    #[inline(always)]
+    pub fn hash_kmers_scalar<'s>(&self, seq: impl Seq<'s>) -> impl ExactSizeIterator<Item = u32> {
+        let mut salt = 0;
+        self.hasher
+            .hash_kmers_scalar(seq)
+            .enumerate()
+            .map(move |(pos, h)| {
+                if pos % self.window == 0 {
+                    salt = self.salt(pos);
+                }
+                h ^ salt
+            })
+    }
+
+    /// A SIMD-parallel iterator over the salted hashes of all k-mers in `seq`,
+    /// with the same lane layout as [`KmerHasher::hash_kmers_simd`].
+    #[inline(always)]
+    pub fn hash_kmers_simd<'s>(
+        &self,
+        seq: impl Seq<'s>,
+        context: usize,
+    ) -> PaddedIt<impl ChunkIt<S>> {
+        let hashes = self.hasher.hash_kmers_simd(seq, context);
+        let mut positions = hashes.lane_positions(context);
+        let start = positions
+            .clone()
+            .next()
+            .map_or([0; 8], |(pos, _)| pos.to_array().map(|p| p as usize));
+        let mut salt = S::new(start.map(|p| self.salt(p)));
+        // The number of k-mers until the next window, per lane.
+        let mut left = S::new(start.map(|p| (self.window - p % self.window) as u32));
+        hashes.map(move |h: S| {
+            let (pos, _) = positions.next().unwrap();
+            let out = h ^ salt;
+            left -= S::ONE;
+            if left.simd_eq(S::ZERO).to_bitmask() != 0 {
+                let mut l = left.to_array();
+                let mut s = salt.to_array();
+                for (j, p) in pos.to_array().into_iter().enumerate() {
+                    if l[j] == 0 {
+                        l[j] = self.window as u32;
+                        s[j] = self.salt(p as usize + 1);
+                    }
+                }
+                (left, salt) = (S::new(l), S::new(s));
+            }
+            out
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NtHasher;
+    use packed_seq::{PackedSeqVec, SeqVec};
+    use std::iter::zip;
+
+    #[test]
+    fn salted_naive() {
+        let seq = PackedSeqVec::random(5000);
+        for len in [0, 30, 100, 5000] {
+            let seq = seq.slice(0..len);
+            for window in [1, 7, 64, 1000] {
+                let fold = XorFold::new(<NtHasher>::new(21), window);
+                let naive: Vec<u32> = fold
+                    .hasher()
+                    .hash_kmers_scalar(seq)
+                    .enumerate()
+                    .map(|(pos, h)| h ^ fold.salt(pos))
+                    .collect();
+                assert_eq!(fold.hash_kmers_scalar(seq).collect::<Vec<_>>(), naive);
+                assert_eq!(fold.hash_kmers_simd(seq, 1).collect(), naive);
+                for context in [5, 16] {
+                    let hashes = fold.hash_kmers_simd(seq, context);
+                    let positions = hashes.lane_positions(context);
+                    for (h, (pos, _)) in hashes.it.zip(positions) {
+                        for (h, p) in zip(h.to_array(), pos.to_array()) {
+                            if let Some(&expected) = naive.get(p as usize) {
+                                assert_eq!(h, expected, "len={len} window={window}");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}